@@ -0,0 +1,10 @@
+-- Conversations are created implicitly by the chat endpoint, before any user is attached
+ALTER TABLE conversations ALTER COLUMN user_id DROP NOT NULL;
+
+-- Store timestamps with time zone so they map to DateTime<Utc>
+ALTER TABLE conversations
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at AT TIME ZONE 'UTC',
+    ALTER COLUMN updated_at TYPE TIMESTAMPTZ USING updated_at AT TIME ZONE 'UTC';
+
+ALTER TABLE messages
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at AT TIME ZONE 'UTC';
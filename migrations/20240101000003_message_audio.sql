@@ -0,0 +1,8 @@
+-- Original user audio for voice turns (STORE_AUDIO), keyed by message id.
+-- No foreign key: voice session messages are persisted best-effort and may land later.
+CREATE TABLE message_audio (
+    message_id UUID PRIMARY KEY,
+    path TEXT NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
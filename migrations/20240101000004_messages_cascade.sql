@@ -0,0 +1,21 @@
+-- Enforce at the database level that deleting a conversation deletes its messages.
+-- The initial schema declares this, but databases restored from older dumps may
+-- lack it; re-create the constraint under its default name either way.
+
+-- Backfill: give orphaned messages a conversation instead of dropping them
+INSERT INTO conversations (id, created_at, updated_at)
+SELECT DISTINCT m.conversation_id, NOW(), NOW()
+FROM messages m
+WHERE NOT EXISTS (SELECT 1 FROM conversations c WHERE c.id = m.conversation_id)
+ON CONFLICT (id) DO NOTHING;
+
+ALTER TABLE messages DROP CONSTRAINT IF EXISTS messages_conversation_id_fkey;
+
+-- Dropping and adding the constraint still lock `messages` (and `conversations`)
+-- until this migration commits. NOT VALID keeps that short by skipping the scan of
+-- existing rows; 20240101000009 validates them in its own transaction, which only
+-- takes a lock that lets reads and writes carry on.
+ALTER TABLE messages
+    ADD CONSTRAINT messages_conversation_id_fkey
+    FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+    NOT VALID;
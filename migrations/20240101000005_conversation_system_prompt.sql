@@ -0,0 +1,2 @@
+-- Per-conversation persona for the DB-backed chat; NULL uses Tea's default prompt
+ALTER TABLE conversations ADD COLUMN IF NOT EXISTS system_prompt TEXT;
@@ -0,0 +1,10 @@
+-- Per-tenant API keys, checked alongside API_KEY / API_KEYS. Only the SHA-256 of each
+-- key is stored; deactivate a key instead of deleting it to keep its label around.
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY,
+    key_hash CHAR(64) NOT NULL UNIQUE,
+    label TEXT NOT NULL,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
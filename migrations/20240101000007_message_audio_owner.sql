@@ -0,0 +1,4 @@
+-- The API key (as its `SessionOwner` hash) whose voice session recorded the audio.
+-- Only that key can read it back; rows recorded before this column have no owner
+-- and are no longer served.
+ALTER TABLE message_audio ADD COLUMN owner VARCHAR(16);
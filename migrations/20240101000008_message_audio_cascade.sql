@@ -0,0 +1,9 @@
+-- Stored audio belongs to its message: deleting the message (or its conversation)
+-- deletes the audio row too. The message row is now written together with the audio,
+-- so audio whose message never landed can't be read back and is dropped here.
+DELETE FROM message_audio a
+WHERE NOT EXISTS (SELECT 1 FROM messages m WHERE m.id = a.message_id);
+
+ALTER TABLE message_audio
+    ADD CONSTRAINT message_audio_message_id_fkey
+    FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE;
@@ -0,0 +1,4 @@
+-- Check existing messages against the foreign key added NOT VALID in
+-- 20240101000004. Kept apart so the scan doesn't run while that migration's
+-- locks are held; VALIDATE CONSTRAINT itself doesn't block reads or writes.
+ALTER TABLE messages VALIDATE CONSTRAINT messages_conversation_id_fkey;
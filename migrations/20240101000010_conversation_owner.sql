@@ -0,0 +1,6 @@
+-- The API key (as its `SessionOwner` hash) a conversation belongs to. Voice sessions
+-- persisted with PERSIST_VOICE_SESSIONS are stored as conversations too, so without
+-- this any key could read or extend another key's transcript through the
+-- conversation endpoints. Conversations from before this column are claimed by the
+-- first key that uses them, the same as a new voice session.
+ALTER TABLE conversations ADD COLUMN owner VARCHAR(16);
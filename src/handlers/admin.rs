@@ -0,0 +1,44 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::AppState;
+
+/// GET /api/v1/admin/sessions
+/// Live voice sessions with message counts and estimated token totals, largest first
+pub async fn list_sessions(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let sessions = state.voice_sessions.list_sessions().await;
+    let total_tokens: usize = sessions.iter().map(|session| session.estimated_tokens).sum();
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "count": sessions.len(),
+            "estimated_tokens": total_tokens,
+            "sessions": sessions,
+        })),
+    )
+}
+
+/// GET /api/v1/admin/config
+/// The configuration the process actually loaded (env, `CONFIG_FILE` and defaults),
+/// with secrets redacted
+pub async fn get_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (StatusCode::OK, Json(state.config.redacted()))
+}
+
+/// POST /api/v1/admin/flush-sessions
+/// Wipes all in-memory voice sessions without a restart (admin key required)
+pub async fn flush_sessions(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let removed = state.voice_sessions.clear_all().await;
+    warn!("Admin flush removed {} voice sessions", removed);
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "removed": removed,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })),
+    )
+}
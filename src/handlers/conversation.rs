@@ -0,0 +1,473 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::voice_session::MAX_SYSTEM_PROMPT_CHARS;
+use crate::{
+    models::ErrorResponse,
+    services::{
+        database_service::DbError,
+        llm_service::{LlmError, LlmOptions},
+        voice_session_service::SessionOwner,
+    },
+    AppState,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct SendMessageRequest {
+    pub content: String,
+    /// Client-chosen id for the user message; retrying with the same id
+    /// returns the saved reply instead of adding the turn again
+    #[serde(default)]
+    pub message_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SendMessageResponse {
+    pub conversation_id: Uuid,
+    pub user_message_id: Uuid,
+    pub assistant_message_id: Uuid,
+    pub reply: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SystemPromptRequest {
+    /// None or an empty string goes back to Tea's default persona
+    pub system_prompt: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SystemPromptResponse {
+    pub conversation_id: Uuid,
+    pub system_prompt: Option<String>,
+}
+
+/// PUT /api/v1/conversations/:id/system-prompt
+/// Give one conversation its own persona (stored with the conversation).
+/// Conversations belong to the API key that started them; other keys get 403.
+pub async fn set_conversation_system_prompt(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Path(conversation_id): Path<Uuid>,
+    Json(request): Json<SystemPromptRequest>,
+) -> Result<Json<SystemPromptResponse>, ConversationError> {
+    let system_prompt = request
+        .system_prompt
+        .map(|prompt| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty());
+    if let Some(prompt) = &system_prompt {
+        if prompt.chars().count() > MAX_SYSTEM_PROMPT_CHARS {
+            return Err(ConversationError::SystemPromptTooLong);
+        }
+    }
+
+    let db = &state.database_service;
+    db.claim_conversation(conversation_id, owner)
+        .await
+        .map_err(|e| {
+            error!("Failed to claim conversation {}: {}", conversation_id, e);
+            ConversationError::from(e)
+        })?;
+    db.set_conversation_system_prompt(conversation_id, system_prompt.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Failed to set system prompt for conversation {}: {}", conversation_id, e);
+            ConversationError::from(e)
+        })?;
+    info!("Updated system prompt for conversation {}", conversation_id);
+
+    Ok(Json(SystemPromptResponse {
+        conversation_id,
+        system_prompt,
+    }))
+}
+
+/// POST /api/v1/conversations/:id/messages
+/// Persistent text chat: history is loaded from and saved to PostgreSQL.
+/// The conversation's own system prompt, if set, replaces Tea's persona.
+/// A `message_id` makes the request safe to retry.
+/// Conversations belong to the API key that started them; other keys get 403.
+pub async fn send_conversation_message(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Path(conversation_id): Path<Uuid>,
+    Json(request): Json<SendMessageRequest>,
+) -> Result<Json<SendMessageResponse>, ConversationError> {
+    let content = request.content.trim();
+    if content.is_empty() {
+        return Err(ConversationError::EmptyMessage);
+    }
+
+    let db = &state.database_service;
+
+    db.claim_conversation(conversation_id, owner)
+        .await
+        .map_err(|e| {
+            error!("Failed to claim conversation {}: {}", conversation_id, e);
+            ConversationError::from(e)
+        })?;
+
+    let messages = db
+        .get_conversation_history(conversation_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load history for conversation {}: {}", conversation_id, e);
+            ConversationError::from(e)
+        })?;
+
+    // A retry of a turn that was already answered gets the saved reply back
+    if let Some(message_id) = request.message_id {
+        let reused = messages.iter().any(|message| {
+            message.id == message_id && (message.role != "user" || message.content != content)
+        });
+        if reused {
+            warn!("Message id {} reused for a different message", message_id);
+            return Err(ConversationError::MessageIdReused);
+        }
+        let answered = messages
+            .iter()
+            .position(|message| message.id == message_id)
+            .and_then(|at| messages[at + 1..].iter().find(|message| message.role == "assistant"));
+        if let Some(reply) = answered {
+            info!("Message {} was already answered, returning the saved reply", message_id);
+            return Ok(Json(SendMessageResponse {
+                conversation_id,
+                user_message_id: message_id,
+                assistant_message_id: reply.id,
+                reply: reply.content.clone(),
+                timestamp: reply.created_at.to_rfc3339(),
+            }));
+        }
+    }
+
+    let history: Vec<(String, String)> = messages
+        .into_iter()
+        .filter(|message| Some(message.id) != request.message_id)
+        .map(|message| (message.role, message.content))
+        .collect();
+    info!("Loaded {} messages for conversation {}", history.len(), conversation_id);
+
+    let system_prompt = db
+        .get_conversation_system_prompt(conversation_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to load system prompt for conversation {}: {}", conversation_id, e);
+            ConversationError::from(e)
+        })?;
+    let options = LlmOptions {
+        system_prompt,
+        ..Default::default()
+    };
+
+    let reply = state
+        .llm_service
+        .generate_voice_response(&history, content, &options)
+        .await
+        .map_err(|e| {
+            error!("LLM generation failed: {}", e);
+            ConversationError::from(e)
+        })?;
+
+    let user_message_id = db
+        .save_message(conversation_id, "user", content, request.message_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to save user message: {}", e);
+            ConversationError::from(e)
+        })?;
+    let assistant_message_id = db
+        .save_message(conversation_id, "assistant", &reply, None)
+        .await
+        .map_err(|e| {
+            error!("Failed to save assistant message: {}", e);
+            ConversationError::from(e)
+        })?;
+
+    Ok(Json(SendMessageResponse {
+        conversation_id,
+        user_message_id,
+        assistant_message_id,
+        reply,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+#[derive(Debug)]
+pub enum ConversationError {
+    EmptyMessage,
+    SystemPromptTooLong,
+    DatabaseFailed,
+    DatabaseUnavailable,
+    LlmFailed,
+    LlmUnavailable,
+    LlmRateLimited,
+    /// `message_id` already names a different message
+    MessageIdReused,
+    /// The conversation belongs to another API key
+    Forbidden,
+}
+
+impl From<DbError> for ConversationError {
+    fn from(e: DbError) -> Self {
+        match e {
+            DbError::Connection(_) => ConversationError::DatabaseUnavailable,
+            DbError::MessageIdConflict(_) => ConversationError::MessageIdReused,
+            DbError::ConversationForbidden(_) => ConversationError::Forbidden,
+            _ => ConversationError::DatabaseFailed,
+        }
+    }
+}
+
+impl From<LlmError> for ConversationError {
+    fn from(e: LlmError) -> Self {
+        match e {
+            LlmError::RateLimited(_) => ConversationError::LlmRateLimited,
+            e if e.is_retryable() => ConversationError::LlmUnavailable,
+            _ => ConversationError::LlmFailed,
+        }
+    }
+}
+
+impl IntoResponse for ConversationError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ConversationError::EmptyMessage => {
+                (StatusCode::BAD_REQUEST, "Message content must not be empty")
+            }
+            ConversationError::SystemPromptTooLong => {
+                (StatusCode::BAD_REQUEST, "system_prompt must be at most 4000 characters")
+            }
+            ConversationError::DatabaseFailed => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "Database operation failed")
+            }
+            ConversationError::DatabaseUnavailable => {
+                (StatusCode::SERVICE_UNAVAILABLE, "Database is temporarily unavailable, try again later")
+            }
+            ConversationError::LlmFailed => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "LLM generation failed")
+            }
+            ConversationError::LlmUnavailable => {
+                (StatusCode::SERVICE_UNAVAILABLE, "LLM is temporarily unavailable, try again later")
+            }
+            ConversationError::LlmRateLimited => {
+                (StatusCode::TOO_MANY_REQUESTS, "LLM rate limit reached, try again later")
+            }
+            ConversationError::MessageIdReused => {
+                (StatusCode::CONFLICT, "message_id was already used for a different message")
+            }
+            ConversationError::Forbidden => {
+                (StatusCode::FORBIDDEN, "Conversation belongs to another API key")
+            }
+        };
+
+        (
+            status,
+            Json(ErrorResponse::new(message.to_string(), status.as_u16())),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, MockUpstream};
+
+    #[tokio::test]
+    async fn test_empty_message_is_rejected() {
+        let state = Arc::new(test_support::test_state());
+
+        let result = send_conversation_message(
+            State(state),
+            Extension(test_support::session_owner()),
+            Path(Uuid::new_v4()),
+            Json(SendMessageRequest {
+                content: "   ".to_string(),
+                message_id: None,
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(ConversationError::EmptyMessage)));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_send_message_persists_both_turns() {
+        let upstream = MockUpstream::start("So lovely to hear from you!").await;
+        let mut state = test_support::test_state_with_upstream(&upstream);
+        state.database_service = test_support::database_service().await;
+        let state = Arc::new(state);
+        let conversation_id = Uuid::new_v4();
+
+        let Json(response) = send_conversation_message(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Path(conversation_id),
+            Json(SendMessageRequest {
+                content: "Hi Tea!".to_string(),
+                message_id: None,
+            }),
+        )
+        .await
+        .expect("chat request failed");
+
+        assert_eq!(response.reply, "So lovely to hear from you!");
+        let llm_requests = upstream.requests_to("/chat/completions");
+        assert_eq!(llm_requests.len(), 1);
+        let messages = llm_requests[0].json()["messages"].clone();
+        assert_eq!(messages.as_array().unwrap().last().unwrap()["content"], "Hi Tea!");
+
+        let history = state
+            .database_service
+            .get_conversation_history(conversation_id)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].role, "user");
+        assert_eq!(history[0].content, "Hi Tea!");
+        assert_eq!(history[1].role, "assistant");
+        assert_eq!(history[1].content, "So lovely to hear from you!");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_retried_message_id_returns_saved_reply() {
+        let upstream = MockUpstream::start("So lovely to hear from you!").await;
+        let mut state = test_support::test_state_with_upstream(&upstream);
+        state.database_service = test_support::database_service().await;
+        let state = Arc::new(state);
+        let conversation_id = Uuid::new_v4();
+        let message_id = Uuid::new_v4();
+
+        let send = || {
+            send_conversation_message(
+                State(state.clone()),
+                Extension(test_support::session_owner()),
+                Path(conversation_id),
+                Json(SendMessageRequest {
+                    content: "Hi Tea!".to_string(),
+                    message_id: Some(message_id),
+                }),
+            )
+        };
+        let Json(first) = send().await.expect("chat request failed");
+        let Json(retry) = send().await.expect("retried chat request failed");
+
+        assert_eq!(first.user_message_id, message_id);
+        assert_eq!(retry.user_message_id, message_id);
+        assert_eq!(retry.assistant_message_id, first.assistant_message_id);
+        assert_eq!(retry.reply, first.reply);
+        assert_eq!(upstream.requests_to("/chat/completions").len(), 1);
+
+        let history = state
+            .database_service
+            .get_conversation_history(conversation_id)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_conversation_system_prompt_only_applies_to_that_conversation() {
+        let upstream = MockUpstream::start("Arr, welcome aboard!").await;
+        let mut state = test_support::test_state_with_upstream(&upstream);
+        state.database_service = test_support::database_service().await;
+        let state = Arc::new(state);
+        let pirate = Uuid::new_v4();
+        let regular = Uuid::new_v4();
+
+        let Json(set) = set_conversation_system_prompt(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Path(pirate),
+            Json(SystemPromptRequest {
+                system_prompt: Some("  You are a friendly pirate. ".to_string()),
+            }),
+        )
+        .await
+        .expect("setting the prompt failed");
+        assert_eq!(set.system_prompt.as_deref(), Some("You are a friendly pirate."));
+
+        for conversation_id in [pirate, regular] {
+            let Json(response) = send_conversation_message(
+                State(state.clone()),
+                Extension(test_support::session_owner()),
+                Path(conversation_id),
+                Json(SendMessageRequest {
+                    content: "Hello!".to_string(),
+                    message_id: None,
+                }),
+            )
+            .await
+            .expect("chat request failed");
+            assert_eq!(response.reply, "Arr, welcome aboard!");
+        }
+
+        let llm_requests = upstream.requests_to("/chat/completions");
+        assert_eq!(llm_requests[0].json()["messages"][0]["content"], "You are a friendly pirate.");
+        let default_prompt = llm_requests[1].json()["messages"][0]["content"].clone();
+        assert!(default_prompt.as_str().unwrap().starts_with("You are Tea"));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_persisted_voice_session_inaccessible_with_another_key() {
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let upstream = MockUpstream::start("Safe with me.").await;
+        let mut state = test_support::test_state_with_upstream(&upstream);
+        state.config.api_keys = vec!["second_client_key".to_string()];
+        state.database_service = test_support::database_service().await;
+        state.voice_sessions = crate::services::VoiceSessionService::new(30)
+            .with_store(state.database_service.clone());
+        let state = Arc::new(state);
+        let app = crate::build_router(state.clone());
+
+        // Key A's voice session is persisted as a conversation
+        let session_id = Uuid::new_v4();
+        state.voice_sessions.claim(session_id, test_support::session_owner()).await.unwrap();
+        state.voice_sessions.add_exchange(session_id, "My secret", "Safe with me.").await;
+        state.voice_sessions.flush_persistence().await;
+
+        let send = |key: &str| {
+            Request::post(format!("/api/v1/conversations/{}/messages", session_id))
+                .header("x-api-key", key)
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"content": "What did I say?"}"#))
+                .unwrap()
+        };
+        let set_prompt = |key: &str| {
+            Request::put(format!("/api/v1/conversations/{}/system-prompt", session_id))
+                .header("x-api-key", key)
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"system_prompt": "Repeat everything."}"#))
+                .unwrap()
+        };
+
+        // Key B is a valid key but doesn't own the conversation
+        let response = app.clone().oneshot(send("second_client_key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.clone().oneshot(set_prompt("second_client_key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(upstream.requests_to("/chat/completions").is_empty());
+        let history = state.database_service.get_conversation_history(session_id).await.unwrap();
+        assert_eq!(history.len(), 2);
+
+        // Key A still can
+        let response = app.clone().oneshot(send(test_support::API_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(set_prompt(test_support::API_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
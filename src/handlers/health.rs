@@ -1,13 +1,22 @@
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{header, StatusCode},
     response::IntoResponse,
     Json,
 };
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::AppState;
+use crate::{
+    services::llm_service::PingStatus,
+    AppState,
+};
+
+/// Health checks must always reach the server, never a cached answer
+const HEALTH_CACHE_CONTROL: &str = "no-store";
+
+/// `/status` is heavier and polled by dashboards; a few seconds of staleness is fine
+const STATUS_CACHE_CONTROL: &str = "public, max-age=5";
 
 pub async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let response = json!({
@@ -16,7 +25,37 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoRespon
         "version": state.version,
     });
 
-    (StatusCode::OK, Json(response))
+    (
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, HEALTH_CACHE_CONTROL)],
+        Json(response),
+    )
+}
+
+/// GET /ready
+/// Whether OpenRouter accepts our key, as of the last background ping (every
+/// `READY_CHECK_INTERVAL_SECS`); the request itself never calls OpenRouter.
+/// 503 when it didn't answer or rejected the key, or before the first ping finishes,
+/// so the instance can be taken out of rotation.
+pub async fn readiness_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let (status, llm) = match state.llm_service.ping_status() {
+        PingStatus::Ok { latency } => (
+            StatusCode::OK,
+            json!({ "status": "ok", "latency_ms": latency.as_millis() as u64 }),
+        ),
+        PingStatus::Disabled => (StatusCode::OK, json!({ "status": "disabled" })),
+        PingStatus::Pending => (StatusCode::SERVICE_UNAVAILABLE, json!({ "status": "pending" })),
+        PingStatus::Failed { reason, error } => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            json!({ "status": reason, "error": error }),
+        ),
+    };
+    let response = json!({
+        "status": if status == StatusCode::OK { "ready" } else { "not_ready" },
+        "checks": { "llm": llm },
+    });
+
+    (status, [(header::CACHE_CONTROL, HEALTH_CACHE_CONTROL)], Json(response))
 }
 
 pub async fn server_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
@@ -28,10 +67,152 @@ pub async fn server_status(State(state): State<Arc<AppState>>) -> impl IntoRespo
         "endpoints": {
             "health": "/health",
             "status": "/status",
+            "ready": "/ready",
+            "version": "/version",
+            "metrics": "/metrics",
+            "models": "GET /api/v1/models",
+            "warmup": "POST /api/v1/warmup",
             "transcribe_batch": "POST /api/v1/transcriptions",
+            "transcription_batch": "POST /api/v1/transcriptions/batch",
+            "transcription_job": "GET /api/v1/transcriptions/:id",
+            "audio_probe": "POST /api/v1/audio/probe",
             "transcribe_stream": "WebSocket /api/v1/transcribe/stream",
+            "transcribe_sse": "GET /api/v1/transcribe/sse",
+            "voice_chat_stream": "WebSocket /voice-chat/stream",
+            "conversation_messages": "POST /api/v1/conversations/:id/messages",
+            "conversation_system_prompt": "PUT /api/v1/conversations/:id/system-prompt",
+            "llm_batch": "POST /api/v1/llm/batch",
+            "regenerate_reply": "POST /api/v1/voice-sessions/:id/regenerate",
+            "session_settings": "PATCH /api/v1/voice-sessions/:id/settings",
+            "session_history": "GET /api/v1/voice-sessions/:id/history",
+            "cancel_turn": "POST /api/v1/voice-sessions/:id/cancel",
+            "stats": "GET /api/v1/stats",
+            "message_audio": "GET /api/v1/messages/:id/audio",
+        },
+        "circuits": {
+            "llm": state.llm_service.circuit_state(),
+            "tts": state.elevenlabs_service.circuit_state(),
+        },
+        "llm": state.llm_service.metadata(),
+        "spilled_uploads": state.upload_spool.spilled_count(),
+        "session_persistence": {
+            "queued": state.voice_sessions.persist_queue_len(),
+            "failures": state.voice_sessions.persist_failure_count(),
+            "dead_letters": state.voice_sessions.dead_letter_count().await,
+            "dead_letters_dropped": state.voice_sessions.dead_letter_drop_count(),
         }
     });
 
+    (
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, STATUS_CACHE_CONTROL)],
+        Json(response),
+    )
+}
+
+/// GET /metrics
+/// Per-stage latency histograms (transcription, LLM, TTS) in the Prometheus text format
+pub async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/plain; version=0.0.4"),
+            (header::CACHE_CONTROL, HEALTH_CACHE_CONTROL),
+        ],
+        state.latency.render(),
+    )
+}
+
+pub async fn version_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let response = json!({
+        "version": state.version,
+        "git_sha": state.git_sha,
+        "build_timestamp": state.build_timestamp,
+    });
+
     (StatusCode::OK, Json(response))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[tokio::test]
+    async fn test_status_reports_circuit_states() {
+        let state = Arc::new(test_support::test_state());
+
+        let response = server_status(State(state)).await.into_response();
+        let body = test_support::body_json(response).await;
+
+        assert_eq!(body["circuits"]["llm"], "closed");
+        assert_eq!(body["circuits"]["tts"], "closed");
+        assert_eq!(body["llm"]["active_model"], "test-model");
+    }
+
+    #[tokio::test]
+    async fn test_health_not_cached_and_status_briefly_cached() {
+        let state = Arc::new(test_support::test_state());
+
+        let first = health_check(State(state.clone())).await.into_response();
+        assert_eq!(first.headers()[header::CACHE_CONTROL], "no-store");
+        let first = test_support::body_json(first).await;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let second = health_check(State(state.clone())).await.into_response();
+        let second = test_support::body_json(second).await;
+        assert_ne!(first["timestamp"], second["timestamp"]);
+
+        let response = server_status(State(state)).await.into_response();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=5");
+    }
+
+    #[tokio::test]
+    async fn test_ready_when_openrouter_answers() {
+        let upstream = test_support::MockUpstream::start("pong").await;
+        let state = Arc::new(test_support::test_state_with_upstream(&upstream));
+
+        // Not ready until the first ping has finished
+        let response = readiness_check(State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(test_support::body_json(response).await["checks"]["llm"]["status"], "pending");
+
+        state.llm_service.refresh_ping_status().await;
+        for _ in 0..3 {
+            let response = readiness_check(State(state.clone())).await.into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = test_support::body_json(response).await;
+            assert_eq!(body["status"], "ready");
+            assert_eq!(body["checks"]["llm"]["status"], "ok");
+        }
+        // Only the background ping reached OpenRouter
+        assert_eq!(upstream.requests_to("/chat/completions").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_when_openrouter_unreachable() {
+        // test_state points the LLM client at a closed port
+        let state = Arc::new(test_support::test_state());
+        state.llm_service.refresh_ping_status().await;
+
+        let response = readiness_check(State(state)).await.into_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = test_support::body_json(response).await;
+        assert_eq!(body["status"], "not_ready");
+        assert_eq!(body["checks"]["llm"]["status"], "unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_version_info_reports_build_metadata() {
+        let state = Arc::new(test_support::test_state());
+
+        let response = version_info(State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = test_support::body_json(response).await;
+        for field in ["version", "git_sha", "build_timestamp"] {
+            let value = body[field].as_str().expect("field should be a string");
+            assert!(!value.is_empty(), "{} should not be empty", field);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use super::voice_session::MAX_SYSTEM_PROMPT_CHARS;
+use crate::{models::ErrorResponse, services::llm_service::LlmOptions, AppState};
+
+/// Most messages accepted by one `/api/v1/llm/batch` request
+const MAX_LLM_BATCH_MESSAGES: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct LlmBatchRequest {
+    /// User messages, each answered on its own with no history
+    pub messages: Vec<String>,
+    /// Replaces Tea's persona for every message of the batch
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+}
+
+/// Outcome for one message of a batch: `reply` on success, `error` otherwise
+#[derive(Debug, Serialize)]
+pub struct LlmBatchResult {
+    /// Position of the message in the request, counting from 0
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// POST /api/v1/llm/batch
+/// Offline prompt evaluation: every message goes to the LLM as a fresh one-turn chat,
+/// up to `LLM_BATCH_CONCURRENCY` at a time, and `{"results": [...]}` comes back in
+/// request order. A message that fails gets an `error` without failing the rest.
+pub async fn llm_batch(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<LlmBatchRequest>,
+) -> Result<Json<serde_json::Value>, LlmBatchError> {
+    if request.messages.is_empty() {
+        return Err(LlmBatchError::NoMessages);
+    }
+    if request.messages.len() > MAX_LLM_BATCH_MESSAGES {
+        return Err(LlmBatchError::TooManyMessages);
+    }
+    let system_prompt = request
+        .system_prompt
+        .map(|prompt| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty());
+    if let Some(prompt) = &system_prompt {
+        if prompt.chars().count() > MAX_SYSTEM_PROMPT_CHARS {
+            return Err(LlmBatchError::SystemPromptTooLong);
+        }
+    }
+
+    info!("Running LLM batch of {} messages", request.messages.len());
+    let options = LlmOptions {
+        system_prompt,
+        ..Default::default()
+    };
+    let concurrency = state.config.llm_batch_concurrency.max(1);
+    let messages = request.messages.into_iter().enumerate();
+    let results: Vec<LlmBatchResult> = futures::stream::iter(messages)
+        .map(|(index, message)| {
+            let state = state.clone();
+            let options = options.clone();
+            async move { answer_batch_message(&state, index, &message, &options).await }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    Ok(Json(serde_json::json!({ "results": results })))
+}
+
+async fn answer_batch_message(
+    state: &AppState,
+    index: usize,
+    message: &str,
+    options: &LlmOptions,
+) -> LlmBatchResult {
+    let mut result = LlmBatchResult {
+        index,
+        reply: None,
+        error: None,
+    };
+    let message = message.trim();
+    if message.is_empty() {
+        result.error = Some("Message must not be empty".to_string());
+        return result;
+    }
+
+    match state.llm_service.generate_voice_response(&[], message, options).await {
+        Ok(reply) => result.reply = Some(reply),
+        Err(e) => {
+            warn!("LLM batch message {} failed: {}", index, e);
+            result.error = Some(format!("LLM generation failed: {}", e));
+        }
+    }
+    result
+}
+
+#[derive(Debug)]
+pub enum LlmBatchError {
+    NoMessages,
+    TooManyMessages,
+    SystemPromptTooLong,
+}
+
+impl IntoResponse for LlmBatchError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            LlmBatchError::NoMessages => {
+                (StatusCode::BAD_REQUEST, "messages must not be empty".to_string())
+            }
+            LlmBatchError::TooManyMessages => (
+                StatusCode::BAD_REQUEST,
+                format!("At most {} messages per batch", MAX_LLM_BATCH_MESSAGES),
+            ),
+            LlmBatchError::SystemPromptTooLong => (
+                StatusCode::BAD_REQUEST,
+                format!("system_prompt must be at most {} characters", MAX_SYSTEM_PROMPT_CHARS),
+            ),
+        };
+
+        (
+            status,
+            Json(ErrorResponse::new(message, status.as_u16())),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support;
+    use axum::{body::Body, http::Request, routing::post, Json, Router};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    /// OpenRouter stand-in that echoes each user message back, answering earlier
+    /// messages more slowly so replies finish out of order
+    async fn echo_llm(system_prompts: Arc<Mutex<Vec<String>>>) -> String {
+        let app = Router::new().route(
+            "/chat/completions",
+            post(move |Json(body): Json<serde_json::Value>| {
+                let system_prompts = system_prompts.clone();
+                async move {
+                    let messages = body["messages"].as_array().unwrap();
+                    let system = messages[0]["content"].as_str().unwrap().to_string();
+                    system_prompts.lock().unwrap().push(system);
+                    let user = messages.last().unwrap()["content"].as_str().unwrap().to_string();
+                    let n: u64 = user.trim_start_matches("message ").parse().unwrap();
+                    tokio::time::sleep(Duration::from_millis((5 - n) * 40)).await;
+                    Json(test_support::chat_completion(&format!("reply to {}", user), "stop"))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_batch_replies_keep_request_order() {
+        let system_prompts = Arc::new(Mutex::new(Vec::new()));
+        let llm_url = echo_llm(system_prompts.clone()).await;
+        let mut state = test_support::test_state();
+        state.config.llm_batch_concurrency = 4;
+        state.llm_service = Arc::new(
+            crate::services::LlmService::new("sk-or-v1-test", &llm_url, "test-model").unwrap(),
+        );
+        let app = crate::build_router(Arc::new(state));
+
+        let body = serde_json::json!({
+            "messages": ["message 1", "message 2", " ", "message 3", "message 4"],
+            "system_prompt": "You are a terse tea critic.",
+        });
+        let request = Request::post("/api/v1/llm/batch")
+            .header("x-api-key", test_support::API_KEY)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let body = test_support::body_json(response).await;
+        let results = body["results"].as_array().unwrap();
+        let indexes: Vec<u64> = results.iter().map(|r| r["index"].as_u64().unwrap()).collect();
+        assert_eq!(indexes, [0, 1, 2, 3, 4]);
+        assert_eq!(results[0]["reply"], "reply to message 1");
+        assert_eq!(results[1]["reply"], "reply to message 2");
+        assert_eq!(results[2]["error"], "Message must not be empty");
+        assert_eq!(results[3]["reply"], "reply to message 3");
+        assert_eq!(results[4]["reply"], "reply to message 4");
+
+        let system_prompts = system_prompts.lock().unwrap();
+        assert_eq!(system_prompts.len(), 4);
+        assert!(system_prompts.iter().all(|p| p.starts_with("You are a terse tea critic.")));
+    }
+
+    #[tokio::test]
+    async fn test_too_many_messages_rejected_with_limit() {
+        let app = crate::build_router(Arc::new(test_support::test_state()));
+        let messages = vec!["hi"; super::MAX_LLM_BATCH_MESSAGES + 1];
+        let request = Request::post("/api/v1/llm/batch")
+            .header("x-api-key", test_support::API_KEY)
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "messages": messages }).to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+        let body = test_support::body_json(response).await;
+        assert_eq!(
+            body["error"],
+            format!("At most {} messages per batch", super::MAX_LLM_BATCH_MESSAGES)
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use axum::{
+    body::Body,
+    extract::{Extension, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+use tokio_util::io::ReaderStream;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    models::ErrorResponse,
+    services::{database_service::DbError, voice_session_service::SessionOwner},
+    AppState,
+};
+
+/// GET /api/v1/messages/:id/audio
+/// Streams back the original audio of a voice turn (only recorded with `STORE_AUDIO`).
+/// Only the API key whose voice session recorded it can fetch it; others get 404.
+pub async fn get_message_audio(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Path(message_id): Path<Uuid>,
+) -> Response {
+    let Some(store) = &state.audio_store else {
+        return not_found("Audio storage is disabled".to_string());
+    };
+
+    let (path, content_type) = match state.database_service.get_message_audio(message_id, owner).await {
+        Ok(stored) => stored,
+        Err(DbError::NotFound) => {
+            return not_found(format!("No stored audio for message {}", message_id))
+        }
+        Err(e) => {
+            error!("Failed to look up audio for message {}: {}", message_id, e);
+            let status = match e {
+                DbError::Connection(_) => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            return (
+                status,
+                Json(ErrorResponse::new("Failed to load stored audio".to_string(), status.as_u16())),
+            )
+                .into_response();
+        }
+    };
+
+    match store.open(std::path::Path::new(&path)).await {
+        Ok(file) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, content_type)],
+            Body::from_stream(ReaderStream::new(file)),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("{}", e);
+            not_found(format!("Stored audio for message {} is missing", message_id))
+        }
+    }
+}
+
+fn not_found(message: String) -> Response {
+    (StatusCode::NOT_FOUND, Json(ErrorResponse::new(message, 404))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        services::{voice_session_service::Turn, AudioStore},
+        test_support,
+    };
+
+    fn owner() -> Extension<SessionOwner> {
+        Extension(test_support::session_owner())
+    }
+
+    #[tokio::test]
+    async fn test_disabled_storage_returns_404() {
+        let state = Arc::new(test_support::test_state());
+
+        let response = get_message_audio(State(state), owner(), Path(Uuid::new_v4())).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_stored_audio_fetched_by_message_id() {
+        let mut state = test_support::test_state();
+        state.database_service = test_support::database_service().await;
+        let store = AudioStore::new(std::env::temp_dir().join(format!("rusty-tea-audio-{}", Uuid::new_v4())));
+        state.audio_store = Some(store.clone());
+        let state = Arc::new(state);
+
+        let session_id = Uuid::new_v4();
+        let turn = Turn::new("user", "two earl grey please");
+        let message_id = turn.id;
+        let audio = b"OggS\x00\x02fake-opus-page".to_vec();
+        let (path, content_type) = store.save(message_id, &audio).await.unwrap();
+        state
+            .database_service
+            .save_message_audio(
+                session_id,
+                &turn,
+                test_support::session_owner(),
+                &path.to_string_lossy(),
+                content_type,
+            )
+            .await
+            .unwrap();
+
+        let response = get_message_audio(State(state.clone()), owner(), Path(message_id)).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "audio/ogg");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], &audio[..]);
+
+        let response = get_message_audio(State(state.clone()), owner(), Path(Uuid::new_v4())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Another tenant can't fetch it by guessing the message id
+        let other = Extension(SessionOwner::from_api_key("another-tenant"));
+        let response = get_message_audio(State(state), other, Path(message_id)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_deleting_conversation_deletes_its_audio_rows() {
+        let db = test_support::database_service().await;
+        let session_id = Uuid::new_v4();
+        let turn = Turn::new("user", "hello tea");
+        db.save_message_audio(session_id, &turn, test_support::session_owner(), "/tmp/a.wav", "audio/wav")
+            .await
+            .unwrap();
+        // The message row was written alongside the audio
+        assert_eq!(db.get_conversation_history(session_id).await.unwrap().len(), 1);
+
+        sqlx::query("DELETE FROM conversations WHERE id = $1")
+            .bind(session_id)
+            .execute(db.pool())
+            .await
+            .unwrap();
+
+        let err = db
+            .get_message_audio(turn.id, test_support::session_owner())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbError::NotFound));
+    }
+}
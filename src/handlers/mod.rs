@@ -1,7 +1,25 @@
+pub mod admin;
+pub mod conversation;
 pub mod health;
+pub mod llm_batch;
+pub mod message_audio;
+pub mod models;
+pub mod stats;
 pub mod transcription;
 pub mod voice_chat;
+pub mod voice_session;
+pub mod voice_stream;
+pub mod warmup;
 
+pub use admin::*;
+pub use conversation::*;
 pub use health::*;
+pub use llm_batch::*;
+pub use message_audio::*;
+pub use models::*;
+pub use stats::*;
 pub use transcription::*;
 pub use voice_chat::*;
+pub use voice_session::*;
+pub use voice_stream::*;
+pub use warmup::*;
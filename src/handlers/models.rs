@@ -0,0 +1,75 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::AppState;
+
+/// GET /api/v1/models
+/// Languages that can be transcribed and the Vosk model serving each. Only the model's
+/// directory name is returned, never its path on disk.
+pub async fn list_models(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let models: Vec<_> = state
+        .config
+        .language_models()
+        .into_iter()
+        .map(|(language, path)| {
+            let name = Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            json!({
+                "language": language,
+                "model": name,
+                "loaded": state.stt_service.is_model_loaded(&path),
+            })
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "default_language": state.config.default_language,
+            "models": models,
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support;
+    use axum::body::Body;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_configured_languages_listed_without_paths() {
+        let mut state = test_support::test_state();
+        state.config.default_language = "en".to_string();
+        state.config.vosk_model_path = "/models/vosk-model-small-en-us-0.15".to_string();
+        state.config.vosk_models = vec![("es".to_string(), "/models/vosk-model-small-es-0.42".to_string())];
+        let app = crate::build_router(Arc::new(state));
+
+        // Public: no API key needed
+        let request = axum::http::Request::builder()
+            .uri("/api/v1/models")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let body = test_support::body_json(response).await;
+        assert_eq!(body["default_language"], "en");
+        let models = body["models"].as_array().unwrap();
+        let languages: Vec<&str> = models.iter().map(|m| m["language"].as_str().unwrap()).collect();
+        assert_eq!(languages, ["en", "es"]);
+        assert_eq!(models[1]["model"], "vosk-model-small-es-0.42");
+        assert_eq!(models[1]["loaded"], false);
+        assert!(!body.to_string().contains("/models/"));
+    }
+}
@@ -0,0 +1,89 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::{
+    models::ErrorResponse,
+    services::{
+        database_service::{DbError, Stats},
+        tts_quota::TtsUsage,
+    },
+    AppState,
+};
+
+#[derive(Debug, Serialize)]
+struct StatsResponse {
+    #[serde(flatten)]
+    stats: Stats,
+    /// ElevenLabs characters per voice this month, against `ELEVENLABS_MONTHLY_CHAR_QUOTA`
+    tts_characters: TtsUsage,
+}
+
+/// GET /api/v1/stats
+/// Conversation and message totals for the internal dashboard, plus this month's
+/// text-to-speech characters per voice
+pub async fn get_stats(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.database_service.get_stats().await {
+        Ok(stats) => {
+            let tts_characters = state.elevenlabs_service.character_usage();
+            (StatusCode::OK, Json(StatsResponse { stats, tts_characters })).into_response()
+        }
+        Err(e) => {
+            error!("Failed to load stats: {}", e);
+            let status = match e {
+                DbError::Connection(_) => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (
+                status,
+                Json(ErrorResponse::new("Failed to load stats".to_string(), status.as_u16())),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_stats_count_inserted_rows() {
+        let mut state = test_support::test_state();
+        state.database_service = test_support::database_service().await;
+        let state = Arc::new(state);
+        let db = &state.database_service;
+
+        let before = db.get_stats().await.unwrap();
+
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        db.ensure_conversation_exists(first).await.unwrap();
+        db.ensure_conversation_exists(second).await.unwrap();
+        db.save_message(first, "user", "Hi", None).await.unwrap();
+        db.save_message(first, "assistant", "Hello!", None).await.unwrap();
+        db.save_message_at(Uuid::new_v4(), second, "user", "Old news", Utc::now() - Duration::days(2))
+            .await
+            .unwrap();
+
+        let response = get_stats(State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let after = test_support::body_json(response).await;
+
+        // Other DB tests may insert concurrently, so compare against our own rows
+        let delta = |field: &str, before: i64| after[field].as_i64().unwrap() - before;
+        assert!(delta("conversations", before.conversations) >= 2);
+        assert!(delta("messages", before.messages) >= 3);
+        assert!(delta("messages_last_24h", before.messages_last_24h) >= 2);
+        assert_eq!(
+            delta("messages", before.messages) - delta("messages_last_24h", before.messages_last_24h),
+            1,
+            "the back-dated message must not count as recent"
+        );
+    }
+}
@@ -1,123 +1,2165 @@
 use axum::{
-    extract::{ws::WebSocketUpgrade, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Body,
+    extract::{
+        ws::{close_code, CloseFrame, WebSocketUpgrade},
+        Extension, Multipart, Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-use futures::{SinkExt, StreamExt};
+use futures::{channel::mpsc, stream::SplitSink, SinkExt, StreamExt};
+use serde::Deserialize;
 use std::sync::Arc;
-use tracing::{error, info};
+use tokio::io::AsyncReadExt;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 use crate::{
-    models::{ErrorResponse, StreamingMessage},
+    middleware::ws_key_protocol,
+    models::{
+        BatchFileResult, BatchTranscriptionParams, ErrorResponse, StreamingMessage,
+        TranscriptionRequest, TranscriptionResponse,
+    },
+    services::{
+        audio_decode::{canonical_mime, check_wav_header, AudioContainer, HeaderCheck},
+        circuit_breaker::CircuitOpen,
+        database_service::DbError,
+        elevenlabs_service::TtsQueueTimeout,
+        endpointing::Endpointer,
+        language_detector::LanguageDetector,
+        latency_metrics::Stage,
+        model_pool::{ModelLoading, MODEL_LOADING_RETRY_AFTER_SECS},
+        partial_throttle::PartialThrottle,
+        stream_resume::StreamProgress,
+        text_normalizer,
+        transcript_cache::AudioHasher,
+        upload_spool::{SpilledUpload, UploadSpool},
+        voice_session_service::SessionOwner,
+        vosk_service::{QueueTimeout, Transcript},
+    },
     AppState,
 };
 
+/// POST /api/v1/transcriptions
+/// With `?speak=true` the transcription is synthesized and returned as MP3 audio.
+/// With `?callback_url=...` the job runs in the background: 202 is returned
+/// immediately and the job (status plus `result` or `error`) is POSTed to the callback
+/// when done. Callback hosts must resolve to public addresses (see
+/// `ALLOW_PRIVATE_CALLBACKS`), and only the same API key can poll the job.
+/// With `?normalize=true` number words become digits and sentences are capitalized.
+/// Uploads whose type isn't in `ALLOWED_AUDIO_TYPES` get 415 before any decoding.
+/// The body is read as a stream: the type and WAV header are checked as soon as
+/// enough bytes arrive, so a bad upload is rejected without waiting for the rest.
+/// `Accept: text/plain` returns the bare transcription instead of the JSON object.
+/// With `?debug=true` the JSON also carries the recognizer's full result under `raw`.
+/// The JSON names the `language` asked for, or else the one detected from the text.
 pub async fn transcribe_batch(
     State(state): State<Arc<AppState>>,
-    body: axum::body::Bytes,
+    Extension(owner): Extension<SessionOwner>,
+    Query(params): Query<TranscriptionRequest>,
+    headers: HeaderMap,
+    body: Body,
 ) -> impl IntoResponse {
-    if body.is_empty() {
+    let upload = match receive_upload(&state, &headers, body).await {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+
+    if let Some(callback_url) = params.callback_url {
+        return start_transcription_job(
+            state,
+            owner,
+            callback_url,
+            params.language,
+            params.normalize,
+            upload,
+        )
+        .await;
+    }
+
+    match transcribe_upload(&state, upload.data, &upload.digest).await {
+        Ok(transcript) => {
+            let confidence = transcript.confidence();
+            let text = postprocess(transcript.text, params.normalize);
+            info!("Transcription completed: {} chars", text.len());
+            if params.speak {
+                return speak_transcription(&state, &text).await;
+            }
+            if wants_plain_text(&headers) {
+                return (
+                    StatusCode::OK,
+                    [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+                    text,
+                )
+                    .into_response();
+            }
+            let language = chosen_language(&state, &text, params.language);
+            let mut response = serde_json::json!({ "text": text, "language": language });
+            if let Some(confidence) = confidence {
+                response["confidence"] = serde_json::json!(confidence);
+            }
+            if params.debug {
+                response["raw"] = transcript.raw.unwrap_or(serde_json::Value::Null);
+            }
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => transcription_error(e),
+    }
+}
+
+/// Error response for a failed upload transcription
+fn transcription_error(e: anyhow::Error) -> Response {
+    if e.downcast_ref::<QueueTimeout>().is_some() {
         return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse::new(
-                "No audio data provided".to_string(),
-                400,
-            )),
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(e.to_string(), 503)),
+        )
+            .into_response();
+    }
+    if e.downcast_ref::<ModelLoading>().is_some() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, MODEL_LOADING_RETRY_AFTER_SECS.to_string())],
+            Json(ErrorResponse::new(e.to_string(), 503)),
         )
             .into_response();
     }
+    error!("Transcription error: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new(format!("Transcription failed: {}", e), 500)),
+    )
+        .into_response()
+}
 
-    match state.vosk_service.transcribe(body.to_vec()).await {
-        Ok(text) => {
-            info!("Transcription completed: {} chars", text.len());
-            (StatusCode::OK, Json(serde_json::json!({ "text": text }))).into_response()
+/// Language hypotheses listed by `/api/v1/audio/probe`
+const PROBE_CANDIDATES: usize = 3;
+
+/// POST /api/v1/audio/probe
+/// Checks an upload like `/api/v1/transcriptions` and reports what it is: container,
+/// WAV duration, transcript, and the language auto-detect would pick with its top
+/// `(language, score)` candidates among the configured languages, best first.
+pub async fn probe_audio(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    let upload = match receive_upload(&state, &headers, body).await {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+    let container = AudioContainer::detect(&upload.head);
+    let duration = wav_duration_secs(&upload.head);
+
+    let transcript = match transcribe_upload(&state, upload.data, &upload.digest).await {
+        Ok(transcript) => transcript,
+        Err(e) => return transcription_error(e),
+    };
+    let mut candidates = language_detector(&state).rank(&transcript.text);
+    candidates.truncate(PROBE_CANDIDATES);
+    let language = chosen_language(&state, &transcript.text, None);
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "content_type": container.content_type(),
+            "duration": duration,
+            "text": transcript.text,
+            "language": language,
+            "language_candidates": candidates,
+        })),
+    )
+        .into_response()
+}
+
+/// Most files accepted by one `/api/v1/transcriptions/batch` request
+const MAX_BATCH_FILES: usize = 32;
+
+/// One audio file of a multi-file batch, as uploaded
+struct BatchFile {
+    filename: Option<String>,
+    content_type: Option<String>,
+    audio: bytes::Bytes,
+}
+
+/// POST /api/v1/transcriptions/batch
+/// Multipart upload of several audio files (any field names), transcribed up to
+/// `BATCH_TRANSCRIPTION_CONCURRENCY` at a time. Replies with `{"results": [...]}` in
+/// upload order once all are done, or with `?stream=true` sends an NDJSON line per file
+/// as soon as it finishes. Each result carries the file's `index` in the upload; a file
+/// that can't be transcribed gets an `error` without failing the rest of the batch.
+pub async fn transcribe_multi(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<BatchTranscriptionParams>,
+    mut multipart: Multipart,
+) -> Response {
+    let mut files = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return upload_error(e.status(), format!("Failed to read upload: {}", e)),
+        };
+        if files.len() == MAX_BATCH_FILES {
+            return upload_error(
+                StatusCode::BAD_REQUEST,
+                format!("At most {} files per batch", MAX_BATCH_FILES),
+            );
+        }
+        let filename = field.file_name().map(str::to_string);
+        let content_type = field.content_type().map(str::to_string);
+        match field.bytes().await {
+            Ok(audio) => files.push(BatchFile {
+                filename,
+                content_type,
+                audio,
+            }),
+            Err(e) => return upload_error(e.status(), format!("Failed to read upload: {}", e)),
+        }
+    }
+    if files.is_empty() {
+        return upload_error(StatusCode::BAD_REQUEST, "No audio files provided".to_string());
+    }
+
+    info!("Transcribing batch of {} files", files.len());
+    let concurrency = state.config.batch_transcription_concurrency.max(1);
+    let normalize = params.normalize;
+    let results = futures::stream::iter(files.into_iter().enumerate())
+        .map(move |(index, file)| {
+            let state = state.clone();
+            async move { transcribe_batch_file(&state, index, file, normalize).await }
+        })
+        .buffer_unordered(concurrency);
+
+    if params.stream {
+        let lines = results.map(|result| {
+            let mut line = serde_json::to_vec(&result).unwrap_or_default();
+            line.push(b'\n');
+            Ok::<_, std::convert::Infallible>(line)
+        });
+        return (
+            [(header::CONTENT_TYPE, "application/x-ndjson")],
+            Body::from_stream(lines),
+        )
+            .into_response();
+    }
+
+    let mut results: Vec<BatchFileResult> = results.collect().await;
+    results.sort_by_key(|result| result.index);
+    (StatusCode::OK, Json(serde_json::json!({ "results": results }))).into_response()
+}
+
+/// Check and transcribe one file of a multi-file batch
+async fn transcribe_batch_file(
+    state: &AppState,
+    index: usize,
+    file: BatchFile,
+    normalize: bool,
+) -> BatchFileResult {
+    let mut result = BatchFileResult {
+        index,
+        filename: file.filename,
+        text: None,
+        confidence: None,
+        error: None,
+    };
+
+    let mut headers = HeaderMap::new();
+    if let Some(content_type) = file.content_type.and_then(|value| value.parse().ok()) {
+        headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    let checked = if file.audio.is_empty() {
+        Err("No audio data provided".to_string())
+    } else {
+        check_upload_start(state, &headers, &file.audio, true).map_err(|(_, message)| message)
+    };
+    if let Err(message) = checked {
+        warn!("Batch file {} rejected: {}", index, message);
+        result.error = Some(message);
+        return result;
+    }
+
+    match state.latency.time(Stage::Transcription, state.stt_service.transcribe(file.audio.to_vec())).await {
+        Ok(transcript) => {
+            result.confidence = transcript.confidence();
+            result.text = Some(postprocess(transcript.text, normalize));
+        }
+        Err(e) => {
+            error!("Batch file {} transcription error: {}", index, e);
+            result.error = Some(format!("Transcription failed: {}", e));
+        }
+    }
+    result
+}
+
+/// Bytes of an upload kept while waiting for a complete WAV header; a header that
+/// doesn't fit is treated as invalid
+const HEADER_PROBE_BYTES: usize = 64 * 1024;
+
+/// Bytes needed to sniff the container
+const SNIFF_BYTES: usize = 12;
+
+/// Where a received upload's bytes ended up
+#[derive(Debug)]
+enum UploadData {
+    Memory(Vec<u8>),
+    /// Went over `TRANSCRIBE_SPILL_BYTES` while arriving
+    Spilled(SpilledUpload),
+}
+
+impl UploadData {
+    async fn append(
+        &mut self,
+        chunk: &[u8],
+        spool: &UploadSpool,
+        spill_bytes: usize,
+    ) -> anyhow::Result<()> {
+        match self {
+            UploadData::Memory(buffer) if buffer.len() + chunk.len() > spill_bytes => {
+                info!("Upload over {} bytes, spilling to disk", spill_bytes);
+                let mut spilled = spool.create().await?;
+                spilled.append(buffer).await?;
+                spilled.append(chunk).await?;
+                *self = UploadData::Spilled(spilled);
+            }
+            UploadData::Memory(buffer) => buffer.extend_from_slice(chunk),
+            UploadData::Spilled(spilled) => spilled.append(chunk).await?,
+        }
+        Ok(())
+    }
+}
+
+/// An upload that passed the type and header checks
+#[derive(Debug)]
+struct ReceivedUpload {
+    data: UploadData,
+    /// The first bytes, for reading the WAV duration
+    head: Vec<u8>,
+    /// SHA-256 of the whole upload, the `TranscriptCache` key
+    digest: String,
+}
+
+fn upload_error(status: StatusCode, message: String) -> Response {
+    (status, Json(ErrorResponse::new(message, status.as_u16()))).into_response()
+}
+
+/// Read the request body chunk by chunk, enforcing `MAX_TRANSCRIBE_BYTES` and checking
+/// the start of the upload once enough of it is in. Chunks go to memory, or to a temp
+/// file once the upload is over `TRANSCRIBE_SPILL_BYTES`.
+async fn receive_upload(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: Body,
+) -> Result<ReceivedUpload, Response> {
+    let max_bytes = state.config.max_transcribe_bytes;
+    let too_large = || {
+        upload_error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Upload is over the {} byte limit", max_bytes),
+        )
+    };
+    let declared_len = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared_len.is_some_and(|len| len > max_bytes) {
+        return Err(too_large());
+    }
+
+    let mut stream = body.into_data_stream();
+    let mut head = Vec::new();
+    let mut checked = false;
+    let mut received = 0;
+    let mut data = UploadData::Memory(Vec::new());
+    let mut hasher = AudioHasher::default();
+
+    loop {
+        let chunk = match stream.next().await {
+            Some(Ok(chunk)) => Some(chunk),
+            Some(Err(e)) => {
+                warn!("Upload failed while receiving: {}", e);
+                return Err(upload_error(
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to read upload: {}", e),
+                ));
+            }
+            None => None,
+        };
+
+        if let Some(chunk) = &chunk {
+            received += chunk.len();
+            if received > max_bytes {
+                return Err(too_large());
+            }
+            let take = HEADER_PROBE_BYTES.saturating_sub(head.len()).min(chunk.len());
+            head.extend_from_slice(&chunk[..take]);
+        }
+        if !checked {
+            checked = check_upload_start(state, headers, &head, chunk.is_none())
+                .map_err(|(status, message)| upload_error(status, message))?;
+        }
+
+        let Some(chunk) = chunk else {
+            break;
+        };
+        hasher.update(&chunk);
+        if let Err(e) = data
+            .append(&chunk, &state.upload_spool, state.config.transcribe_spill_bytes)
+            .await
+        {
+            error!("Failed to buffer upload: {}", e);
+            return Err(upload_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to buffer upload: {}", e),
+            ));
+        }
+    }
+
+    if received == 0 {
+        return Err(upload_error(StatusCode::BAD_REQUEST, "No audio data provided".to_string()));
+    }
+    Ok(ReceivedUpload {
+        data,
+        head,
+        digest: hasher.finish(),
+    })
+}
+
+/// Check the media type and, for WAV, the header against the recognizer's format.
+/// Returns false while more bytes are needed to decide; `ended` means none are coming.
+/// Containers other than WAV are left to the decoder.
+fn check_upload_start(
+    state: &AppState,
+    headers: &HeaderMap,
+    head: &[u8],
+    ended: bool,
+) -> Result<bool, (StatusCode, String)> {
+    if head.len() < SNIFF_BYTES && !ended {
+        return Ok(false);
+    }
+
+    if let Some(media_type) = disallowed_media_type(headers, head, &state.config.allowed_audio_types) {
+        warn!("Rejecting upload of type {}", media_type);
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Unsupported audio type: {}", media_type),
+        ));
+    }
+    if AudioContainer::detect(head) != AudioContainer::Wav {
+        return Ok(true);
+    }
+
+    match check_wav_header(head, state.config.vosk_sample_rate) {
+        HeaderCheck::Valid => Ok(true),
+        HeaderCheck::Incomplete if !ended && head.len() < HEADER_PROBE_BYTES => Ok(false),
+        HeaderCheck::Incomplete => Err((
+            StatusCode::BAD_REQUEST,
+            "Invalid WAV upload: header is truncated".to_string(),
+        )),
+        HeaderCheck::Invalid(reason) => {
+            warn!("Rejecting WAV upload: {}", reason);
+            Err((StatusCode::BAD_REQUEST, format!("Invalid WAV upload: {}", reason)))
+        }
+    }
+}
+
+/// Transcribe a received upload, from its temp file if it was spilled to disk.
+/// Identical audio seen within `TRANSCRIPT_CACHE_TTL_SECS` reuses the earlier transcript.
+async fn transcribe_upload(
+    state: &AppState,
+    data: UploadData,
+    digest: &str,
+) -> anyhow::Result<Transcript> {
+    if let Some(transcript) = state.transcript_cache.get(digest).await {
+        info!("Reusing cached transcript for identical audio {}", digest);
+        return Ok(transcript);
+    }
+
+    let transcript = match data {
+        UploadData::Memory(audio) => {
+            state.latency.time(Stage::Transcription, state.stt_service.transcribe(audio)).await?
+        }
+        UploadData::Spilled(mut spilled) => {
+            spilled.finish().await?;
+            let path = spilled.path();
+            state.latency.time(Stage::Transcription, state.stt_service.transcribe_file(path)).await?
+        }
+    };
+    state.transcript_cache.store(digest, transcript.clone()).await;
+    Ok(transcript)
+}
+
+/// Queue a background transcription that reports to `callback_url`
+async fn start_transcription_job(
+    state: Arc<AppState>,
+    owner: SessionOwner,
+    callback_url: String,
+    language: Option<String>,
+    normalize: bool,
+    upload: ReceivedUpload,
+) -> Response {
+    if let Err(e) = state.transcription_jobs.check_callback_url(&callback_url).await {
+        return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(e.to_string(), 400)))
+            .into_response();
+    }
+
+    let job_id = state.transcription_jobs.create(owner).await;
+    info!("Queued transcription job {} with callback {}", job_id, callback_url);
+
+    let jobs = state.transcription_jobs.clone();
+    tokio::spawn(async move {
+        let duration = wav_duration_secs(&upload.head);
+
+        let delivery = match transcribe_upload(&state, upload.data, &upload.digest).await {
+            Ok(transcript) => {
+                let confidence = transcript.confidence();
+                let text = postprocess(transcript.text, normalize);
+                let mut response = transcription_response(&state, text, language, duration);
+                response.id = job_id.to_string();
+                response.confidence = confidence;
+                match jobs.complete(job_id, response).await {
+                    Some(job) => jobs.deliver_callback(&callback_url, &job).await,
+                    None => Ok(()),
+                }
+            }
+            Err(e) => {
+                error!("Transcription job {} failed: {}", job_id, e);
+                match jobs.fail(job_id, format!("Transcription failed: {}", e)).await {
+                    Some(job) => jobs.deliver_callback(&callback_url, &job).await,
+                    None => Ok(()),
+                }
+            }
+        };
+
+        if let Err(e) = delivery {
+            warn!("Transcription job {}: {}", job_id, e);
+        }
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "id": job_id,
+            "status": "pending",
+            "status_url": format!("/api/v1/transcriptions/{}", job_id),
+        })),
+    )
+        .into_response()
+}
+
+/// The first of the declared `Content-Type` and the sniffed container that isn't
+/// allowed. Generic declarations (`application/octet-stream`) and unrecognized
+/// bytes aren't judged here; the decoder reports those.
+fn disallowed_media_type(headers: &HeaderMap, body: &[u8], allowed: &[String]) -> Option<String> {
+    let declared = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(canonical_mime)
+        .filter(|mime| !mime.is_empty() && mime != "application/octet-stream");
+    let sniffed = match AudioContainer::detect(body) {
+        AudioContainer::Unknown => None,
+        container => Some(container.content_type().to_string()),
+    };
+
+    [declared, sniffed]
+        .into_iter()
+        .flatten()
+        .find(|mime| mime != "audio/wav" && !allowed.contains(mime))
+}
+
+/// True when the client accepts `text/plain` but not JSON (JSON stays the default)
+fn wants_plain_text(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|accept| accept.contains("text/plain") && !accept.contains("application/json"))
+        .unwrap_or(false)
+}
+
+/// Raw recognizer output unless the client asked for `normalize=true`
+fn postprocess(text: String, normalize: bool) -> String {
+    if normalize {
+        text_normalizer::normalize_transcript(&text)
+    } else {
+        text
+    }
+}
+
+/// Build a `TranscriptionResponse` in the language the request named, or else the
+/// detected one
+fn transcription_response(
+    state: &AppState,
+    text: String,
+    language: Option<String>,
+    duration: f32,
+) -> TranscriptionResponse {
+    let language = chosen_language(state, &text, language);
+    TranscriptionResponse::new(text, language, duration)
+}
+
+/// Ranks the languages that have a configured Vosk model
+fn language_detector(state: &AppState) -> LanguageDetector {
+    let languages = state.config.language_models().into_iter().map(|(language, _)| language);
+    LanguageDetector::new(languages.collect())
+}
+
+/// `requested` if given, else the top auto-detect candidate for `text`, falling back
+/// to `DEFAULT_LANGUAGE` when nothing could be detected
+fn chosen_language(state: &AppState, text: &str, requested: Option<String>) -> String {
+    requested
+        .or_else(|| language_detector(state).detect(text))
+        .unwrap_or_else(|| state.config.default_language.clone())
+}
+
+/// Audio length in seconds from the WAV header (0.0 if unreadable). Only the header
+/// is read, so the start of the upload is enough.
+fn wav_duration_secs(audio: &[u8]) -> f32 {
+    hound::WavReader::new(std::io::Cursor::new(audio))
+        .map(|reader| reader.duration() as f32 / reader.spec().sample_rate as f32)
+        .unwrap_or(0.0)
+}
+
+/// GET /api/v1/transcriptions/:id
+/// Poll fallback for jobs started with `callback_url`; other keys' jobs are 404
+pub async fn get_transcription_job(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    match state.transcription_jobs.get(id, owner).await {
+        Some(job) => (StatusCode::OK, Json(job)).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!("Transcription job {} not found", id), 404)),
+        )
+            .into_response(),
+    }
+}
+
+/// Read a transcription back through the TTS service
+async fn speak_transcription(state: &AppState, text: &str) -> Response {
+    match state.latency.time(Stage::Tts, state.elevenlabs_service.text_to_speech(text)).await {
+        Ok(audio) => {
+            info!("Synthesized {} bytes of transcription read-back", audio.len());
+            (StatusCode::OK, [(header::CONTENT_TYPE, "audio/mpeg")], audio).into_response()
+        }
+        Err(e)
+            if e.downcast_ref::<CircuitOpen>().is_some()
+                || e.downcast_ref::<TtsQueueTimeout>().is_some() =>
+        {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::new(e.to_string(), 503)),
+            )
+                .into_response()
         }
         Err(e) => {
-            error!("Transcription error: {}", e);
+            error!("Transcription read-back TTS failed: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(format!("Transcription failed: {}", e), 500)),
+                Json(ErrorResponse::new("Text-to-speech failed".to_string(), 500)),
             )
                 .into_response()
         }
     }
 }
 
+/// GET /api/v1/transcribe/stream (WebSocket)
+/// Binary frames carry 16-bit PCM. An utterance is finalized when the client sends
+/// "FINISH" or, with endpointing enabled, after `VAD_HANG_MS` of silence; the socket
+/// then keeps listening for the next utterance. Oversized chunks or more than
+/// `STREAM_MAX_AUDIO_BYTES` of buffered audio close the socket with an error.
+/// With `?partials=true` the hypothesis so far is sent as "partial" messages while
+/// audio arrives, at most once per `STREAM_PARTIAL_INTERVAL_MS`.
+/// Messages are JSON text frames, or with `?format=binary` length-prefixed binary frames.
+/// The socket ends with a close frame: 1000 once the last utterance is sent, 1008 when
+/// a limit is exceeded, no audio came or nothing arrived for `STREAM_IDLE_TIMEOUT_SECS`,
+/// and 1011 when the last transcription failed.
+/// At most `MAX_STREAMING_CONNECTIONS` sockets are open at once; upgrades past that get 503.
+/// With `?session_token=`, a socket that drops without a close frame keeps its unfinished
+/// utterance for `STREAM_RESUME_GRACE_SECS`; reconnecting with the same token (and API
+/// key) continues it, announced by a "resumed" message.
 pub async fn transcribe_stream(
     State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Query(params): Query<StreamTranscriptionParams>,
+    headers: HeaderMap,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_streaming(socket, state))
+    if let Some(token) = &params.session_token {
+        if token.is_empty() || token.len() > MAX_SESSION_TOKEN_LEN {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(
+                    format!("session_token must be 1 to {} characters", MAX_SESSION_TOKEN_LEN),
+                    400,
+                )),
+            )
+                .into_response();
+        }
+    }
+    let Ok(permit) = state.streaming_permits.clone().try_acquire_owned() else {
+        warn!(
+            "Refusing streaming connection: {} already open",
+            state.config.max_streaming_connections
+        );
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                "Too many streaming connections, try again later".to_string(),
+                503,
+            )),
+        )
+            .into_response();
+    };
+
+    // Oversized messages fail the socket before they are buffered in full
+    ws.protocols(ws_key_protocol(&headers))
+        .max_message_size(state.config.ws_max_message_bytes)
+        .max_frame_size(state.config.ws_max_message_bytes)
+        .on_upgrade(move |socket| async move {
+            let resume = params.session_token.map(|token| (owner, token));
+            handle_streaming(socket, state, params.partials, params.format, resume).await;
+            drop(permit);
+        })
+}
+
+/// Longest `session_token` accepted on `/api/v1/transcribe/stream`
+const MAX_SESSION_TOKEN_LEN: usize = 128;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StreamTranscriptionParams {
+    #[serde(default)]
+    pub partials: bool,
+    /// Client-chosen id that lets a dropped socket be resumed by reconnecting with it
+    pub session_token: Option<String>,
+    #[serde(default)]
+    pub format: StreamFormat,
 }
 
-async fn handle_streaming(socket: axum::extract::ws::WebSocket, state: Arc<AppState>) {
+/// How server messages are framed on `/api/v1/transcribe/stream` (`?format=`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    /// Each `StreamingMessage` as a JSON text frame
+    #[default]
+    Json,
+    /// Binary frames: the same JSON prefixed with its byte length as a big-endian u32
+    Binary,
+}
+
+impl StreamFormat {
+    fn frame(self, message: &StreamingMessage) -> axum::extract::ws::Message {
+        let json = serde_json::to_string(message).unwrap();
+        match self {
+            StreamFormat::Json => axum::extract::ws::Message::Text(json),
+            StreamFormat::Binary => {
+                let mut frame = Vec::with_capacity(4 + json.len());
+                frame.extend_from_slice(&(json.len() as u32).to_be_bytes());
+                frame.extend_from_slice(json.as_bytes());
+                axum::extract::ws::Message::Binary(frame)
+            }
+        }
+    }
+}
+
+async fn handle_streaming(
+    socket: axum::extract::ws::WebSocket,
+    state: Arc<AppState>,
+    partials: bool,
+    format: StreamFormat,
+    resume: Option<(SessionOwner, String)>,
+) {
     let (mut sender, mut receiver) = socket.split();
-    let mut audio_chunks = Vec::new();
+    let resumed = match &resume {
+        Some((owner, token)) => state.stream_resumes.resume(*owner, token).await,
+        None => None,
+    };
+    let mut stream = match resumed {
+        Some(progress) => {
+            info!("Resuming stream with {} buffered bytes", progress.buffered_bytes);
+            let _ = sender
+                .send(format.frame(&StreamingMessage::resumed()))
+                .await;
+            progress
+        }
+        None => StreamProgress {
+            endpointer: (state.config.vad_hang_ms > 0).then(|| {
+                Endpointer::new(
+                    state.config.vad_energy_threshold,
+                    Duration::from_millis(state.config.vad_hang_ms),
+                    state.config.vosk_sample_rate,
+                )
+            }),
+            ..Default::default()
+        },
+    };
+    let mut partial_throttle = partials.then(|| {
+        PartialThrottle::new(Duration::from_millis(state.config.stream_partial_interval_ms))
+    });
+    let idle_timeout = Duration::from_secs(state.config.stream_idle_timeout_secs);
 
-    while let Some(msg) = receiver.next().await {
+    loop {
+        let next = if idle_timeout.is_zero() {
+            receiver.next().await
+        } else {
+            match tokio::time::timeout(idle_timeout, receiver.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    // A silent socket may be a dead network the server hasn't noticed yet;
+                    // keep the utterance for a reconnect like any other drop
+                    if let Some((owner, token)) = &resume {
+                        info!("Stream {} idle, keeping its audio for a reconnect", token);
+                        state.stream_resumes.park(*owner, token, stream).await;
+                    }
+                    reject_stream(
+                        &mut sender,
+                        format,
+                        format!("No data received for {} seconds", idle_timeout.as_secs()),
+                    )
+                    .await;
+                    return;
+                }
+            }
+        };
+        let msg = match (next, &resume) {
+            (Some(Ok(msg)), _) => msg,
+            // Gone without a close frame (e.g. the network dropped): keep the utterance
+            // for a reconnect with the same session token
+            (Some(Err(_)) | None, Some((owner, token))) => {
+                info!("Stream {} dropped, keeping its audio for a reconnect", token);
+                state.stream_resumes.park(*owner, token, stream).await;
+                return;
+            }
+            (Some(Err(e)), None) => {
+                error!("WebSocket error: {}", e);
+                let _ = sender
+                    .send(format.frame(&StreamingMessage::error(format!(
+                        "WebSocket error: {}",
+                        e
+                    ))))
+                    .await;
+                close_stream(&mut sender, close_code::ERROR, "WebSocket error").await;
+                return;
+            }
+            (None, None) => break,
+        };
         match msg {
-            Ok(axum::extract::ws::Message::Binary(data)) => {
-                audio_chunks.push(data.to_vec());
+            axum::extract::ws::Message::Binary(data) => {
                 info!("Received audio chunk: {} bytes", data.len());
+                stream.buffered_bytes += data.len();
+                if stream.buffered_bytes > state.config.stream_max_audio_bytes {
+                    reject_stream(
+                        &mut sender,
+                        format,
+                        format!(
+                            "Streamed audio exceeds the {} byte limit",
+                            state.config.stream_max_audio_bytes
+                        ),
+                    )
+                    .await;
+                    return;
+                }
+
+                let end_of_utterance = stream
+                    .endpointer
+                    .as_mut()
+                    .map(|endpointer| endpointer.push(&data))
+                    .unwrap_or(false);
+                stream.audio_chunks.push(data.to_vec());
+
+                if end_of_utterance {
+                    info!("Silence detected, finalizing utterance");
+                    let chunks = std::mem::take(&mut stream.audio_chunks);
+                    stream.buffered_bytes = 0;
+                    if let Err(code) = send_final(&mut sender, format, &state, chunks).await {
+                        if code == close_code::AGAIN {
+                            close_stream(&mut sender, code, "Try again later").await;
+                            return;
+                        }
+                    }
+                    if let Some(endpointer) = stream.endpointer.as_mut() {
+                        endpointer.reset();
+                    }
+                    if let Some(throttle) = partial_throttle.as_mut() {
+                        throttle.reset();
+                    }
+                    stream.utterances += 1;
+                } else if partial_throttle
+                    .as_mut()
+                    .map(|t| t.ready(stream.buffered_bytes))
+                    .unwrap_or(false)
+                {
+                    send_partial(&mut sender, format, &state, &stream.audio_chunks).await;
+                }
             }
-            Ok(axum::extract::ws::Message::Text(text)) => {
+            axum::extract::ws::Message::Text(text) => {
                 if text == "FINISH" {
                     info!("Stream finish signal received");
                     break;
                 }
             }
-            Ok(axum::extract::ws::Message::Close(_)) => {
+            axum::extract::ws::Message::Close(_) => {
                 info!("WebSocket closed by client");
                 break;
             }
-            Err(e) => {
-                error!("WebSocket error: {}", e);
-                let _ = sender
-                    .send(axum::extract::ws::Message::Text(
-                        serde_json::to_string(&StreamingMessage::error(format!(
-                            "WebSocket error: {}",
-                            e
-                        )))
-                        .unwrap(),
-                    ))
-                    .await;
-                return;
-            }
             _ => {}
         }
     }
 
-    if audio_chunks.is_empty() {
-        let _ = sender
-            .send(axum::extract::ws::Message::Text(
-                serde_json::to_string(&StreamingMessage::error(
-                    "No audio data received".to_string(),
-                ))
-                .unwrap(),
-            ))
-            .await;
+    if stream.audio_chunks.is_empty() {
+        // Nothing left over after endpointed utterances is fine
+        if stream.utterances == 0 {
+            reject_stream(&mut sender, format, "No audio data received".to_string()).await;
+        } else {
+            close_stream(&mut sender, close_code::NORMAL, "Transcription complete").await;
+        }
         return;
     }
 
-    match state.vosk_service.transcribe_streaming(audio_chunks).await {
-        Ok(text) => {
-            info!("Streaming transcription completed: {}", text);
-            let message = StreamingMessage::final_result(text);
+    match send_final(&mut sender, format, &state, stream.audio_chunks).await {
+        Ok(()) => close_stream(&mut sender, close_code::NORMAL, "Transcription complete").await,
+        Err(close_code::AGAIN) => close_stream(&mut sender, close_code::AGAIN, "Try again later").await,
+        Err(code) => close_stream(&mut sender, code, "Transcription failed").await,
+    }
+}
+
+/// Tell the client why and close the socket with 1008 (limits exceeded, idle, no audio)
+async fn reject_stream(
+    sender: &mut SplitSink<axum::extract::ws::WebSocket, axum::extract::ws::Message>,
+    format: StreamFormat,
+    reason: String,
+) {
+    warn!("Closing transcription stream: {}", reason);
+    let _ = sender
+        .send(format.frame(&StreamingMessage::error(reason.clone())))
+        .await;
+    close_stream(sender, close_code::POLICY, &reason).await;
+}
+
+/// Send a close frame; `reason` is cut to fit the 123 bytes a close frame allows
+async fn close_stream(
+    sender: &mut SplitSink<axum::extract::ws::WebSocket, axum::extract::ws::Message>,
+    code: u16,
+    reason: &str,
+) {
+    let mut end = reason.len().min(123);
+    while !reason.is_char_boundary(end) {
+        end -= 1;
+    }
+    let _ = sender
+        .send(axum::extract::ws::Message::Close(Some(CloseFrame {
+            code,
+            reason: reason[..end].to_string().into(),
+        })))
+        .await;
+}
+
+/// Send the hypothesis for the audio buffered so far as a "partial" message;
+/// nothing is sent while no speech has been recognized yet
+async fn send_partial(
+    sender: &mut SplitSink<axum::extract::ws::WebSocket, axum::extract::ws::Message>,
+    format: StreamFormat,
+    state: &AppState,
+    audio_chunks: &[Vec<u8>],
+) {
+    let transcribed = state.stt_service.transcribe_streaming(audio_chunks.to_vec());
+    match state.latency.time(Stage::Partial, transcribed).await {
+        Ok(transcript) => {
             let _ = sender
-                .send(axum::extract::ws::Message::Text(
-                    serde_json::to_string(&message).unwrap(),
-                ))
+                .send(format.frame(&StreamingMessage::partial(transcript.text)))
                 .await;
         }
+        Err(e) => debug!("No partial result yet: {}", e),
+    }
+}
+
+/// Transcribe one utterance and send the "final" (or "error") message. When
+/// transcription failed, Err has the close code to end the stream with: 1013 (try
+/// again later) while the model loads or the queue is full, else 1011.
+async fn send_final(
+    sender: &mut SplitSink<axum::extract::ws::WebSocket, axum::extract::ws::Message>,
+    format: StreamFormat,
+    state: &AppState,
+    audio_chunks: Vec<Vec<u8>>,
+) -> Result<(), u16> {
+    let message = utterance_message(state, audio_chunks).await;
+    let _ = sender.send(format.frame(&message)).await;
+    match (message.r#type.as_str(), message.code) {
+        ("error", Some(503)) => Err(close_code::AGAIN),
+        ("error", _) => Err(close_code::ERROR),
+        _ => Ok(()),
+    }
+}
+
+/// Transcribe one streamed utterance into its "final" (or "error") message
+async fn utterance_message(state: &AppState, audio_chunks: Vec<Vec<u8>>) -> StreamingMessage {
+    let transcribed = state.stt_service.transcribe_streaming(audio_chunks);
+    match state.latency.time(Stage::Transcription, transcribed).await {
+        Ok(transcript) => {
+            info!("Streaming transcription completed: {}", transcript.text);
+            StreamingMessage::final_with_segments(transcript.text, transcript.words)
+        }
+        Err(e) if is_busy(&e) => {
+            warn!("Streaming transcription refused: {}", e);
+            StreamingMessage::unavailable(e.to_string())
+        }
         Err(e) => {
             error!("Streaming transcription error: {}", e);
-            let message = StreamingMessage::error(format!("Transcription failed: {}", e));
-            let _ = sender
-                .send(axum::extract::ws::Message::Text(
-                    serde_json::to_string(&message).unwrap(),
-                ))
+            StreamingMessage::error(format!("Transcription failed: {}", e))
+        }
+    }
+}
+
+/// Whether transcription was refused for now (model still loading, queue full)
+/// rather than failed, so the client should retry
+fn is_busy(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<ModelLoading>().is_some() || e.downcast_ref::<QueueTimeout>().is_some()
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SseTranscriptionParams {
+    /// Transcribe the stored audio of this voice-turn message instead of the request body
+    pub audio_id: Option<Uuid>,
+}
+
+/// GET /api/v1/transcribe/sse
+/// Server-Sent Events alternative to the WebSocket stream, for clients that can't
+/// use WebSockets. With `?audio_id=<message id>` the stored audio of that voice turn
+/// is transcribed (503 with Retry-After while the model loads); otherwise the (chunked)
+/// request body is read as 16-bit PCM and, with endpointing enabled, each utterance gets
+/// its "final" event as soon as it ends. Errors the client should retry carry `code: 503`.
+/// Events are named after the message type and carry a `StreamingMessage` as JSON.
+/// Stored audio is only served to the API key whose voice session recorded it.
+pub async fn transcribe_sse(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Query(params): Query<SseTranscriptionParams>,
+    body: Body,
+) -> Response {
+    let (mut events, messages) = mpsc::channel::<StreamingMessage>(16);
+
+    match params.audio_id {
+        Some(message_id) => {
+            let audio = match load_stored_audio(&state, owner, message_id).await {
+                Ok(audio) => audio,
+                Err(response) => return response,
+            };
+            // Stored audio is one transcription, so it runs before the stream opens and
+            // a loading model or full queue is a real 503 with Retry-After
+            let transcribed = state.stt_service.transcribe(audio);
+            let message = match state.latency.time(Stage::Transcription, transcribed).await {
+                Ok(transcript) => {
+                    StreamingMessage::final_with_segments(transcript.text, transcript.words)
+                }
+                Err(e) if is_busy(&e) => return transcription_error(e),
+                Err(e) => {
+                    error!("SSE transcription of message {} failed: {}", message_id, e);
+                    StreamingMessage::error(format!("Transcription failed: {}", e))
+                }
+            };
+            let _ = events.send(message).await;
+        }
+        None => {
+            tokio::spawn(stream_body_events(state, body, events));
+        }
+    }
+
+    let stream = messages.map(|message| {
+        Event::default()
+            .event(message.r#type.clone())
+            .json_data(&message)
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// Read the stored audio of one of `owner`'s voice turns, or the error response to send
+/// instead (404 for audio recorded under another API key)
+async fn load_stored_audio(
+    state: &AppState,
+    owner: SessionOwner,
+    message_id: Uuid,
+) -> Result<Vec<u8>, Response> {
+    let error_response = |status: StatusCode, message: String| {
+        (status, Json(ErrorResponse::new(message, status.as_u16()))).into_response()
+    };
+    let Some(store) = &state.audio_store else {
+        return Err(error_response(
+            StatusCode::NOT_FOUND,
+            "Audio storage is disabled".to_string(),
+        ));
+    };
+
+    let (path, _) = match state.database_service.get_message_audio(message_id, owner).await {
+        Ok(stored) => stored,
+        Err(DbError::NotFound) => {
+            return Err(error_response(
+                StatusCode::NOT_FOUND,
+                format!("No stored audio for message {}", message_id),
+            ))
+        }
+        Err(e) => {
+            error!("Failed to look up audio for message {}: {}", message_id, e);
+            let status = match e {
+                DbError::Connection(_) => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            return Err(error_response(status, "Failed to load stored audio".to_string()));
+        }
+    };
+
+    let mut audio = Vec::new();
+    let read = match store.open(std::path::Path::new(&path)).await {
+        Ok(mut file) => file.read_to_end(&mut audio).await.map_err(anyhow::Error::from),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = read {
+        error!("{}", e);
+        return Err(error_response(
+            StatusCode::NOT_FOUND,
+            format!("Stored audio for message {} is missing", message_id),
+        ));
+    }
+    Ok(audio)
+}
+
+/// Transcribe a PCM request body as it arrives, sending a message per utterance
+async fn stream_body_events(
+    state: Arc<AppState>,
+    body: Body,
+    mut events: mpsc::Sender<StreamingMessage>,
+) {
+    let mut body = body.into_data_stream();
+    let mut endpointer = (state.config.vad_hang_ms > 0).then(|| {
+        Endpointer::new(
+            state.config.vad_energy_threshold,
+            Duration::from_millis(state.config.vad_hang_ms),
+            state.config.vosk_sample_rate,
+        )
+    });
+    let mut audio_chunks = Vec::new();
+    let mut buffered_bytes = 0;
+    let mut utterances = 0;
+    // Body chunks can split a 16-bit sample; carry the odd byte into the next chunk
+    let mut odd_byte = None;
+
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                error!("SSE upload error: {}", e);
+                let _ = events
+                    .send(StreamingMessage::error(format!("Upload failed: {}", e)))
+                    .await;
+                return;
+            }
+        };
+
+        let mut pcm = Vec::with_capacity(chunk.len() + 1);
+        pcm.extend(odd_byte.take());
+        pcm.extend_from_slice(&chunk);
+        if pcm.len() % 2 == 1 {
+            odd_byte = pcm.pop();
+        }
+
+        buffered_bytes += pcm.len();
+        if buffered_bytes > state.config.stream_max_audio_bytes {
+            warn!("Ending SSE transcription: utterance exceeds the audio limit");
+            let _ = events
+                .send(StreamingMessage::error(format!(
+                    "Streamed audio exceeds the {} byte limit",
+                    state.config.stream_max_audio_bytes
+                )))
+                .await;
+            return;
+        }
+
+        let end_of_utterance = endpointer
+            .as_mut()
+            .map(|endpointer| endpointer.push(&pcm))
+            .unwrap_or(false);
+        audio_chunks.push(pcm);
+
+        if end_of_utterance {
+            info!("Silence detected, finalizing utterance");
+            let chunks = std::mem::take(&mut audio_chunks);
+            buffered_bytes = 0;
+            if events.send(utterance_message(&state, chunks).await).await.is_err() {
+                // Client went away
+                return;
+            }
+            if let Some(endpointer) = endpointer.as_mut() {
+                endpointer.reset();
+            }
+            utterances += 1;
+        }
+    }
+
+    if audio_chunks.is_empty() {
+        if utterances == 0 {
+            let _ = events
+                .send(StreamingMessage::error("No audio data received".to_string()))
                 .await;
         }
+        return;
+    }
+
+    let _ = events.send(utterance_message(&state, audio_chunks).await).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{MockSpeechToText, SpeechToText, TranscriptionJobService};
+    use crate::test_support::{self, MockUpstream, MOCK_MP3};
+
+    /// A silent WAV upload in the given format
+    fn test_wav(sample_rate: u32, channels: u16, samples: usize) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for _ in 0..samples {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_speak_returns_tts_audio() {
+        let upstream = MockUpstream::start("unused").await;
+        let state = test_support::test_state_with_upstream(&upstream);
+
+        let response = speak_transcription(&state, "hello there").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "audio/mpeg");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], MOCK_MP3);
+
+        let tts_requests = upstream.requests_to("/text-to-speech/");
+        assert_eq!(tts_requests.len(), 1);
+        assert_eq!(tts_requests[0].json()["text"], "hello there");
+    }
+
+    #[tokio::test]
+    async fn test_sse_stream_yields_final_event() {
+        let state = Arc::new(test_support::test_state());
+        let pcm = crate::services::endpointing::pcm_chunk(8000, 500, 16000);
+
+        let response = transcribe_sse(
+            State(state),
+            Extension(test_support::session_owner()),
+            Query(SseTranscriptionParams::default()),
+            Body::from(pcm),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("event: final"), "{}", body);
+        let data = body
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .unwrap();
+        let message: serde_json::Value = serde_json::from_str(data).unwrap();
+        assert_eq!(message["type"], "final");
+        assert_eq!(message["result"], test_support::MOCK_TRANSCRIPT);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_sse_stored_audio_only_for_the_recording_key() {
+        let mut state = test_support::test_state();
+        state.database_service = test_support::database_service().await;
+        let store = crate::services::AudioStore::new(
+            std::env::temp_dir().join(format!("rusty-tea-audio-{}", Uuid::new_v4())),
+        );
+        state.audio_store = Some(store.clone());
+        let state = Arc::new(state);
+
+        let turn = crate::services::voice_session_service::Turn::new("user", "hello tea");
+        let message_id = turn.id;
+        let (path, content_type) = store.save(message_id, &test_wav(16000, 1, 1600)).await.unwrap();
+        state
+            .database_service
+            .save_message_audio(
+                Uuid::new_v4(),
+                &turn,
+                test_support::session_owner(),
+                &path.to_string_lossy(),
+                content_type,
+            )
+            .await
+            .unwrap();
+
+        let sse = |owner: SessionOwner| {
+            transcribe_sse(
+                State(state.clone()),
+                Extension(owner),
+                Query(SseTranscriptionParams {
+                    audio_id: Some(message_id),
+                }),
+                Body::empty(),
+            )
+        };
+
+        let response = sse(SessionOwner::from_api_key("another-tenant")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = sse(test_support::session_owner()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_batch_transcription_returns_text() {
+        let state = Arc::new(test_support::test_state());
+
+        let response = transcribe_batch(
+            State(state),
+            Extension(test_support::session_owner()),
+            Query(TranscriptionRequest {
+                language: None,
+                speak: false,
+                callback_url: None,
+                normalize: false,
+                debug: false,
+            }),
+            HeaderMap::new(),
+            Body::from(&b"RIFFfake"[..]),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = test_support::body_json(response).await;
+        assert_eq!(body["text"], test_support::MOCK_TRANSCRIPT);
+        // The mock recognizer has no word info
+        assert!(body.get("confidence").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_batch_lines_carry_file_index() {
+        use tower::ServiceExt;
+
+        let boundary = "tea-batch";
+        let mut body = Vec::new();
+        let files = [
+            ("first.wav", test_wav(16000, 1, 1600)),
+            ("wrong_rate.wav", test_wav(8000, 1, 800)),
+            ("third.wav", test_wav(16000, 1, 1600)),
+        ];
+        for (filename, audio) in &files {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"files\"; filename=\"{}\"\r\n\
+                     Content-Type: audio/wav\r\n\r\n",
+                    boundary, filename
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(audio);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        let app = crate::build_router(Arc::new(test_support::test_state()));
+        let request = axum::http::Request::post("/api/v1/transcriptions/batch?stream=true")
+            .header("x-api-key", test_support::API_KEY)
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut lines: Vec<serde_json::Value> = std::str::from_utf8(&bytes)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), files.len());
+
+        lines.sort_by_key(|line| line["index"].as_u64().unwrap());
+        for (index, line) in lines.iter().enumerate() {
+            assert_eq!(line["index"], index);
+            assert_eq!(line["filename"], files[index].0);
+        }
+        assert_eq!(lines[0]["text"], test_support::MOCK_TRANSCRIPT);
+        assert!(lines[1]["error"].as_str().unwrap().contains("Invalid WAV upload"));
+        assert!(lines[1].get("text").is_none());
+        assert_eq!(lines[2]["text"], test_support::MOCK_TRANSCRIPT);
+    }
+
+    #[tokio::test]
+    async fn test_upload_over_spill_threshold_transcribed_from_disk() {
+        let mut state = test_support::test_state();
+        state.config.transcribe_spill_bytes = 64;
+        let state = Arc::new(state);
+
+        let audio = test_wav(16000, 1, 2048);
+        let response = transcribe_batch(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Query(TranscriptionRequest {
+                language: None,
+                speak: false,
+                callback_url: None,
+                normalize: false,
+                debug: false,
+            }),
+            HeaderMap::new(),
+            Body::from(audio),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = test_support::body_json(response).await;
+        assert_eq!(body["text"], test_support::MOCK_TRANSCRIPT);
+        assert_eq!(state.upload_spool.spilled_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_wav_header_rejected_before_body_completes() {
+        let state = Arc::new(test_support::test_state());
+
+        // A 44.1kHz stereo header followed by a body that never finishes arriving
+        let header = test_wav(44100, 2, 0);
+        let chunks = futures::stream::iter([Ok::<_, std::io::Error>(axum::body::Bytes::from(header))])
+            .chain(futures::stream::pending());
+        let response = tokio::time::timeout(
+            Duration::from_secs(5),
+            transcribe_batch(
+                State(state),
+                Extension(test_support::session_owner()),
+                Query(TranscriptionRequest {
+                    language: None,
+                    speak: false,
+                    callback_url: None,
+                    normalize: false,
+                    debug: false,
+                }),
+                HeaderMap::new(),
+                Body::from_stream(chunks),
+            ),
+        )
+        .await
+        .expect("handler waited for the rest of the body")
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = test_support::body_json(response).await;
+        assert!(body["error"].as_str().unwrap().contains("16000Hz mono"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_accept_header_selects_plain_text_or_json() {
+        let state = Arc::new(test_support::test_state());
+        let transcribe = |accept: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, accept.parse().unwrap());
+            transcribe_batch(
+                State(state.clone()),
+                Extension(test_support::session_owner()),
+                Query(TranscriptionRequest {
+                    language: None,
+                    speak: false,
+                    callback_url: None,
+                    normalize: false,
+                    debug: false,
+                }),
+                headers,
+                Body::from(&b"RIFFfake"[..]),
+            )
+        };
+
+        let response = transcribe("text/plain").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], test_support::MOCK_TRANSCRIPT.as_bytes());
+
+        let response = transcribe("application/json").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let body = test_support::body_json(response).await;
+        assert_eq!(body["text"], test_support::MOCK_TRANSCRIPT);
+    }
+
+    #[tokio::test]
+    async fn test_debug_param_includes_raw_result() {
+        let state = Arc::new(test_support::test_state());
+        let transcribe = |debug| {
+            transcribe_batch(
+                State(state.clone()),
+                Extension(test_support::session_owner()),
+                Query(TranscriptionRequest {
+                    language: None,
+                    speak: false,
+                    callback_url: None,
+                    normalize: false,
+                    debug,
+                }),
+                HeaderMap::new(),
+                Body::from(&b"RIFFfake"[..]),
+            )
+        };
+
+        let body = test_support::body_json(transcribe(true).await.into_response()).await;
+        assert_eq!(body["text"], test_support::MOCK_TRANSCRIPT);
+        assert_eq!(body["raw"]["text"], test_support::MOCK_TRANSCRIPT);
+
+        let body = test_support::body_json(transcribe(false).await.into_response()).await;
+        assert!(body.get("raw").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_image_upload_rejected_with_415() {
+        let state = Arc::new(test_support::test_state());
+        let png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR".to_vec();
+        let upload = |content_type: Option<&str>, body: Vec<u8>| {
+            let mut headers = HeaderMap::new();
+            if let Some(content_type) = content_type {
+                headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+            }
+            transcribe_batch(
+                State(state.clone()),
+                Extension(test_support::session_owner()),
+                Query(TranscriptionRequest {
+                    language: None,
+                    speak: false,
+                    callback_url: None,
+                    normalize: false,
+                    debug: false,
+                }),
+                headers,
+                Body::from(body),
+            )
+        };
+
+        let response = upload(Some("image/png"), png).await.into_response();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let body = test_support::body_json(response).await;
+        assert!(body["error"].as_str().unwrap().contains("image/png"));
+
+        // WAV is accepted under any of its aliases
+        let response = upload(Some("audio/x-wav"), b"RIFFfake".to_vec()).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_normalize_param_postprocesses_text() {
+        let mut state = test_support::test_state();
+        state.stt_service = Arc::new(MockSpeechToText::new("add twenty three sugars"));
+
+        let transcribe = |normalize| {
+            transcribe_batch(
+                State(Arc::new(state.clone())),
+                Extension(test_support::session_owner()),
+                Query(TranscriptionRequest {
+                    language: None,
+                    speak: false,
+                    callback_url: None,
+                    normalize,
+                    debug: false,
+                }),
+                HeaderMap::new(),
+                Body::from(&b"RIFFfake"[..]),
+            )
+        };
+
+        let raw = test_support::body_json(transcribe(false).await.into_response()).await;
+        assert_eq!(raw["text"], "add twenty three sugars");
+        let normalized = test_support::body_json(transcribe(true).await.into_response()).await;
+        assert_eq!(normalized["text"], "Add 23 sugars");
+    }
+
+    #[tokio::test]
+    async fn test_silence_finalizes_stream_without_finish() {
+        use crate::services::endpointing::pcm_chunk;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut state = test_support::test_state();
+        state.config.vad_energy_threshold = 500.0;
+        state.config.vad_hang_ms = 300;
+        let addr = test_support::spawn_app(state).await;
+
+        let mut socket = test_support::ws_connect(addr, "/api/v1/transcribe/stream").await;
+
+        // Speech, then silence past the hang time; no "FINISH"
+        socket.send(Message::Binary(pcm_chunk(8000, 200, 16000))).await.unwrap();
+        for _ in 0..4 {
+            socket.send(Message::Binary(pcm_chunk(0, 100, 16000))).await.unwrap();
+        }
+
+        let reply = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+            .await
+            .expect("no final result before timeout")
+            .unwrap()
+            .unwrap();
+        let Message::Text(text) = reply else {
+            panic!("expected a text frame, got {:?}", reply);
+        };
+        let message: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(message["type"], "final");
+        assert_eq!(message["result"], test_support::MOCK_TRANSCRIPT);
+    }
+
+    #[tokio::test]
+    async fn test_stream_authenticates_with_query_key_or_subprotocol() {
+        use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Error as WsError};
+
+        let addr = test_support::spawn_app(test_support::test_state()).await;
+        let url = |query: &str| format!("ws://{}/api/v1/transcribe/stream{}", addr, query);
+
+        let valid = url(&format!("?api_key={}", test_support::API_KEY));
+        assert!(tokio_tungstenite::connect_async(valid).await.is_ok());
+
+        match tokio_tungstenite::connect_async(url("?api_key=not-a-key")).await {
+            Err(WsError::Http(response)) => assert_eq!(response.status(), StatusCode::FORBIDDEN),
+            other => panic!("expected the wrong key to be refused, got {:?}", other.is_ok()),
+        }
+
+        // Browsers that can't use the query string offer the key as a subprotocol, which
+        // has to be echoed back for the handshake to complete
+        let protocol = format!("api-key.{}", test_support::API_KEY);
+        let mut request = url("").into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("sec-websocket-protocol", protocol.parse().unwrap());
+        let (_socket, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+        assert_eq!(response.headers()["sec-websocket-protocol"], protocol.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_resumes_stream_only_within_grace() {
+        use crate::services::{endpointing::pcm_chunk, StreamResumeService};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut state = test_support::test_state();
+        state.config.vad_hang_ms = 0;
+        state.stream_resumes = StreamResumeService::new(30).with_grace(Duration::from_millis(300));
+        let addr = test_support::spawn_app(state).await;
+
+        let connect = |token: &'static str| async move {
+            let path = format!("/api/v1/transcribe/stream?session_token={}", token);
+            test_support::ws_connect(addr, &path).await
+        };
+        async fn next_message(socket: &mut test_support::WsClient) -> serde_json::Value {
+            let reply = tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .expect("no message before timeout")
+                .unwrap()
+                .unwrap();
+            let Message::Text(text) = reply else {
+                panic!("expected a text frame, got {:?}", reply);
+            };
+            serde_json::from_str(&text).unwrap()
+        }
+        // Speech, then the connection is lost without a close frame
+        let drop_mid_utterance = |token: &'static str| async move {
+            let mut socket = connect(token).await;
+            socket.send(Message::Binary(pcm_chunk(8000, 200, 16000))).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(socket);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        };
+
+        drop_mid_utterance("within").await;
+        let mut socket = connect("within").await;
+        assert_eq!(next_message(&mut socket).await["type"], "resumed");
+        socket.send(Message::Text("FINISH".to_string())).await.unwrap();
+        let message = next_message(&mut socket).await;
+        assert_eq!(message["type"], "final");
+        assert_eq!(message["result"], test_support::MOCK_TRANSCRIPT);
+
+        drop_mid_utterance("after").await;
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        let mut socket = connect("after").await;
+        socket.send(Message::Text("FINISH".to_string())).await.unwrap();
+        let message = next_message(&mut socket).await;
+        assert_eq!(message["type"], "error");
+        assert_eq!(message["error"], "No audio data received");
+    }
+
+    #[tokio::test]
+    async fn test_negotiated_format_frames_stream_messages() {
+        use crate::services::endpointing::pcm_chunk;
+        use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+        let mut state = test_support::test_state();
+        state.config.vad_hang_ms = 0;
+        let addr = test_support::spawn_app(state).await;
+
+        let final_frame = |query: &'static str| async move {
+            let path = format!("/api/v1/transcribe/stream{}", query);
+            let mut socket = test_support::ws_connect(addr, &path).await;
+            socket.send(Message::Binary(pcm_chunk(8000, 200, 16000))).await.unwrap();
+            socket.send(Message::Text("FINISH".to_string())).await.unwrap();
+            tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .expect("no final result before timeout")
+                .unwrap()
+                .unwrap()
+        };
+
+        for format in ["", "?format=json"] {
+            let Message::Text(text) = final_frame(format).await else {
+                panic!("expected a text frame for {:?}", format);
+            };
+            let message: serde_json::Value = serde_json::from_str(&text).unwrap();
+            assert_eq!(message["type"], "final");
+        }
+
+        let Message::Binary(frame) = final_frame("?format=binary").await else {
+            panic!("expected a binary frame");
+        };
+        let len = u32::from_be_bytes(frame[..4].try_into().unwrap()) as usize;
+        assert_eq!(len, frame.len() - 4);
+        let message: serde_json::Value = serde_json::from_slice(&frame[4..]).unwrap();
+        assert_eq!(message["type"], "final");
+        assert_eq!(message["result"], test_support::MOCK_TRANSCRIPT);
+
+        match test_support::ws_try_connect(addr, "/api/v1/transcribe/stream?format=xml").await {
+            Err(WsError::Http(response)) => assert_eq!(response.status(), StatusCode::BAD_REQUEST),
+            other => panic!("expected an unknown format to be refused, got {:?}", other.is_ok()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streaming_connection_over_limit_refused() {
+        use tokio_tungstenite::tungstenite::Error as WsError;
+
+        let mut state = test_support::test_state();
+        state.config.max_streaming_connections = 2;
+        state.streaming_permits = Arc::new(tokio::sync::Semaphore::new(2));
+        let addr = test_support::spawn_app(state).await;
+
+        let connect = || test_support::ws_try_connect(addr, "/api/v1/transcribe/stream");
+
+        let mut first = connect().await.unwrap();
+        let _second = connect().await.unwrap();
+
+        match connect().await {
+            Err(WsError::Http(response)) => {
+                assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE)
+            }
+            other => panic!("expected the third connection to be refused, got {:?}", other.is_ok()),
+        }
+
+        // Closing a socket frees its slot
+        first.close(None).await.unwrap();
+        let reconnected = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if connect().await.is_ok() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await;
+        assert!(reconnected.is_ok(), "slot was not released after close");
+    }
+
+    #[tokio::test]
+    async fn test_partial_interval_coalesces_rapid_chunks() {
+        use crate::services::endpointing::pcm_chunk;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut state = test_support::test_state();
+        state.config.vad_hang_ms = 0;
+        state.config.stream_partial_interval_ms = 60_000;
+        let addr = test_support::spawn_app(state).await;
+
+        let mut socket = test_support::ws_connect(addr, "/api/v1/transcribe/stream?partials=true").await;
+
+        let chunks = 10;
+        for _ in 0..chunks {
+            socket.send(Message::Binary(pcm_chunk(8000, 20, 16000))).await.unwrap();
+        }
+        socket.send(Message::Text("FINISH".to_string())).await.unwrap();
+
+        let mut partials = 0;
+        loop {
+            let reply = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+                .await
+                .expect("no final result before timeout")
+                .unwrap()
+                .unwrap();
+            let Message::Text(text) = reply else {
+                continue;
+            };
+            let message: serde_json::Value = serde_json::from_str(&text).unwrap();
+            match message["type"].as_str() {
+                Some("partial") => {
+                    assert_eq!(message["result"], test_support::MOCK_TRANSCRIPT);
+                    partials += 1;
+                }
+                Some("final") => break,
+                other => panic!("unexpected message type {:?}", other),
+            }
+        }
+        assert!(partials >= 1);
+        assert!(partials < chunks, "{} partials for {} chunks", partials, chunks);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_fails_stream() {
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut state = test_support::test_state();
+        state.config.ws_max_message_bytes = 1024;
+        let addr = test_support::spawn_app(state).await;
+
+        let mut socket = test_support::ws_connect(addr, "/api/v1/transcribe/stream").await;
+
+        let _ = socket.send(Message::Binary(vec![0u8; 4096])).await;
+
+        let mut messages = Vec::new();
+        while let Ok(Some(Ok(msg))) =
+            tokio::time::timeout(std::time::Duration::from_secs(5), socket.next()).await
+        {
+            match msg {
+                Message::Text(text) => {
+                    messages.push(serde_json::from_str::<serde_json::Value>(&text).unwrap())
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+
+        assert_eq!(messages.len(), 1, "{:?}", messages);
+        assert_eq!(messages[0]["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_exceeding_total_audio_cap_closes_stream() {
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut state = test_support::test_state();
+        state.config.vad_hang_ms = 0;
+        state.config.stream_max_audio_bytes = 4096;
+        let addr = test_support::spawn_app(state).await;
+
+        let mut socket = test_support::ws_connect(addr, "/api/v1/transcribe/stream").await;
+
+        for _ in 0..3 {
+            // The server may already have closed by the third chunk
+            let _ = socket.send(Message::Binary(vec![0u8; 2048])).await;
+        }
+
+        let mut error = None;
+        let mut closed = false;
+        while let Ok(Some(msg)) =
+            tokio::time::timeout(std::time::Duration::from_secs(5), socket.next()).await
+        {
+            match msg {
+                Ok(Message::Text(text)) => {
+                    let message: serde_json::Value = serde_json::from_str(&text).unwrap();
+                    error = Some(message);
+                }
+                Ok(Message::Close(_)) | Err(_) => {
+                    closed = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let error = error.expect("expected an error message before close");
+        assert_eq!(error["type"], "error");
+        assert!(error["error"].as_str().unwrap().contains("4096 byte limit"));
+        assert!(closed);
+    }
+
+    #[tokio::test]
+    async fn test_close_code_matches_how_stream_ended() {
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut state = test_support::test_state();
+        state.config.vad_hang_ms = 0;
+        state.config.stream_idle_timeout_secs = 1;
+        let addr = test_support::spawn_app(state).await;
+
+        // Sends `frames`, then waits for the server's close frame
+        let close_code = |frames: Vec<Message>| async move {
+            let mut socket = test_support::ws_connect(addr, "/api/v1/transcribe/stream").await;
+            for frame in frames {
+                socket.send(frame).await.unwrap();
+            }
+            while let Ok(Some(Ok(msg))) =
+                tokio::time::timeout(std::time::Duration::from_secs(5), socket.next()).await
+            {
+                if let Message::Close(frame) = msg {
+                    let frame = frame.expect("close frame should carry a code");
+                    return (u16::from(frame.code), frame.reason.to_string());
+                }
+            }
+            panic!("stream ended without a close frame");
+        };
+
+        let (code, reason) = close_code(vec![
+            Message::Binary(vec![0u8; 3200]),
+            Message::Text("FINISH".to_string()),
+        ])
+        .await;
+        assert_eq!(code, 1000, "{}", reason);
+
+        // Silent client: dropped after the idle timeout
+        let (code, reason) = close_code(Vec::new()).await;
+        assert_eq!(code, 1008);
+        assert!(reason.contains("No data received"), "{}", reason);
+
+        let (code, _) = close_code(vec![Message::Text("FINISH".to_string())]).await;
+        assert_eq!(code, 1008);
+    }
+
+    /// Backend whose model another request is still loading
+    struct LoadingSpeechToText;
+
+    #[async_trait::async_trait]
+    impl SpeechToText for LoadingSpeechToText {
+        async fn transcribe(&self, _audio: Vec<u8>) -> anyhow::Result<Transcript> {
+            Err(ModelLoading.into())
+        }
+
+        async fn transcribe_streaming(&self, _chunks: Vec<Vec<u8>>) -> anyhow::Result<Transcript> {
+            Err(ModelLoading.into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_while_model_loading_says_try_again() {
+        use tokio_tungstenite::tungstenite::Message;
+
+        let mut state = test_support::test_state();
+        state.config.vad_hang_ms = 0;
+        state.stt_service = Arc::new(LoadingSpeechToText);
+        let addr = test_support::spawn_app(state).await;
+
+        let mut socket = test_support::ws_connect(addr, "/api/v1/transcribe/stream").await;
+        socket.send(Message::Binary(vec![0u8; 3200])).await.unwrap();
+        socket.send(Message::Text("FINISH".to_string())).await.unwrap();
+
+        let mut error = None;
+        let mut close = None;
+        while let Ok(Some(Ok(msg))) =
+            tokio::time::timeout(std::time::Duration::from_secs(5), socket.next()).await
+        {
+            match msg {
+                Message::Text(text) => {
+                    error = Some(serde_json::from_str::<StreamingMessage>(&text).unwrap())
+                }
+                Message::Close(frame) => {
+                    close = frame.map(|frame| u16::from(frame.code));
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let error = error.expect("error message before close");
+        assert_eq!(error.r#type, "error");
+        assert_eq!(error.code, Some(503));
+        assert_eq!(close, Some(close_code::AGAIN));
+    }
+
+    /// Counts recognizer runs, answering like `MockSpeechToText`
+    #[derive(Default)]
+    struct CountingSpeechToText {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl SpeechToText for CountingSpeechToText {
+        async fn transcribe(&self, audio: Vec<u8>) -> anyhow::Result<Transcript> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            MockSpeechToText::new("one oolong please").transcribe(audio).await
+        }
+
+        async fn transcribe_streaming(&self, chunks: Vec<Vec<u8>>) -> anyhow::Result<Transcript> {
+            MockSpeechToText::new("one oolong please").transcribe_streaming(chunks).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identical_uploads_transcribed_once() {
+        let stt = Arc::new(CountingSpeechToText::default());
+        let mut state = test_support::test_state();
+        state.stt_service = stt.clone();
+        let state = Arc::new(state);
+
+        let upload = |audio: Vec<u8>| {
+            transcribe_batch(
+                State(state.clone()),
+                Extension(test_support::session_owner()),
+                Query(TranscriptionRequest {
+                    language: None,
+                    speak: false,
+                    callback_url: None,
+                    normalize: false,
+                    debug: false,
+                }),
+                HeaderMap::new(),
+                Body::from(audio),
+            )
+        };
+
+        for _ in 0..2 {
+            let response = upload(test_wav(16000, 1, 800)).await.into_response();
+            let body = test_support::body_json(response).await;
+            assert_eq!(body["text"], "one oolong please");
+        }
+        assert_eq!(stt.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let _ = upload(test_wav(16000, 1, 1600)).await;
+        assert_eq!(stt.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_callback_url_must_be_http() {
+        let state = Arc::new(test_support::test_state());
+
+        let response = start_transcription_job(
+            state,
+            test_support::session_owner(),
+            "file:///etc/passwd".to_string(),
+            None,
+            false,
+            ReceivedUpload {
+                data: UploadData::Memory(b"RIFF".to_vec()),
+                head: b"RIFF".to_vec(),
+                digest: String::new(),
+            },
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_private_callback_url_rejected_by_default() {
+        let mut state = test_support::test_state();
+        state.transcription_jobs = TranscriptionJobService::new(60).unwrap();
+
+        let response = start_transcription_job(
+            Arc::new(state),
+            test_support::session_owner(),
+            "http://169.254.169.254/latest/meta-data/".to_string(),
+            None,
+            false,
+            ReceivedUpload {
+                data: UploadData::Memory(b"RIFF".to_vec()),
+                head: b"RIFF".to_vec(),
+                digest: String::new(),
+            },
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = test_support::body_json(response).await;
+        assert!(body["error"].as_str().unwrap().contains("private"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_job_polled_only_by_its_key() {
+        let state = Arc::new(test_support::test_state());
+        let owner = test_support::session_owner();
+        let id = state.transcription_jobs.create(owner).await;
+
+        let response = get_transcription_job(State(state.clone()), Extension(owner), Path(id))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let other = SessionOwner::from_api_key("another-tenant");
+        let response = get_transcription_job(State(state), Extension(other), Path(id))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_unspecified_language_uses_config_default() {
+        let mut state = test_support::test_state();
+        state.config.default_language = "es".to_string();
+
+        let response = transcription_response(&state, "hola".to_string(), None, 1.0);
+        assert_eq!(response.language, "es");
+
+        let response = transcription_response(&state, "hello".to_string(), Some("en".to_string()), 1.0);
+        assert_eq!(response.language, "en");
+    }
+
+    #[tokio::test]
+    async fn test_probe_lists_ranked_language_candidates() {
+        use tower::ServiceExt;
+
+        let mut state = test_support::test_state();
+        state.config.default_language = "en".to_string();
+        state.config.vosk_models = vec![
+            ("es".to_string(), "/models/es".to_string()),
+            ("de".to_string(), "/models/de".to_string()),
+        ];
+        state.stt_service = Arc::new(MockSpeechToText::new("hola, el té es para ti and the cake"));
+        let app = crate::build_router(Arc::new(state));
+
+        let request = axum::http::Request::post("/api/v1/audio/probe")
+            .header("x-api-key", test_support::API_KEY)
+            .body(Body::from(&b"RIFFfake"[..]))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = test_support::body_json(response).await;
+        let candidates = body["language_candidates"].as_array().unwrap();
+        let scores: Vec<f64> = candidates.iter().map(|c| c["score"].as_f64().unwrap()).collect();
+        assert_eq!(candidates.len(), 3);
+        assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", scores);
+        assert_eq!(candidates[0]["language"], "es");
+        assert_eq!(body["language"], candidates[0]["language"]);
+
+        // Transcription picks the same top candidate unless the request names a language
+        let transcribe = |uri: &str| {
+            axum::http::Request::post(uri)
+                .header("x-api-key", test_support::API_KEY)
+                .body(Body::from(&b"RIFFfake"[..]))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(transcribe("/api/v1/transcriptions")).await.unwrap();
+        assert_eq!(test_support::body_json(response).await["language"], "es");
+        let response =
+            app.oneshot(transcribe("/api/v1/transcriptions?language=de")).await.unwrap();
+        assert_eq!(test_support::body_json(response).await["language"], "de");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_job_returns_404() {
+        let state = Arc::new(test_support::test_state());
+
+        let response = get_transcription_job(
+            State(state),
+            Extension(test_support::session_owner()),
+            Path(Uuid::new_v4()),
+        )
+            .await
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 }
@@ -0,0 +1,700 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::voice_chat::{speech_text, TTS_SKIPPED_QUOTA};
+use crate::{
+    models::ErrorResponse,
+    services::{
+        circuit_breaker::CircuitOpen,
+        emotion,
+        elevenlabs_service::{is_valid_voice_id, TtsOptions, TtsQueueTimeout},
+        llm_service::{LlmError, LlmOptions},
+        tts_quota::TtsQuotaExceeded,
+        voice_session_service::{SessionOwner, SessionSettings, Turn},
+    },
+    AppState,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RegenerateParams {
+    /// Return the new reply as MP3 audio instead of JSON
+    #[serde(default)]
+    pub speak: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegenerateResponse {
+    pub voice_session_id: Uuid,
+    pub reply: String,
+    /// "quota" when `?speak=true` was asked for but the voice is over its character quota
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tts_skipped: Option<&'static str>,
+}
+
+/// POST /api/v1/voice-sessions/:id/regenerate
+/// Re-runs the LLM on the last user message and replaces the last assistant turn.
+/// Like the read-only endpoints it doesn't open sessions: an unknown id is 404.
+pub async fn regenerate_reply(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Path(session_id): Path<Uuid>,
+    Query(params): Query<RegenerateParams>,
+) -> Result<Response, RegenerateError> {
+    match state.voice_sessions.check_owner(session_id, owner).await {
+        None => return Err(RegenerateError::SessionNotFound),
+        Some(Err(_)) => return Err(RegenerateError::SessionForbidden),
+        Some(Ok(())) => {}
+    }
+    let turns = state.voice_sessions.get_turns(session_id).await;
+    if turns.is_empty() {
+        return Err(RegenerateError::SessionNotFound);
+    }
+
+    // The session must end with a user -> assistant exchange
+    let [.., user_turn, assistant_turn] = turns.as_slice() else {
+        return Err(RegenerateError::NothingToRegenerate);
+    };
+    if assistant_turn.role != "assistant" || user_turn.role != "user" {
+        return Err(RegenerateError::NothingToRegenerate);
+    }
+
+    let history: Vec<(String, String)> = turns[..turns.len() - 2]
+        .iter()
+        .map(|turn| turn.to_llm_message())
+        .collect();
+
+    state
+        .voice_sessions
+        .record_llm_call(session_id)
+        .await
+        .map_err(|_| RegenerateError::SessionRateLimited)?;
+
+    info!("Regenerating last reply for voice session {}", session_id);
+    let settings = state.voice_sessions.get_settings(session_id).await;
+    let styles = &state.config.tts_emotion_styles;
+    let options = LlmOptions {
+        temperature: settings.temperature,
+        system_prompt: settings.system_prompt,
+        tag_instruction: emotion::tag_instruction(styles),
+        ..Default::default()
+    };
+    let tagged = state
+        .llm_service
+        .generate_voice_response(&history, &user_turn.content, &options)
+        .await
+        .map_err(|e| {
+            error!("LLM regeneration failed: {}", e);
+            RegenerateError::from(e)
+        })?;
+    // Spoken in the tone of its first tag, shown and kept without any of them
+    let (style, _) = emotion::style_for(&tagged, styles);
+    let reply = emotion::strip_tags(&tagged, styles);
+
+    if !state
+        .voice_sessions
+        .replace_last_assistant(session_id, assistant_turn.id, &reply)
+        .await
+    {
+        // The session moved on (or expired) while the LLM was running
+        warn!("Voice session {} changed while its reply was regenerated", session_id);
+        return Err(RegenerateError::SessionChanged);
+    }
+
+    let mut tts_skipped = None;
+    if params.speak {
+        let options = TtsOptions {
+            voice_id: state.voice_sessions.get_voice(session_id).await,
+            style,
+            ..Default::default()
+        };
+        match state
+            .elevenlabs_service
+            .text_to_speech_with(&speech_text(&state, &reply), &options)
+            .await
+        {
+            Ok(audio) => {
+                return Ok((StatusCode::OK, [(header::CONTENT_TYPE, "audio/mpeg")], audio)
+                    .into_response())
+            }
+            // Over the voice's character quota: fall back to the text reply below
+            Err(e) if e.downcast_ref::<TtsQuotaExceeded>().is_some() => {
+                warn!("{}", e);
+                tts_skipped = Some(TTS_SKIPPED_QUOTA);
+            }
+            Err(e) => {
+                if e.downcast_ref::<CircuitOpen>().is_some()
+                    || e.downcast_ref::<TtsQueueTimeout>().is_some()
+                {
+                    return Err(RegenerateError::TtsUnavailable);
+                }
+                error!("TTS generation failed: {}", e);
+                return Err(RegenerateError::TtsFailed);
+            }
+        }
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(RegenerateResponse {
+            voice_session_id: session_id,
+            reply,
+            tts_skipped,
+        }),
+    )
+        .into_response())
+}
+
+/// Upper bound accepted for `temperature` (OpenRouter's range is 0-2)
+const MAX_TEMPERATURE: f32 = 2.0;
+pub(crate) const MAX_SYSTEM_PROMPT_CHARS: usize = 4000;
+
+/// Partial update; omitted fields keep their current value
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateSettingsRequest {
+    pub temperature: Option<f32>,
+    pub voice_id: Option<String>,
+    /// An empty string clears the override (back to Tea's persona)
+    pub system_prompt: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionSettingsResponse {
+    pub voice_session_id: Uuid,
+    pub voice_id: Option<String>,
+    #[serde(flatten)]
+    pub settings: SessionSettings,
+}
+
+/// PATCH /api/v1/voice-sessions/:id/settings
+/// Tune temperature, voice and system prompt for the session's following turns
+pub async fn update_session_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<UpdateSettingsRequest>,
+) -> Result<Json<SessionSettingsResponse>, SettingsError> {
+    if let Some(temperature) = request.temperature {
+        if !(0.0..=MAX_TEMPERATURE).contains(&temperature) {
+            return Err(SettingsError::InvalidTemperature);
+        }
+    }
+    if let Some(voice_id) = &request.voice_id {
+        if !is_valid_voice_id(voice_id) {
+            return Err(SettingsError::InvalidVoiceId);
+        }
+    }
+    let system_prompt = request.system_prompt.map(|prompt| prompt.trim().to_string());
+    if let Some(prompt) = &system_prompt {
+        if prompt.chars().count() > MAX_SYSTEM_PROMPT_CHARS {
+            return Err(SettingsError::SystemPromptTooLong);
+        }
+    }
+
+    state
+        .voice_sessions
+        .claim(session_id, owner)
+        .await
+        .map_err(|_| SettingsError::SessionForbidden)?;
+
+    if let Some(voice_id) = &request.voice_id {
+        state.voice_sessions.set_voice(session_id, voice_id).await;
+    }
+    let settings = state
+        .voice_sessions
+        .update_settings(session_id, |settings| {
+            if let Some(temperature) = request.temperature {
+                settings.temperature = Some(temperature);
+            }
+            if let Some(prompt) = system_prompt {
+                settings.system_prompt = (!prompt.is_empty()).then_some(prompt);
+            }
+        })
+        .await;
+    info!("Updated settings for voice session {}: {:?}", session_id, settings);
+
+    Ok(Json(SessionSettingsResponse {
+        voice_session_id: session_id,
+        voice_id: state.voice_sessions.get_voice(session_id).await,
+        settings,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryTurn {
+    pub id: Uuid,
+    pub role: String,
+    pub content: String,
+    /// When the turn was added, RFC 3339 in UTC
+    pub timestamp: String,
+}
+
+impl From<Turn> for HistoryTurn {
+    fn from(turn: Turn) -> Self {
+        Self {
+            id: turn.id,
+            role: turn.role,
+            content: turn.content,
+            timestamp: turn.at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionHistoryResponse {
+    pub voice_session_id: Uuid,
+    pub turns: Vec<HistoryTurn>,
+}
+
+/// GET /api/v1/voice-sessions/:id/history
+/// The session's turns, oldest first, with when each was added
+pub async fn get_session_history(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<SessionHistoryResponse>, HistoryError> {
+    existing_session(&state, session_id, owner).await?;
+    let turns = state.voice_sessions.get_turns(session_id).await;
+    if turns.is_empty() {
+        return Err(HistoryError::SessionNotFound);
+    }
+
+    Ok(Json(SessionHistoryResponse {
+        voice_session_id: session_id,
+        turns: turns.into_iter().map(HistoryTurn::from).collect(),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct CancelTurnResponse {
+    pub voice_session_id: Uuid,
+    /// False when no turn of the session was being answered
+    pub cancelled: bool,
+}
+
+/// POST /api/v1/voice-sessions/:id/cancel
+/// Barge-in: stops the LLM and TTS work of the session's in-flight turns, which then
+/// answer 409. The interrupted exchange isn't added to the history.
+pub async fn cancel_turn(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<CancelTurnResponse>, HistoryError> {
+    existing_session(&state, session_id, owner).await?;
+
+    Ok(Json(CancelTurnResponse {
+        voice_session_id: session_id,
+        cancelled: state.voice_sessions.cancel_turns(session_id),
+    }))
+}
+
+/// Read-only endpoints don't open sessions: an unknown id is 404, not a new empty
+/// session claimed by the caller
+async fn existing_session(
+    state: &AppState,
+    session_id: Uuid,
+    owner: SessionOwner,
+) -> Result<(), HistoryError> {
+    match state.voice_sessions.check_owner(session_id, owner).await {
+        None => Err(HistoryError::SessionNotFound),
+        Some(Err(_)) => Err(HistoryError::SessionForbidden),
+        Some(Ok(())) => Ok(()),
+    }
+}
+
+#[derive(Debug)]
+pub enum HistoryError {
+    SessionNotFound,
+    SessionForbidden,
+}
+
+impl IntoResponse for HistoryError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            HistoryError::SessionNotFound => (StatusCode::NOT_FOUND, "Voice session not found"),
+            HistoryError::SessionForbidden => {
+                (StatusCode::FORBIDDEN, "Voice session belongs to another API key")
+            }
+        };
+
+        (
+            status,
+            Json(ErrorResponse::new(message.to_string(), status.as_u16())),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    InvalidTemperature,
+    InvalidVoiceId,
+    SystemPromptTooLong,
+    SessionForbidden,
+}
+
+impl IntoResponse for SettingsError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            SettingsError::InvalidTemperature => {
+                (StatusCode::BAD_REQUEST, "temperature must be between 0 and 2".to_string())
+            }
+            SettingsError::InvalidVoiceId => {
+                (StatusCode::BAD_REQUEST, "Invalid voice_id format".to_string())
+            }
+            SettingsError::SystemPromptTooLong => (
+                StatusCode::BAD_REQUEST,
+                format!("system_prompt must be at most {} characters", MAX_SYSTEM_PROMPT_CHARS),
+            ),
+            SettingsError::SessionForbidden => {
+                (StatusCode::FORBIDDEN, "Voice session belongs to another API key".to_string())
+            }
+        };
+
+        (
+            status,
+            Json(ErrorResponse::new(message, status.as_u16())),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug)]
+pub enum RegenerateError {
+    SessionNotFound,
+    SessionForbidden,
+    NothingToRegenerate,
+    /// Another turn was added (or the session expired) while the LLM was running
+    SessionChanged,
+    LlmFailed,
+    LlmUnavailable,
+    LlmRateLimited,
+    SessionRateLimited,
+    TtsFailed,
+    TtsUnavailable,
+}
+
+impl From<LlmError> for RegenerateError {
+    fn from(e: LlmError) -> Self {
+        match e {
+            LlmError::RateLimited(_) => RegenerateError::LlmRateLimited,
+            e if e.is_retryable() => RegenerateError::LlmUnavailable,
+            _ => RegenerateError::LlmFailed,
+        }
+    }
+}
+
+impl IntoResponse for RegenerateError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            RegenerateError::SessionNotFound => (StatusCode::NOT_FOUND, "Voice session not found"),
+            RegenerateError::SessionForbidden => {
+                (StatusCode::FORBIDDEN, "Voice session belongs to another API key")
+            }
+            RegenerateError::NothingToRegenerate => {
+                (StatusCode::BAD_REQUEST, "Last turn is not an assistant reply")
+            }
+            RegenerateError::SessionChanged => {
+                (StatusCode::CONFLICT, "Voice session changed while the reply was being regenerated")
+            }
+            RegenerateError::LlmFailed => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "LLM generation failed")
+            }
+            RegenerateError::LlmUnavailable => {
+                (StatusCode::SERVICE_UNAVAILABLE, "LLM is temporarily unavailable, try again later")
+            }
+            RegenerateError::LlmRateLimited => {
+                (StatusCode::TOO_MANY_REQUESTS, "LLM rate limit reached, try again later")
+            }
+            RegenerateError::SessionRateLimited => {
+                (StatusCode::TOO_MANY_REQUESTS, "Too many requests for this voice session, slow down")
+            }
+            RegenerateError::TtsFailed => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "Text-to-speech failed")
+            }
+            RegenerateError::TtsUnavailable => {
+                (StatusCode::SERVICE_UNAVAILABLE, "Text-to-speech is temporarily unavailable, try again later")
+            }
+        };
+
+        (
+            status,
+            Json(ErrorResponse::new(message.to_string(), status.as_u16())),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, MockUpstream};
+
+    #[tokio::test]
+    async fn test_regenerate_replaces_assistant_turn() {
+        let upstream = MockUpstream::start("A better answer.").await;
+        let state = Arc::new(test_support::test_state_with_upstream(&upstream));
+        let session_id = Uuid::new_v4();
+        state.voice_sessions.add_message(session_id, "user", "Tell me a joke").await;
+        state.voice_sessions.add_message(session_id, "assistant", "A bad answer.").await;
+
+        let response = regenerate_reply(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Path(session_id),
+            Query(RegenerateParams::default()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = test_support::body_json(response).await;
+        assert_eq!(body["reply"], "A better answer.");
+
+        let history = state.voice_sessions.get_history(session_id).await;
+        assert_eq!(
+            history,
+            vec![
+                ("user".to_string(), "Tell me a joke".to_string()),
+                ("assistant".to_string(), "A better answer.".to_string()),
+            ]
+        );
+
+        // The old reply isn't sent back to the LLM
+        let request = upstream.requests_to("/chat/completions")[0].json();
+        let messages = request["messages"].as_array().unwrap();
+        assert_eq!(messages.last().unwrap()["content"], "Tell me a joke");
+        assert!(messages.iter().all(|m| m["content"] != "A bad answer."));
+    }
+
+    #[tokio::test]
+    async fn test_regenerated_reply_tagged_for_tone_but_kept_untagged() {
+        let upstream = MockUpstream::start("[calm] Sorry, try this one.").await;
+        let mut state = test_support::test_state_with_upstream(&upstream);
+        state.config.tts_emotion_styles = vec![("calm".to_string(), 0.0)];
+        let state = Arc::new(state);
+        let session_id = Uuid::new_v4();
+        state.voice_sessions.add_message(session_id, "user", "Tell me a joke").await;
+        state.voice_sessions.add_message(session_id, "assistant", "A bad answer.").await;
+
+        let response = regenerate_reply(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Path(session_id),
+            Query(RegenerateParams::default()),
+        )
+        .await
+        .unwrap();
+
+        let body = test_support::body_json(response).await;
+        assert_eq!(body["reply"], "Sorry, try this one.");
+        let history = state.voice_sessions.get_history(session_id).await;
+        assert_eq!(history[1].1, "Sorry, try this one.");
+
+        let request = upstream.requests_to("/chat/completions")[0].json();
+        let system_prompt = request["messages"][0]["content"].as_str().unwrap();
+        assert!(system_prompt.contains("[calm]"), "{}", system_prompt);
+    }
+
+    #[tokio::test]
+    async fn test_patched_temperature_used_on_next_generation() {
+        let upstream = MockUpstream::start("Cooler now.").await;
+        let state = Arc::new(test_support::test_state_with_upstream(&upstream));
+        let session_id = Uuid::new_v4();
+        state.voice_sessions.add_message(session_id, "user", "Be less random").await;
+        state.voice_sessions.add_message(session_id, "assistant", "Banana!").await;
+
+        let update = UpdateSettingsRequest {
+            temperature: Some(0.25),
+            system_prompt: Some("You are a terse assistant.".to_string()),
+            ..Default::default()
+        };
+        let Json(response) = update_session_settings(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Path(session_id),
+            Json(update),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.settings.temperature, Some(0.25));
+
+        regenerate_reply(
+            State(state),
+            Extension(test_support::session_owner()),
+            Path(session_id),
+            Query(RegenerateParams::default()),
+        )
+        .await
+        .unwrap();
+
+        let request = upstream.requests_to("/chat/completions")[0].json();
+        assert_eq!(request["temperature"].as_f64(), Some(0.25));
+        assert_eq!(request["messages"][0]["content"], "You are a terse assistant.");
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_settings_rejected() {
+        let state = Arc::new(test_support::test_state());
+        let session_id = Uuid::new_v4();
+
+        for update in [
+            UpdateSettingsRequest { temperature: Some(2.5), ..Default::default() },
+            UpdateSettingsRequest { temperature: Some(-0.1), ..Default::default() },
+            UpdateSettingsRequest { voice_id: Some("../etc".to_string()), ..Default::default() },
+            UpdateSettingsRequest { system_prompt: Some("x".repeat(4001)), ..Default::default() },
+        ] {
+            let err = update_session_settings(
+                State(state.clone()),
+                Extension(test_support::session_owner()),
+                Path(session_id),
+                Json(update),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(state.voice_sessions.get_settings(session_id).await, SessionSettings::default());
+    }
+
+    #[tokio::test]
+    async fn test_history_has_ordered_rfc3339_timestamps() {
+        let state = Arc::new(test_support::test_state());
+        let session_id = Uuid::new_v4();
+        state.voice_sessions.add_message(session_id, "user", "Is oolong green or black?").await;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        state.voice_sessions.add_message(session_id, "assistant", "Somewhere in between!").await;
+
+        let Json(history) = get_session_history(
+            State(state),
+            Extension(test_support::session_owner()),
+            Path(session_id),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(history.voice_session_id, session_id);
+        let roles: Vec<&str> = history.turns.iter().map(|turn| turn.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant"]);
+        let times: Vec<chrono::DateTime<chrono::Utc>> = history
+            .turns
+            .iter()
+            .map(|turn| turn.timestamp.parse().expect("RFC 3339 timestamp"))
+            .collect();
+        assert!(times[0] < times[1]);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_session_is_404_without_opening_it() {
+        let state = Arc::new(test_support::test_state());
+
+        let err = get_session_history(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Path(Uuid::new_v4()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+
+        let err = cancel_turn(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Path(Uuid::new_v4()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+
+        let err = regenerate_reply(
+            State(state.clone()),
+            Extension(test_support::session_owner()),
+            Path(Uuid::new_v4()),
+            Query(RegenerateParams { speak: false }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+
+        // Looking doesn't open (and claim) the sessions
+        assert_eq!(state.voice_sessions.active_session_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_requires_assistant_last() {
+        let state = Arc::new(test_support::test_state());
+        let session_id = Uuid::new_v4();
+        state.voice_sessions.add_message(session_id, "user", "Hello?").await;
+
+        let err = regenerate_reply(
+            State(state),
+            Extension(test_support::session_owner()),
+            Path(session_id),
+            Query(RegenerateParams::default()),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_session_inaccessible_with_another_key() {
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let mut state = test_support::test_state();
+        state.config.api_keys = vec!["second_client_key".to_string()];
+        let state = Arc::new(state);
+        let app = crate::build_router(state.clone());
+        let session_id = Uuid::new_v4();
+        let patch_settings = |key: &str| {
+            Request::patch(format!("/api/v1/voice-sessions/{}/settings", session_id))
+                .header("x-api-key", key)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"temperature": 0.5}"#))
+                .unwrap()
+        };
+
+        // Key A opens the session
+        let response = app.clone().oneshot(patch_settings(test_support::API_KEY)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        state.voice_sessions.add_message(session_id, "user", "My secret").await;
+        state.voice_sessions.add_message(session_id, "assistant", "Safe with me.").await;
+
+        // Key B is a valid key but doesn't own the session
+        let response = app.clone().oneshot(patch_settings("second_client_key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let regenerate = Request::post(format!("/api/v1/voice-sessions/{}/regenerate", session_id))
+            .header("x-api-key", "second_client_key")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(regenerate).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let history = Request::get(format!("/api/v1/voice-sessions/{}/history", session_id))
+            .header("x-api-key", "second_client_key")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(history).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let cancel = Request::post(format!("/api/v1/voice-sessions/{}/cancel", session_id))
+            .header("x-api-key", "second_client_key")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(cancel).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        assert_eq!(state.voice_sessions.get_settings(session_id).await.temperature, Some(0.5));
+        assert_eq!(state.voice_sessions.get_turns(session_id).await.len(), 2);
+    }
+}
@@ -0,0 +1,295 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Extension, Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{stream::SplitSink, SinkExt, StreamExt};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::voice_chat::{run_turn, transcribe_in_time, VoiceChatError};
+use crate::{
+    middleware::ws_key_protocol,
+    models::{ErrorResponse, VoiceStreamMessage},
+    services::{
+        elevenlabs_service::{is_valid_voice_id, TtsOptions},
+        llm_service::reply_language_name,
+        voice_session_service::SessionOwner,
+    },
+    AppState,
+};
+
+/// Client text frame marking the end of an utterance
+const END_OF_UTTERANCE: &str = "END";
+
+/// Reply audio is streamed back in frames of this size
+const AUDIO_FRAME_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct VoiceStreamParams {
+    pub voice_session_id: Uuid,
+    pub voice_id: Option<String>,
+    /// Reply language hint for the LLM (e.g. "es")
+    pub language: Option<String>,
+}
+
+/// GET /voice-chat/stream (WebSocket)
+/// Full-duplex voice chat on one connection. The client sends raw 16-bit PCM
+/// as binary frames and "END" after each utterance; the server answers with
+/// "transcript" and "reply" messages, the reply MP3 as binary frames, then "audio_end".
+/// With `THINKING_AUDIO_PATH` set, a "thinking" message and the filler clip's frames
+/// are streamed right after the transcript while the reply is being generated.
+pub async fn voice_chat_stream(
+    State(state): State<Arc<AppState>>,
+    Extension(owner): Extension<SessionOwner>,
+    Query(params): Query<VoiceStreamParams>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if let Err(e) = state.voice_sessions.claim(params.voice_session_id, owner).await {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new(e.to_string(), 403)),
+        )
+            .into_response();
+    }
+    if let Some(language) = &params.language {
+        if reply_language_name(language).is_none() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new("Unsupported language".to_string(), 400)),
+            )
+                .into_response();
+        }
+    }
+    if let Some(voice_id) = &params.voice_id {
+        if !is_valid_voice_id(voice_id) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new("Invalid voice_id format".to_string(), 400)),
+            )
+                .into_response();
+        }
+        state.voice_sessions.set_voice(params.voice_session_id, voice_id).await;
+    }
+
+    // Oversized messages fail the socket before they are buffered in full
+    let max_message_bytes = state.config.ws_max_message_bytes;
+    ws.protocols(ws_key_protocol(&headers))
+        .max_message_size(max_message_bytes)
+        .max_frame_size(max_message_bytes)
+        .on_upgrade(move |socket| {
+            handle_voice_stream(socket, state, params.voice_session_id, params.language)
+        })
+}
+
+async fn handle_voice_stream(
+    socket: WebSocket,
+    state: Arc<AppState>,
+    session_id: Uuid,
+    language: Option<String>,
+) {
+    info!("Voice stream opened for session {}", session_id);
+    let (mut sender, mut receiver) = socket.split();
+    let mut utterance: Vec<Vec<u8>> = Vec::new();
+    let mut buffered_bytes = 0;
+
+    while let Some(msg) = receiver.next().await {
+        match msg {
+            Ok(Message::Binary(data)) => {
+                buffered_bytes += data.len();
+                if buffered_bytes > state.config.stream_max_audio_bytes {
+                    warn!("Voice stream for session {} exceeded its audio limits", session_id);
+                    let error = VoiceStreamMessage::error("Audio exceeds the size limit".to_string());
+                    let _ = send(&mut sender, error).await;
+                    let _ = sender.close().await;
+                    return;
+                }
+                utterance.push(data);
+            }
+            Ok(Message::Text(text)) if text == END_OF_UTTERANCE => {
+                let chunks = std::mem::take(&mut utterance);
+                buffered_bytes = 0;
+                let language = language.as_deref();
+                if respond_to_utterance(&mut sender, &state, session_id, language, chunks)
+                    .await
+                    .is_err()
+                {
+                    warn!("Voice stream client went away mid-reply");
+                    return;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Err(e) => {
+                error!("Voice stream WebSocket error: {}", e);
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    info!("Voice stream closed for session {}", session_id);
+}
+
+/// "error" frame for a failed turn, with the status `/voice-chat` would answer
+/// (503 while the speech model loads, so the client knows to retry)
+fn stream_error(e: &VoiceChatError) -> VoiceStreamMessage {
+    VoiceStreamMessage::error_with_code(e.message().to_string(), e.status().as_u16())
+}
+
+/// Run one turn and stream the results back; Err means the socket is gone
+async fn respond_to_utterance(
+    sender: &mut SplitSink<WebSocket, Message>,
+    state: &AppState,
+    session_id: Uuid,
+    language: Option<&str>,
+    chunks: Vec<Vec<u8>>,
+) -> Result<(), axum::Error> {
+    if chunks.is_empty() {
+        return send(sender, VoiceStreamMessage::error("No audio data received".to_string())).await;
+    }
+
+    let transcription = state.stt_service.transcribe_streaming(chunks);
+    let transcript = match transcribe_in_time(state, session_id, transcription).await {
+        Ok(transcript) => transcript,
+        Err(e) => return send(sender, stream_error(&e)).await,
+    };
+    send(sender, VoiceStreamMessage::transcript(transcript.text.clone())).await?;
+
+    let tts_options = TtsOptions {
+        voice_id: state.voice_sessions.get_voice(session_id).await,
+        ..Default::default()
+    };
+    // Play the filler clip while the LLM and TTS run instead of leaving dead air
+    let filler = async {
+        if let Some(clip) = &state.thinking_audio {
+            send(sender, VoiceStreamMessage::thinking()).await?;
+            for frame in clip.chunks(AUDIO_FRAME_BYTES) {
+                sender.send(Message::Binary(frame.to_vec())).await?;
+            }
+        }
+        Ok::<_, axum::Error>(())
+    };
+    let (filler, turn) = tokio::join!(
+        filler,
+        run_turn(state, session_id, &transcript, language, &tts_options)
+    );
+    filler?;
+    let turn = match turn {
+        Ok(turn) => turn,
+        Err(e) => return send(sender, stream_error(&e)).await,
+    };
+
+    send(sender, VoiceStreamMessage::reply(turn.reply)).await?;
+    // Over the voice's character quota the reply goes unspoken: no frames before audio_end
+    for frame in turn.audio.iter().flat_map(|audio| audio.chunks(AUDIO_FRAME_BYTES)) {
+        sender.send(Message::Binary(frame.to_vec())).await?;
+    }
+    send(sender, VoiceStreamMessage::audio_end()).await
+}
+
+async fn send(
+    sender: &mut SplitSink<WebSocket, Message>,
+    message: VoiceStreamMessage,
+) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(&message).expect("VoiceStreamMessage serializes");
+    sender.send(Message::Text(text)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{self, MockUpstream, MOCK_MP3, MOCK_TRANSCRIPT};
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[test]
+    fn test_loading_model_error_carries_503() {
+        let message = super::stream_error(&super::VoiceChatError::ModelLoading);
+        assert_eq!(message.r#type, "error");
+        assert_eq!(message.code, Some(503));
+    }
+
+    #[tokio::test]
+    async fn test_single_round_trip_over_socket() {
+        let upstream = MockUpstream::start("Lovely to hear from you!").await;
+        let addr = test_support::spawn_app(test_support::test_state_with_upstream(&upstream)).await;
+
+        let path = format!("/voice-chat/stream?voice_session_id={}", uuid::Uuid::new_v4());
+        let mut socket = test_support::ws_connect(addr, &path).await;
+
+        socket.send(Message::Binary(vec![0u8; 3200])).await.unwrap();
+        socket.send(Message::Text("END".to_string())).await.unwrap();
+
+        let mut texts = Vec::new();
+        let mut audio = Vec::new();
+        while let Some(msg) = socket.next().await {
+            match msg.unwrap() {
+                Message::Text(text) => {
+                    let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+                    let done = value["type"] == "audio_end";
+                    texts.push(value);
+                    if done {
+                        break;
+                    }
+                }
+                Message::Binary(data) => audio.extend_from_slice(&data),
+                _ => {}
+            }
+        }
+
+        assert_eq!(texts[0]["type"], "transcript");
+        assert_eq!(texts[0]["text"], MOCK_TRANSCRIPT);
+        assert_eq!(texts[1]["type"], "reply");
+        assert_eq!(texts[1]["text"], "Lovely to hear from you!");
+        assert_eq!(texts[2]["type"], "audio_end");
+        assert_eq!(audio, MOCK_MP3);
+    }
+
+    #[tokio::test]
+    async fn test_thinking_frames_precede_reply_frames() {
+        let upstream = MockUpstream::start("Let me think about that.").await;
+        let mut state = test_support::test_state_with_upstream(&upstream);
+        state.thinking_audio = Some(axum::body::Bytes::from_static(b"filler-clip"));
+        let addr = test_support::spawn_app(state).await;
+
+        let path = format!("/voice-chat/stream?voice_session_id={}", uuid::Uuid::new_v4());
+        let mut socket = test_support::ws_connect(addr, &path).await;
+
+        socket.send(Message::Binary(vec![0u8; 3200])).await.unwrap();
+        socket.send(Message::Text("END".to_string())).await.unwrap();
+
+        // Frames in arrival order: message types for text, payloads for binary
+        let mut frames = Vec::new();
+        while let Some(msg) = socket.next().await {
+            match msg.unwrap() {
+                Message::Text(text) => {
+                    let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+                    let kind = value["type"].as_str().unwrap().to_string();
+                    let done = kind == "audio_end";
+                    frames.push(kind.into_bytes());
+                    if done {
+                        break;
+                    }
+                }
+                Message::Binary(data) => frames.push(data),
+                _ => {}
+            }
+        }
+
+        let expected: Vec<&[u8]> = vec![
+            b"transcript",
+            b"thinking",
+            b"filler-clip",
+            b"reply",
+            MOCK_MP3,
+            b"audio_end",
+        ];
+        assert_eq!(frames, expected);
+    }
+}
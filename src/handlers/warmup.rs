@@ -0,0 +1,166 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::AppState;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WarmupParams {
+    /// Also make a one-token LLM call and a one-word TTS call
+    #[serde(default)]
+    pub probe: bool,
+}
+
+/// POST /api/v1/warmup
+/// Gets a fresh instance ready before it takes traffic: loads the speech model and
+/// checks PostgreSQL and Qdrant, all at once. With `?probe=true` it also opens the
+/// OpenRouter and ElevenLabs connections with a tiny request each (the TTS call uses
+/// a few characters of quota). Answers 200 when every component is ready, else 503,
+/// with each component's status and how long it took.
+pub async fn warmup(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<WarmupParams>,
+) -> impl IntoResponse {
+    info!("Warming up (probe: {})", params.probe);
+    let stt = async {
+        let mut status = timed(state.stt_service.warm_up()).await;
+        status["model_loaded"] =
+            json!(state.stt_service.is_model_loaded(&state.config.vosk_model_path));
+        status
+    };
+    let database = timed(state.database_service.health_check());
+    let qdrant = async {
+        match &state.rag_service {
+            Some(rag) => timed(rag.health_check()).await,
+            None => json!({ "status": "disabled" }),
+        }
+    };
+    let llm = async {
+        match params.probe {
+            true => timed(state.llm_service.ping()).await,
+            false => json!({ "status": "skipped" }),
+        }
+    };
+    let tts = async {
+        match params.probe {
+            true => timed(state.elevenlabs_service.text_to_speech("Hi")).await,
+            false => json!({ "status": "skipped" }),
+        }
+    };
+    let (stt, database, qdrant, llm, tts) = tokio::join!(stt, database, qdrant, llm, tts);
+
+    let components = json!({
+        "stt": stt,
+        "database": database,
+        "qdrant": qdrant,
+        "llm": llm,
+        "tts": tts,
+    });
+    let ready = components
+        .as_object()
+        .unwrap()
+        .values()
+        .all(|component| component["status"] != "error");
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    if !ready {
+        warn!("Warmup finished with failing components: {}", components);
+    }
+
+    (
+        status,
+        Json(json!({
+            "status": if ready { "ready" } else { "not_ready" },
+            "components": components,
+        })),
+    )
+}
+
+/// Run one warmup step, reporting `{"status": "ok", "latency_ms": ...}` or the error
+async fn timed<T, E: std::fmt::Display>(step: impl Future<Output = Result<T, E>>) -> Value {
+    let started = Instant::now();
+    let result = step.await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok(_) => json!({ "status": "ok", "latency_ms": latency_ms }),
+        Err(e) => json!({ "status": "error", "error": e.to_string(), "latency_ms": latency_ms }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::vosk_service::Transcript;
+    use crate::services::SpeechToText;
+    use crate::test_support;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    /// STT backend whose model only counts as loaded once warmed up
+    #[derive(Default)]
+    struct LazyModel(AtomicBool);
+
+    #[async_trait::async_trait]
+    impl SpeechToText for LazyModel {
+        async fn transcribe(&self, _audio: Vec<u8>) -> anyhow::Result<Transcript> {
+            Err(anyhow::anyhow!("not used in this test"))
+        }
+
+        async fn transcribe_streaming(&self, _chunks: Vec<Vec<u8>>) -> anyhow::Result<Transcript> {
+            Err(anyhow::anyhow!("not used in this test"))
+        }
+
+        fn is_model_loaded(&self, _model_path: &str) -> bool {
+            self.0.load(Ordering::SeqCst)
+        }
+
+        async fn warm_up(&self) -> anyhow::Result<()> {
+            self.0.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warmup_loads_model() {
+        let stt = Arc::new(LazyModel::default());
+        let mut state = test_support::test_state();
+        state.stt_service = stt.clone();
+        let app = crate::build_router(Arc::new(state));
+        assert!(!stt.is_model_loaded("/models/any"));
+
+        // Warming up costs a model load and LLM/TTS calls, so it needs a key
+        let anonymous = Request::post("/api/v1/warmup").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(anonymous).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(!stt.is_model_loaded("/models/any"));
+
+        let request = Request::post("/api/v1/warmup")
+            .header("x-api-key", test_support::API_KEY)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert!(stt.is_model_loaded("/models/any"));
+        let body = test_support::body_json(response).await;
+        let components = &body["components"];
+        assert_eq!(components["stt"]["status"], "ok");
+        assert_eq!(components["stt"]["model_loaded"], true);
+        assert_eq!(components["qdrant"]["status"], "disabled");
+        assert_eq!(components["llm"]["status"], "skipped");
+        assert_eq!(components["tts"]["status"], "skipped");
+        // No database in unit tests, so the lazy pool can't connect
+        assert!(components["database"]["status"].is_string());
+    }
+}
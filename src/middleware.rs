@@ -1,46 +1,208 @@
-// API key authentication middleware
+// API key authentication and access logging middleware
 use axum::{
-    extract::Request,
-    http::StatusCode,
+    extract::{ConnectInfo, Query, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
+use serde::Deserialize;
 use serde_json::json;
-use tracing::warn;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+use uuid::Uuid;
 
-pub async fn check_api_key(
+use crate::{services::voice_session_service::SessionOwner, AppState};
+
+/// WebSocket subprotocol carrying the API key: `Sec-WebSocket-Protocol: api-key.<key>`
+pub const WS_KEY_PROTOCOL_PREFIX: &str = "api-key.";
+
+/// The caller's IP address.
+/// Behind `trusted_hops` proxies, each appends the address it saw to `X-Forwarded-For`,
+/// so the client is the entry that many places from the right; anything to its left
+/// came from the client and is ignored. `X-Real-IP` is the fallback. Without trusted
+/// proxies forwarding headers are ignored as spoofable and the socket peer address is used.
+pub fn client_ip(request: &Request, trusted_hops: usize) -> Option<IpAddr> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    if trusted_hops > 0 {
+        if let Some(ip) = forwarded_ip(request.headers(), trusted_hops) {
+            return Some(ip);
+        }
+    }
+    peer
+}
+
+fn forwarded_ip(headers: &HeaderMap, trusted_hops: usize) -> Option<IpAddr> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let forwarded_for = header("x-forwarded-for").and_then(|v| {
+        // Shorter than the proxy chain means it didn't come through all of it
+        let entries: Vec<&str> = v.split(',').collect();
+        let client = entries.len().checked_sub(trusted_hops)?;
+        entries[client].trim().parse().ok()
+    });
+    forwarded_for.or_else(|| header("x-real-ip").and_then(|ip| ip.trim().parse().ok()))
+}
+
+/// One structured `info!` line per request (target `access`) with method, path,
+/// status, latency and request id. The id comes from `X-Request-Id` when the client
+/// sends one, otherwise it is generated, and is echoed back on the response.
+/// Paths in `ACCESS_LOG_EXCLUDE` (default `/health`) are not logged.
+pub async fn access_log(
+    State(state): State<Arc<AppState>>,
     request: Request,
     next: Next,
+) -> Response {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| !id.is_empty())
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let started = Instant::now();
+
+    let mut response = next.run(request).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert("x-request-id", value);
+    }
+    if !state.config.access_log_exclude.contains(&path) {
+        info!(
+            target: "access",
+            method = %method,
+            path = %path,
+            status = response.status().as_u16(),
+            latency_ms = started.elapsed().as_millis() as u64,
+            request_id = %request_id,
+            "request"
+        );
+    }
+    response
+}
+
+/// Accepts `API_KEY`, any of `API_KEYS` or an active key from the `api_keys` table,
+/// and tags the request with a `SessionOwner` for the key so handlers can keep voice
+/// sessions private to it. With `UNIFORM_AUTH_ERRORS` every rejection is the same 401,
+/// so callers can't tell a missing key from a wrong one.
+pub async fn check_api_key(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
 ) -> Result<Response, ApiKeyError> {
-    let api_key = request
+    match authorize(&state, &mut request) {
+        Ok(()) => Ok(next.run(request).await),
+        Err(_) if state.config.uniform_auth_errors => Err(ApiKeyError::Unauthorized),
+        Err(e) => Err(e),
+    }
+}
+
+/// The offered `api-key.<key>` WebSocket subprotocol, if any. A browser drops the socket
+/// unless the server picks one of the protocols it offered, so the WebSocket handlers
+/// echo this one back.
+pub fn ws_key_protocol(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all("sec-websocket-protocol")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .find(|protocol| protocol.starts_with(WS_KEY_PROTOCOL_PREFIX))
+        .map(str::to_string)
+}
+
+/// The caller's key from `x-api-key`. Browsers can't set headers on a WebSocket upgrade,
+/// so there the `api_key` query parameter or an `api-key.<key>` subprotocol also count.
+fn request_api_key(request: &Request) -> Option<String> {
+    let header = request
         .headers()
         .get("x-api-key")
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
+    if header.is_some() || !is_websocket_upgrade(request.headers()) {
+        return header;
+    }
+
+    let query_key = Query::<WsKeyParams>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(params)| params.api_key);
+    query_key.or_else(|| {
+        ws_key_protocol(request.headers())
+            .map(|protocol| protocol[WS_KEY_PROTOCOL_PREFIX.len()..].to_string())
+    })
+}
+
+#[derive(Deserialize)]
+struct WsKeyParams {
+    api_key: Option<String>,
+}
+
+fn is_websocket_upgrade(headers: &HeaderMap) -> bool {
+    headers
+        .get("upgrade")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+}
+
+/// Whether the request may go ahead: public path, admin key on an admin path, or a
+/// valid client key (recorded as the request's `SessionOwner`)
+fn authorize(state: &AppState, request: &mut Request) -> Result<(), ApiKeyError> {
+    let api_key = request_api_key(request);
 
     let path = request.uri().path();
+    let caller = client_ip(request, state.config.trusted_proxy_hops)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
     
     // Check if path is public (no auth required)
-    if path == "/health" || path == "/status" {
-        return Ok(next.run(request).await);
+    if path == "/health"
+        || path == "/status"
+        || path == "/ready"
+        || path == "/version"
+        || path == "/metrics"
+        || path == "/api/v1/models"
+    {
+        return Ok(());
+    }
+
+    // Admin endpoints only accept the admin key
+    if path.starts_with("/api/v1/admin/") {
+        return match (api_key, &state.config.admin_api_key) {
+            (Some(key), Some(admin_key)) if &key == admin_key => Ok(()),
+            (None, _) => {
+                warn!("Missing API key on {} from {}", path, caller);
+                Err(ApiKeyError::MissingKey)
+            }
+            _ => {
+                warn!("Non-admin key used on {} from {}", path, caller);
+                Err(ApiKeyError::AdminRequired)
+            }
+        };
     }
 
     match api_key {
         Some(key) => {
             // Validate the key
-            let stored_key = std::env::var("API_KEY")
-                .unwrap_or_else(|_| "dev_key_12345_change_in_production".to_string());
-
-            if key == stored_key {
-                Ok(next.run(request).await)
+            if key == state.config.api_key
+                || state.config.api_keys.contains(&key)
+                || state.api_key_store.contains(&key)
+            {
+                request.extensions_mut().insert(SessionOwner::from_api_key(&key));
+                Ok(())
             } else {
-                warn!("Invalid API key attempt on {}", path);
+                warn!("Invalid API key attempt on {} from {}", path, caller);
                 Err(ApiKeyError::InvalidKey)
             }
         }
         None => {
-            warn!("Missing API key on {}", path);
+            warn!("Missing API key on {} from {}", path, caller);
             Err(ApiKeyError::MissingKey)
         }
     }
@@ -49,6 +211,9 @@ pub async fn check_api_key(
 pub enum ApiKeyError {
     MissingKey,
     InvalidKey,
+    AdminRequired,
+    /// Any of the above, with `UNIFORM_AUTH_ERRORS`
+    Unauthorized,
 }
 
 impl IntoResponse for ApiKeyError {
@@ -62,6 +227,14 @@ impl IntoResponse for ApiKeyError {
                 StatusCode::FORBIDDEN,
                 "Invalid API key",
             ),
+            ApiKeyError::AdminRequired => (
+                StatusCode::FORBIDDEN,
+                "Admin API key required",
+            ),
+            ApiKeyError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                "Unauthorized",
+            ),
         };
 
         let body = Json(json!({
@@ -73,3 +246,212 @@ impl IntoResponse for ApiKeyError {
         (status, body).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request_from(peer: &str, headers: &[(&str, &str)]) -> Request {
+        let mut builder = axum::http::Request::builder().uri("/voice-chat");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let mut request = builder.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+        request
+    }
+
+    #[test]
+    fn test_query_key_only_counts_on_websocket_upgrades() {
+        let request = |upgrade: Option<&str>| {
+            let mut builder = axum::http::Request::builder().uri("/api/v1/stats?api_key=k1");
+            if let Some(upgrade) = upgrade {
+                builder = builder.header("upgrade", upgrade);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        assert_eq!(request_api_key(&request(Some("websocket"))).as_deref(), Some("k1"));
+        // Plain requests keep to the header, so keys stay out of URLs and logs
+        assert_eq!(request_api_key(&request(None)), None);
+    }
+
+    #[test]
+    fn test_direct_connection_uses_peer_address() {
+        let request = request_from("203.0.113.7:51000", &[]);
+        assert_eq!(client_ip(&request, 0), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(client_ip(&request, 1), Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_header_ignored_without_trusted_proxies() {
+        let request = request_from(
+            "203.0.113.7:51000",
+            &[("x-forwarded-for", "1.2.3.4"), ("x-real-ip", "5.6.7.8")],
+        );
+        assert_eq!(client_ip(&request, 0), Some("203.0.113.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_trusted_proxies_pick_forwarded_entry_from_the_right() {
+        // The client sent "1.2.3.4" itself; the two proxies appended the rest
+        let request = request_from(
+            "10.0.0.2:40000",
+            &[("x-forwarded-for", "1.2.3.4, 198.51.100.4, 10.0.0.9")],
+        );
+        assert_eq!(client_ip(&request, 1), Some("10.0.0.9".parse().unwrap()));
+        assert_eq!(client_ip(&request, 2), Some("198.51.100.4".parse().unwrap()));
+
+        let request = request_from("10.0.0.2:40000", &[("x-real-ip", "2001:db8::1")]);
+        assert_eq!(client_ip(&request, 1), Some("2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_forwarded_header_shorter_than_proxy_chain_ignored() {
+        let request = request_from("10.0.0.2:40000", &[("x-forwarded-for", "1.2.3.4")]);
+        assert_eq!(client_ip(&request, 2), Some("10.0.0.2".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_trusted_proxy_falls_back_to_peer_on_garbage() {
+        let request = request_from("10.0.0.2:40000", &[("x-forwarded-for", "not-an-ip")]);
+        assert_eq!(client_ip(&request, 1), Some("10.0.0.2".parse().unwrap()));
+    }
+
+    async fn auth_statuses(uniform: bool) -> Vec<(StatusCode, String)> {
+        use tower::ServiceExt;
+
+        let mut state = crate::test_support::test_state();
+        state.config.uniform_auth_errors = uniform;
+        let app = crate::build_router(Arc::new(state));
+
+        let mut statuses = Vec::new();
+        for (path, key) in [
+            ("/api/v1/stats", None),
+            ("/api/v1/stats", Some("wrong-key")),
+            ("/api/v1/admin/sessions", Some(crate::test_support::API_KEY)),
+        ] {
+            let mut request = axum::http::Request::get(path);
+            if let Some(key) = key {
+                request = request.header("x-api-key", key);
+            }
+            let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+            let status = response.status();
+            let body = crate::test_support::body_json(response).await;
+            statuses.push((status, body["error"].as_str().unwrap().to_string()));
+        }
+        statuses
+    }
+
+    #[tokio::test]
+    async fn test_missing_and_invalid_keys_distinguished_by_default() {
+        let statuses = auth_statuses(false).await;
+
+        assert_eq!(statuses[0], (StatusCode::UNAUTHORIZED, "Missing x-api-key header".into()));
+        assert_eq!(statuses[1], (StatusCode::FORBIDDEN, "Invalid API key".into()));
+        assert_eq!(statuses[2], (StatusCode::FORBIDDEN, "Admin API key required".into()));
+    }
+
+    #[tokio::test]
+    async fn test_uniform_auth_errors_give_one_401() {
+        let statuses = auth_statuses(true).await;
+
+        for status in &statuses {
+            assert_eq!(*status, (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()));
+        }
+    }
+
+    /// Collects formatted log output for assertions
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_access_log_line_has_numeric_latency() {
+        use tower::ServiceExt;
+
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = crate::build_router(Arc::new(crate::test_support::test_state()));
+        for path in ["/health", "/status"] {
+            let request = axum::http::Request::builder()
+                .uri(path)
+                .header("x-request-id", "req-123")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.headers()["x-request-id"], "req-123");
+        }
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().filter(|line| line.contains(" access: ")).collect();
+        assert_eq!(lines.len(), 1, "health checks are excluded: {}", output);
+
+        let line = lines[0];
+        assert!(line.contains("method=GET"), "{}", line);
+        assert!(line.contains("path=/status"), "{}", line);
+        assert!(line.contains("status=200"), "{}", line);
+        assert!(line.contains("request_id=req-123"), "{}", line);
+        let latency = line
+            .split("latency_ms=")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("latency_ms field");
+        assert!(latency.parse::<u64>().is_ok(), "{}", line);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_database_keys_checked_by_hash_until_deactivated() {
+        use crate::services::{api_key_store::hash_api_key, ApiKeyStore};
+        use tower::ServiceExt;
+
+        let mut state = crate::test_support::test_state();
+        state.database_service = crate::test_support::database_service().await;
+        state.api_key_store = ApiKeyStore::new(state.database_service.clone());
+        let state = Arc::new(state);
+        let app = crate::build_router(state.clone());
+
+        let key = format!("tenant-{}", Uuid::new_v4());
+        let id = state
+            .database_service
+            .create_api_key(&hash_api_key(&key), "test tenant", &["voice"])
+            .await
+            .unwrap();
+        // Unknown job ids are 404 once the key is accepted
+        let job_status = |key: String| {
+            let request = axum::http::Request::builder()
+                .uri(format!("/api/v1/transcriptions/{}", Uuid::new_v4()))
+                .header("x-api-key", key)
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+
+        state.api_key_store.refresh().await.unwrap();
+        assert_eq!(job_status(key.clone()).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+        state.database_service.set_api_key_active(id, false).await.unwrap();
+        state.api_key_store.refresh().await.unwrap();
+        assert_eq!(job_status(key).await.unwrap().status(), StatusCode::FORBIDDEN);
+    }
+}
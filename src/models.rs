@@ -1,12 +1,50 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Query parameters accepted by the batch transcription endpoint
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TranscriptionRequest {
     pub language: Option<String>,
+    /// Read the transcription back as TTS audio instead of returning JSON
+    #[serde(default)]
+    pub speak: bool,
+    /// Process asynchronously and POST the result here when done
+    pub callback_url: Option<String>,
+    /// Convert number words to digits and capitalize sentence starts
+    #[serde(default)]
+    pub normalize: bool,
+    /// Include the recognizer's full result under `raw` in the JSON response
+    #[serde(default)]
+    pub debug: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Query parameters accepted by the multi-file batch endpoint
+#[derive(Debug, Default, Deserialize)]
+pub struct BatchTranscriptionParams {
+    /// Convert number words to digits and capitalize sentence starts
+    #[serde(default)]
+    pub normalize: bool,
+    /// Send an NDJSON line per file as soon as it finishes instead of one JSON reply
+    #[serde(default)]
+    pub stream: bool,
+}
+
+/// Outcome for one file of a multi-file batch: `text` on success, `error` otherwise
+#[derive(Debug, Serialize)]
+pub struct BatchFileResult {
+    /// Position of the file in the upload, counting from 0
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionSegment {
     pub id: usize,
     pub start: f32,
@@ -14,16 +52,31 @@ pub struct TranscriptionSegment {
     pub text: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionResponse {
     pub id: String,
     pub text: String,
     pub segments: Vec<TranscriptionSegment>,
     pub language: String,
     pub duration: f32,
+    /// Mean word confidence, omitted when the recognizer gave no word info
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
     pub timestamp: String,
 }
 
+/// `/voice-chat` response for clients sending `Accept: application/json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoiceChatResponse {
+    pub transcription: String,
+    pub reply: String,
+    pub audio_base64: String,
+    pub audio_format: String,
+    /// Set ("quota") when the reply was not spoken and `audio_base64` is empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tts_skipped: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -31,14 +84,84 @@ pub struct ErrorResponse {
     pub timestamp: String,
 }
 
+/// A recognized word with its timing and confidence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordSegment {
+    pub word: String,
+    pub start: f32,
+    pub end: f32,
+    pub conf: f32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StreamingMessage {
-    pub r#type: String, // "partial", "final", "error"
+    pub r#type: String, // "partial", "final", "resumed", "error"
     pub result: Option<String>,
     pub error: Option<String>,
+    /// Word timings, only present on final results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segments: Option<Vec<WordSegment>>,
+    /// HTTP-style status of an error the client can retry (503 while the model loads)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<u16>,
     pub timestamp: String,
 }
 
+/// Server -> client text frames on `/voice-chat/stream`
+/// (filler audio is sent as binary frames after "thinking",
+/// reply audio as binary frames between "reply" and "audio_end")
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoiceStreamMessage {
+    pub r#type: String, // "transcript", "thinking", "reply", "audio_end", "error"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// HTTP-style status of an error, as `/voice-chat` would answer it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<u16>,
+    pub timestamp: String,
+}
+
+impl VoiceStreamMessage {
+    fn new(r#type: &str, text: Option<String>, error: Option<String>) -> Self {
+        Self {
+            r#type: r#type.to_string(),
+            text,
+            error,
+            code: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn transcript(text: String) -> Self {
+        Self::new("transcript", Some(text), None)
+    }
+
+    pub fn thinking() -> Self {
+        Self::new("thinking", None, None)
+    }
+
+    pub fn reply(text: String) -> Self {
+        Self::new("reply", Some(text), None)
+    }
+
+    pub fn audio_end() -> Self {
+        Self::new("audio_end", None, None)
+    }
+
+    pub fn error(error: String) -> Self {
+        Self::new("error", None, Some(error))
+    }
+
+    pub fn error_with_code(error: String, code: u16) -> Self {
+        Self {
+            code: Some(code),
+            ..Self::error(error)
+        }
+    }
+}
+
 impl TranscriptionResponse {
     pub fn new(text: String, language: String, duration: f32) -> Self {
         Self {
@@ -47,6 +170,7 @@ impl TranscriptionResponse {
             segments: vec![],
             language,
             duration,
+            confidence: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
     }
@@ -68,6 +192,8 @@ impl StreamingMessage {
             r#type: "partial".to_string(),
             result: Some(result),
             error: None,
+            segments: None,
+            code: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
     }
@@ -77,18 +203,49 @@ impl StreamingMessage {
             r#type: "final".to_string(),
             result: Some(result),
             error: None,
+            segments: None,
+            code: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
     }
 
+    /// First message on a reconnect that picked up a dropped stream's utterance
+    pub fn resumed() -> Self {
+        Self {
+            r#type: "resumed".to_string(),
+            result: None,
+            error: None,
+            segments: None,
+            code: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn final_with_segments(result: String, segments: Vec<WordSegment>) -> Self {
+        Self {
+            segments: Some(segments),
+            ..Self::final_result(result)
+        }
+    }
+
     pub fn error(error: String) -> Self {
         Self {
             r#type: "error".to_string(),
             result: None,
             error: Some(error),
+            segments: None,
+            code: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
     }
+
+    /// Error the client should retry later, e.g. while the speech model loads
+    pub fn unavailable(error: String) -> Self {
+        Self {
+            code: Some(503),
+            ..Self::error(error)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -146,6 +303,34 @@ mod tests {
         assert_eq!(msg.error, Some("processing failed".to_string()));
     }
 
+    #[test]
+    fn test_streaming_final_with_segments() {
+        let segments = vec![
+            WordSegment { word: "hello".to_string(), start: 0.0, end: 0.42, conf: 0.98 },
+            WordSegment { word: "world".to_string(), start: 0.42, end: 0.9, conf: 0.87 },
+        ];
+        let msg = StreamingMessage::final_with_segments("hello world".to_string(), segments);
+
+        let json = serde_json::to_value(&msg).expect("Failed to serialize");
+        assert_eq!(json["type"], "final");
+        let segments = json["segments"].as_array().expect("segments should be an array");
+        assert_eq!(segments.len(), 2);
+        for segment in segments {
+            assert!(segment["word"].is_string());
+            assert!(segment["start"].is_number());
+            assert!(segment["end"].is_number());
+            assert!(segment["conf"].is_number());
+            assert!(segment["start"].as_f64() <= segment["end"].as_f64());
+        }
+    }
+
+    #[test]
+    fn test_streaming_message_omits_segments_when_absent() {
+        let msg = StreamingMessage::final_result("complete".to_string());
+        let json = serde_json::to_value(&msg).expect("Failed to serialize");
+        assert!(json.get("segments").is_none());
+    }
+
     #[test]
     fn test_transcription_response_serialization() {
         let response = TranscriptionResponse::new(
@@ -0,0 +1,75 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use super::database_service::{DatabaseService, DbError};
+
+/// How `api_keys.key_hash` is derived from a key: lowercase hex SHA-256
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Active keys from the `api_keys` table, held as hashes and reloaded every
+/// `API_KEY_REFRESH_SECS` so requests never wait on the database. New and
+/// deactivated keys take effect at the next refresh.
+#[derive(Clone)]
+pub struct ApiKeyStore {
+    db: Arc<DatabaseService>,
+    hashes: Arc<RwLock<HashSet<String>>>,
+}
+
+impl ApiKeyStore {
+    pub fn new(db: Arc<DatabaseService>) -> Self {
+        Self {
+            db,
+            hashes: Arc::new(RwLock::new(HashSet::new())),
+        }
+    }
+
+    /// Whether `key` is an active key as of the last refresh
+    pub fn contains(&self, key: &str) -> bool {
+        self.hashes.read().unwrap().contains(&hash_api_key(key))
+    }
+
+    /// Reload the active keys; on failure the previous set stays in use
+    pub async fn refresh(&self) -> Result<usize, DbError> {
+        let hashes: HashSet<String> = self.db.active_api_key_hashes().await?.into_iter().collect();
+        let count = hashes.len();
+        *self.hashes.write().unwrap() = hashes;
+        debug!("Loaded {} active API keys from the database", count);
+        Ok(count)
+    }
+
+    /// Refresh every `every`, starting one period from now (call `refresh` first at startup)
+    pub fn start_refresh_task(self, every: Duration) {
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + every;
+            let mut interval = tokio::time::interval_at(start, every);
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.refresh().await {
+                    warn!("Failed to refresh API keys, keeping the previous set: {}", e);
+                }
+            }
+        });
+
+        info!("Started API key refresh background task ({:?})", every);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_hash_is_hex_sha256() {
+        assert_eq!(
+            hash_api_key("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(hash_api_key("tenant-key").len(), 64);
+    }
+}
@@ -0,0 +1,326 @@
+use anyhow::Result;
+use tracing::debug;
+
+/// Container of an uploaded audio blob, sniffed from its leading bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioContainer {
+    Wav,
+    /// Matroska/WebM, as produced by browser `MediaRecorder`
+    WebM,
+    Ogg,
+    Unknown,
+}
+
+/// `Content-Type` essence, lowercased, with WAV aliases folded into `audio/wav`
+pub fn canonical_mime(content_type: &str) -> String {
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    match essence.as_str() {
+        "audio/x-wav" | "audio/wave" | "audio/vnd.wave" => "audio/wav".to_string(),
+        _ => essence,
+    }
+}
+
+impl AudioContainer {
+    pub fn detect(audio: &[u8]) -> Self {
+        if audio.len() >= 12 && &audio[0..4] == b"RIFF" && &audio[8..12] == b"WAVE" {
+            AudioContainer::Wav
+        } else if audio.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+            AudioContainer::WebM
+        } else if audio.starts_with(b"OggS") {
+            AudioContainer::Ogg
+        } else {
+            AudioContainer::Unknown
+        }
+    }
+
+    /// MIME type for serving the original upload back
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            AudioContainer::Wav => "audio/wav",
+            AudioContainer::WebM => "audio/webm",
+            AudioContainer::Ogg => "audio/ogg",
+            AudioContainer::Unknown => "application/octet-stream",
+        }
+    }
+
+    /// File extension for stored uploads
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioContainer::Wav => "wav",
+            AudioContainer::WebM => "webm",
+            AudioContainer::Ogg => "ogg",
+            AudioContainer::Unknown => "bin",
+        }
+    }
+}
+
+/// Decode an uploaded audio blob to mono i16 PCM at the recognizer's `sample_rate`.
+/// WAV must already be mono at that rate; WebM/Ogg Opus needs the `opus` feature.
+pub fn decode_to_pcm(audio: &[u8], sample_rate: u32) -> Result<Vec<i16>> {
+    let container = AudioContainer::detect(audio);
+    debug!("Detected audio container: {:?}", container);
+
+    match container {
+        AudioContainer::WebM | AudioContainer::Ogg => decode_opus(audio, sample_rate),
+        // Anything unrecognized goes through the WAV reader for its error message
+        AudioContainer::Wav | AudioContainer::Unknown => decode_wav(audio, sample_rate),
+    }
+}
+
+/// Outcome of checking the start of a WAV upload before the rest has arrived
+#[derive(Debug, PartialEq)]
+pub enum HeaderCheck {
+    /// More bytes are needed to reach the end of the header
+    Incomplete,
+    Valid,
+    Invalid(String),
+}
+
+/// Validate the WAV header in `prefix` (the first bytes of an upload) against the
+/// recognizer's format without needing the sample data
+pub fn check_wav_header(prefix: &[u8], sample_rate: u32) -> HeaderCheck {
+    match hound::WavReader::new(std::io::Cursor::new(prefix)) {
+        Ok(reader) => match check_wav_spec(reader.spec(), sample_rate) {
+            Ok(()) => HeaderCheck::Valid,
+            Err(e) => HeaderCheck::Invalid(e.to_string()),
+        },
+        // Reading from memory only fails when the bytes run out
+        Err(hound::Error::IoError(_)) => HeaderCheck::Incomplete,
+        Err(e) => HeaderCheck::Invalid(format!("Failed to read WAV: {}", e)),
+    }
+}
+
+/// Reject WAV audio the recognizer can't take as-is. Any of the sample formats read
+/// by `i16_samples` is fine; rate and channels must match.
+pub fn check_wav_spec(spec: hound::WavSpec, sample_rate: u32) -> Result<()> {
+    if spec.channels != 1 || spec.sample_rate != sample_rate {
+        return Err(anyhow::anyhow!(
+            "Audio must be {}Hz mono WAV. Got: {}Hz {}ch",
+            sample_rate,
+            spec.sample_rate,
+            spec.channels
+        ));
+    }
+    let supported = match spec.sample_format {
+        hound::SampleFormat::Int => matches!(spec.bits_per_sample, 8..=32),
+        hound::SampleFormat::Float => spec.bits_per_sample == 32,
+    };
+    if !supported {
+        return Err(anyhow::anyhow!(
+            "Unsupported WAV sample format: {}-bit {:?}",
+            spec.bits_per_sample,
+            spec.sample_format
+        ));
+    }
+    Ok(())
+}
+
+/// The samples of `reader` as the 16-bit PCM the recognizer takes: 8-bit and 17 to
+/// 32-bit integers are rescaled, 32-bit floats are clamped to [-1, 1] and scaled
+pub fn i16_samples<'a, R: std::io::Read + 'a>(
+    reader: hound::WavReader<R>,
+) -> Result<Box<dyn Iterator<Item = Result<i16>> + 'a>> {
+    let spec = reader.spec();
+    let samples: Box<dyn Iterator<Item = hound::Result<i16>> + 'a> =
+        match (spec.sample_format, spec.bits_per_sample) {
+            (hound::SampleFormat::Int, 16) => Box::new(reader.into_samples::<i16>()),
+            (hound::SampleFormat::Int, 8) => {
+                Box::new(reader.into_samples::<i8>().map(|s| s.map(|s| (s as i16) << 8)))
+            }
+            (hound::SampleFormat::Int, bits @ 17..=32) => {
+                let shift = bits - 16;
+                Box::new(reader.into_samples::<i32>().map(move |s| s.map(|s| (s >> shift) as i16)))
+            }
+            (hound::SampleFormat::Float, 32) => {
+                Box::new(reader.into_samples::<f32>().map(|s| s.map(float_to_i16)))
+            }
+            (format, bits) => {
+                return Err(anyhow::anyhow!("Unsupported WAV sample format: {}-bit {:?}", bits, format))
+            }
+        };
+    Ok(Box::new(
+        samples.map(|s| s.map_err(|e| anyhow::anyhow!("Failed to read audio samples: {}", e))),
+    ))
+}
+
+fn float_to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+fn decode_wav(audio: &[u8], sample_rate: u32) -> Result<Vec<i16>> {
+    let reader = hound::WavReader::new(std::io::Cursor::new(audio))
+        .map_err(|e| anyhow::anyhow!("Failed to read WAV: {}", e))?;
+
+    check_wav_spec(reader.spec(), sample_rate)?;
+
+    i16_samples(reader)?.collect()
+}
+
+#[cfg(not(feature = "opus"))]
+fn decode_opus(_audio: &[u8], sample_rate: u32) -> Result<Vec<i16>> {
+    Err(anyhow::anyhow!(
+        "WebM/Ogg Opus audio is not supported by this build (enable the `opus` feature), send {}Hz mono WAV",
+        sample_rate
+    ))
+}
+
+/// Demux with symphonia and decode with libopus straight to the target rate in mono
+/// (libopus resamples and downmixes internally)
+#[cfg(feature = "opus")]
+fn decode_opus(audio: &[u8], sample_rate: u32) -> Result<Vec<i16>> {
+    use audiopus::{coder::Decoder, packet::Packet, Channels, MutSignals, SampleRate};
+    use symphonia::core::{
+        codecs::CODEC_TYPE_OPUS, errors::Error as SymphoniaError, formats::FormatOptions,
+        io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+    };
+
+    let source = MediaSourceStream::new(
+        Box::new(std::io::Cursor::new(audio.to_vec())),
+        Default::default(),
+    );
+    let mut format = symphonia::default::get_probe()
+        .format(&Hint::new(), source, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| anyhow::anyhow!("Failed to read audio container: {}", e))?
+        .format;
+
+    let track_id = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec == CODEC_TYPE_OPUS)
+        .map(|track| track.id)
+        .ok_or_else(|| anyhow::anyhow!("No Opus audio track found"))?;
+
+    let opus_rate = SampleRate::try_from(sample_rate as i32).map_err(|_| {
+        anyhow::anyhow!("Opus cannot decode to {}Hz (use 8k/12k/16k/24k/48k)", sample_rate)
+    })?;
+    let mut decoder = Decoder::new(opus_rate, Channels::Mono)
+        .map_err(|e| anyhow::anyhow!("Failed to create Opus decoder: {}", e))?;
+
+    // 120ms is the longest Opus frame
+    let mut frame = vec![0i16; sample_rate as usize * 120 / 1000];
+    let mut samples = Vec::new();
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(anyhow::anyhow!("Failed to read audio packet: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let input = Packet::try_from(packet.buf())
+            .map_err(|e| anyhow::anyhow!("Invalid Opus packet: {}", e))?;
+        let output = MutSignals::try_from(&mut frame[..])
+            .map_err(|e| anyhow::anyhow!("Invalid Opus output buffer: {}", e))?;
+        let decoded = decoder
+            .decode(Some(input), output, false)
+            .map_err(|e| anyhow::anyhow!("Failed to decode Opus packet: {}", e))?;
+        samples.extend_from_slice(&frame[..decoded]);
+    }
+
+    debug!("Decoded {} samples of Opus audio", samples.len());
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEBM_OPUS: &[u8] = include_bytes!("../../tests/fixtures/tone_opus.webm");
+
+    #[test]
+    fn test_wav_header_checked_from_prefix() {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        hound::WavWriter::new(&mut cursor, spec).unwrap().finalize().unwrap();
+        let wav = cursor.into_inner();
+
+        assert_eq!(check_wav_header(&wav[..20], 16000), HeaderCheck::Incomplete);
+        assert_eq!(check_wav_header(&wav, 16000), HeaderCheck::Valid);
+        assert!(matches!(check_wav_header(&wav, 8000), HeaderCheck::Invalid(_)));
+        assert!(matches!(check_wav_header(b"RIFF\0\0\0\0AVI LIST", 16000), HeaderCheck::Invalid(_)));
+    }
+
+    fn wav_with<S: hound::Sample + Copy>(
+        bits_per_sample: u16,
+        sample_format: hound::SampleFormat,
+        samples: &[S],
+    ) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample,
+            sample_format,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn test_float_wav_converted_to_i16() {
+        let wav = wav_with(32, hound::SampleFormat::Float, &[0.0f32, 0.5, -1.0, 1.5]);
+
+        assert_eq!(check_wav_header(&wav, 16000), HeaderCheck::Valid);
+        assert_eq!(decode_to_pcm(&wav, 16000).unwrap(), vec![0, 16384, -32767, 32767]);
+    }
+
+    #[test]
+    fn test_24_bit_and_8_bit_wav_converted_to_i16() {
+        let wav = wav_with(24, hound::SampleFormat::Int, &[0i32, 0x40_0000, -0x80_0000, 0x7F_FFFF]);
+        assert_eq!(check_wav_header(&wav, 16000), HeaderCheck::Valid);
+        assert_eq!(decode_to_pcm(&wav, 16000).unwrap(), vec![0, 0x4000, -0x8000, 0x7FFF]);
+
+        let wav = wav_with(8, hound::SampleFormat::Int, &[0i8, 64, -128]);
+        assert_eq!(decode_to_pcm(&wav, 16000).unwrap(), vec![0, 0x4000, -0x8000]);
+    }
+
+    #[test]
+    fn test_detect_container() {
+        assert_eq!(AudioContainer::detect(b"RIFF\x24\x00\x00\x00WAVEfmt "), AudioContainer::Wav);
+        assert_eq!(AudioContainer::detect(WEBM_OPUS), AudioContainer::WebM);
+        assert_eq!(AudioContainer::detect(b"OggS\x00\x02"), AudioContainer::Ogg);
+        assert_eq!(AudioContainer::detect(&[0xFF, 0xFE]), AudioContainer::Unknown);
+    }
+
+    #[test]
+    fn test_canonical_mime() {
+        assert_eq!(canonical_mime("audio/x-wav"), "audio/wav");
+        assert_eq!(canonical_mime("Audio/WebM; codecs=opus"), "audio/webm");
+        assert_eq!(canonical_mime("image/png"), "image/png");
+    }
+
+    #[test]
+    fn test_webm_opus_skips_wav_reader() {
+        let result = decode_to_pcm(WEBM_OPUS, 16000);
+
+        #[cfg(feature = "opus")]
+        {
+            // 10 x 20ms frames at 16kHz
+            let samples = result.unwrap();
+            assert_eq!(samples.len(), 10 * 320);
+            assert_eq!(decode_to_pcm(WEBM_OPUS, 8000).unwrap().len(), 10 * 160);
+            assert!(samples.iter().any(|&s| s != 0));
+        }
+
+        #[cfg(not(feature = "opus"))]
+        {
+            let err = result.unwrap_err().to_string();
+            assert!(!err.contains("Failed to read WAV"), "{}", err);
+            assert!(err.contains("opus"), "{}", err);
+        }
+    }
+}
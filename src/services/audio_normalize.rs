@@ -0,0 +1,86 @@
+/// Peak level chunks are brought to, as a fraction of full scale (about -3 dBFS)
+const TARGET_PEAK: f32 = 0.7;
+/// Most a chunk is amplified (about +26 dB), so hiss in a near-silent chunk stays hiss
+const MAX_GAIN: f32 = 20.0;
+/// Chunks peaking below this are treated as silence and don't move the gain
+const NOISE_FLOOR: i32 = 64;
+/// Per-chunk limit on how fast the gain may rise again after a loud chunk
+const RELEASE: f32 = 1.25;
+
+/// Basic automatic gain control for quiet recordings, applied to each chunk before it
+/// reaches the recognizer. The first voiced chunk sets the gain outright; later chunks
+/// drop it immediately when they are louder and raise it gradually when they are quieter.
+/// Samples are clamped to the i16 range, so nothing wraps around when amplified.
+#[derive(Debug, Clone, Default)]
+pub struct AudioNormalizer {
+    gain: Option<f32>,
+}
+
+impl AudioNormalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&mut self, chunk: &mut [i16]) {
+        let peak = chunk.iter().map(|&sample| (sample as i32).abs()).max().unwrap_or(0);
+        if peak >= NOISE_FLOOR {
+            let wanted = (TARGET_PEAK * i16::MAX as f32 / peak as f32).min(MAX_GAIN);
+            self.gain = Some(match self.gain {
+                Some(gain) if wanted > gain => (gain * RELEASE).min(wanted),
+                _ => wanted,
+            });
+        }
+
+        let Some(gain) = self.gain else {
+            return;
+        };
+        for sample in chunk.iter_mut() {
+            let scaled = (*sample as f32 * gain).round();
+            *sample = scaled.clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak(samples: &[i16]) -> i32 {
+        samples.iter().map(|&sample| (sample as i32).abs()).max().unwrap()
+    }
+
+    fn sine(amplitude: f32, len: usize) -> Vec<i16> {
+        (0..len)
+            .map(|i| (amplitude * (i as f32 * 0.05).sin()) as i16)
+            .collect()
+    }
+
+    #[test]
+    fn test_quiet_audio_raised_toward_target_peak() {
+        let mut samples = sine(2000.0, 2000);
+        let before = peak(&samples);
+
+        AudioNormalizer::new().apply(&mut samples);
+
+        let target = (TARGET_PEAK * i16::MAX as f32) as i32;
+        assert!(peak(&samples) > before * 5);
+        assert!((peak(&samples) - target).abs() < 50, "peak {}", peak(&samples));
+    }
+
+    #[test]
+    fn test_gain_never_clips_and_silence_left_alone() {
+        let mut normalizer = AudioNormalizer::new();
+        let mut silence = vec![0i16, 3, -5, 2];
+        normalizer.apply(&mut silence);
+        assert_eq!(silence, vec![0, 3, -5, 2]);
+
+        let mut quiet = sine(500.0, 2000);
+        normalizer.apply(&mut quiet);
+
+        // A sudden loud chunk drops the gain instead of wrapping around
+        let mut loud = sine(30000.0, 2000);
+        normalizer.apply(&mut loud);
+        assert!(peak(&loud) <= i16::MAX as i32);
+        assert!(peak(&loud) <= (TARGET_PEAK * i16::MAX as f32) as i32 + 1);
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+use super::audio_decode::AudioContainer;
+
+/// Keeps uploaded voice-turn audio on disk under `AUDIO_STORAGE_DIR`, one file per message
+#[derive(Debug, Clone)]
+pub struct AudioStore {
+    dir: PathBuf,
+}
+
+impl AudioStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        info!("Storing voice turn audio in {}", dir.display());
+        Self { dir }
+    }
+
+    /// Write `audio` for `message_id`; returns the file path and detected content type
+    pub async fn save(&self, message_id: Uuid, audio: &[u8]) -> Result<(PathBuf, &'static str)> {
+        let container = AudioContainer::detect(audio);
+        let path = self
+            .dir
+            .join(format!("{}.{}", message_id, container.extension()));
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("Failed to create audio directory {}", self.dir.display()))?;
+        tokio::fs::write(&path, audio)
+            .await
+            .with_context(|| format!("Failed to write audio to {}", path.display()))?;
+
+        debug!("Stored {} bytes of audio for message {}", audio.len(), message_id);
+        Ok((path, container.content_type()))
+    }
+
+    /// Open a previously stored file for streaming
+    pub async fn open(&self, path: &Path) -> Result<tokio::fs::File> {
+        tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to open stored audio {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_save_and_open_roundtrip() {
+        let store = AudioStore::new(std::env::temp_dir().join(format!("rusty-tea-audio-{}", Uuid::new_v4())));
+        let message_id = Uuid::new_v4();
+        let audio = b"RIFF\x24\x00\x00\x00WAVEfmt fake".to_vec();
+
+        let (path, content_type) = store.save(message_id, &audio).await.unwrap();
+        assert_eq!(content_type, "audio/wav");
+        assert!(path.ends_with(format!("{}.wav", message_id)));
+
+        let mut stored = Vec::new();
+        store.open(&path).await.unwrap().read_to_end(&mut stored).await.unwrap();
+        assert_eq!(stored, audio);
+    }
+}
@@ -0,0 +1,249 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Returned instead of calling a downstream whose breaker is open
+#[derive(Debug, thiserror::Error)]
+#[error("{0} is temporarily unavailable (circuit open)")]
+pub struct CircuitOpen(pub &'static str);
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Per-downstream circuit breaker.
+/// Closed: calls pass through. After `failure_threshold` consecutive failures it opens
+/// and rejects calls for `cooldown`. Then a single probe is let through (half-open):
+/// success closes the breaker, failure re-opens it, and so does a probe that is dropped
+/// before reporting (timed out, cancelled, client gone).
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    name: &'static str,
+    failure_threshold: u32,
+    cooldown: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &'static str, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name,
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            inner: Arc::new(Mutex::new(Inner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+            })),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Ask permission to call the downstream; report the outcome through the returned call
+    pub fn check(&self) -> Result<BreakerCall, CircuitOpen> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            BreakerState::Closed => Ok(BreakerCall {
+                breaker: self.clone(),
+                probe: false,
+            }),
+            BreakerState::Open => {
+                let cooled_down = inner
+                    .opened_at
+                    .map(|at| at.elapsed() >= self.cooldown)
+                    .unwrap_or(true);
+                if cooled_down {
+                    info!("{} circuit half-open, probing", self.name);
+                    inner.state = BreakerState::HalfOpen;
+                    Ok(BreakerCall {
+                        breaker: self.clone(),
+                        probe: true,
+                    })
+                } else {
+                    Err(CircuitOpen(self.name))
+                }
+            }
+            // Only one probe at a time
+            BreakerState::HalfOpen => Err(CircuitOpen(self.name)),
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != BreakerState::Closed {
+            info!("{} circuit closed", self.name);
+        }
+        inner.state = BreakerState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures += 1;
+
+        let trip = inner.state == BreakerState::HalfOpen
+            || inner.consecutive_failures >= self.failure_threshold;
+        if trip && inner.state != BreakerState::Open {
+            warn!(
+                "{} circuit opened after {} consecutive failures (cooldown {:?})",
+                self.name, inner.consecutive_failures, self.cooldown
+            );
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// A probe went away without an outcome: open again and wait another cooldown
+    fn abandon_probe(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state == BreakerState::HalfOpen {
+            warn!("{} circuit probe abandoned, re-opening", self.name);
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// A call let through by `CircuitBreaker::check`. Report how it went with `record`,
+/// `success` or `failure`; dropping a half-open probe unreported re-opens the breaker
+/// so it can't stay half-open forever.
+#[derive(Debug)]
+pub struct BreakerCall {
+    breaker: CircuitBreaker,
+    probe: bool,
+}
+
+impl BreakerCall {
+    pub fn success(mut self) {
+        self.probe = false;
+        self.breaker.record_success();
+    }
+
+    pub fn failure(mut self) {
+        self.probe = false;
+        self.breaker.record_failure();
+    }
+
+    /// Record the outcome of the downstream call
+    pub fn record<T, E>(self, result: &Result<T, E>) {
+        match result {
+            Ok(_) => self.success(),
+            Err(_) => self.failure(),
+        }
+    }
+}
+
+impl Drop for BreakerCall {
+    fn drop(&mut self) {
+        if self.probe {
+            self.breaker.abandon_probe();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_failures() {
+        let breaker = CircuitBreaker::new("test", 3, Duration::from_secs(60));
+
+        for _ in 0..2 {
+            breaker.check().unwrap().failure();
+        }
+        assert_eq!(breaker.state(), BreakerState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.check().is_err());
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let breaker = CircuitBreaker::new("test", 2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_half_opens_after_cooldown() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_millis(20));
+        breaker.record_failure();
+        assert!(breaker.check().is_err());
+
+        std::thread::sleep(Duration::from_millis(30));
+
+        // First caller is the probe, others keep failing fast
+        let probe = breaker.check().unwrap();
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(breaker.check().is_err());
+
+        probe.success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.check().is_ok());
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_millis(20));
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+
+        breaker.check().unwrap().failure();
+
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.check().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_probe_lets_next_call_through() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_millis(20));
+        breaker.record_failure();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        // The probe's call is abandoned, as when a stage timeout drops its future
+        let call = {
+            let breaker = breaker.clone();
+            async move {
+                let probe = breaker.check()?;
+                std::future::pending::<()>().await;
+                probe.success();
+                Ok::<_, CircuitOpen>(())
+            }
+        };
+        let timed_out = tokio::time::timeout(Duration::from_millis(5), call).await;
+        assert!(timed_out.is_err());
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        breaker.check().unwrap().success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_dropped_call_while_closed_changes_nothing() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_secs(60));
+        drop(breaker.check().unwrap());
+        assert_eq!(breaker.state(), BreakerState::Closed);
+    }
+}
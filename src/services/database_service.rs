@@ -1,11 +1,13 @@
 use sqlx::postgres::PgPoolOptions;
+use sqlx::migrate::MigrateError;
 use sqlx::PgPool;
-use std::error::Error;
-use tracing::info;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::voice_session_service::{SessionOwner, Turn, TurnStore};
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Message {
     pub id: Uuid,
@@ -15,6 +17,49 @@ pub struct Message {
     pub created_at: DateTime<Utc>,
 }
 
+/// Totals for the internal dashboard (`GET /api/v1/stats`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub conversations: i64,
+    pub messages: i64,
+    pub messages_last_24h: i64,
+}
+
+/// Why a database call failed, so handlers can answer 404 vs 503 vs 500
+#[derive(Debug, thiserror::Error)]
+pub enum DbError {
+    #[error("Row not found")]
+    NotFound,
+    /// A client-supplied message id already names a different message
+    #[error("Message {0} already exists with different content")]
+    MessageIdConflict(Uuid),
+    /// The conversation was started under another API key
+    #[error("Conversation {0} belongs to another API key")]
+    ConversationForbidden(Uuid),
+    /// PostgreSQL unreachable or the pool exhausted; usually worth retrying
+    #[error("Database connection failed: {0}")]
+    Connection(#[source] sqlx::Error),
+    #[error("Database query failed: {0}")]
+    Query(#[source] sqlx::Error),
+    #[error("Database migration failed: {0}")]
+    Migration(#[from] MigrateError),
+}
+
+impl From<sqlx::Error> for DbError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => DbError::NotFound,
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => DbError::Connection(e),
+            sqlx::Error::Migrate(e) => DbError::Migration(*e),
+            e => DbError::Query(e),
+        }
+    }
+}
+
 /// PostgreSQL database connection pool service
 /// Automatically runs migrations from `migrations/` folder on init
 pub struct DatabaseService {
@@ -23,7 +68,7 @@ pub struct DatabaseService {
 
 impl DatabaseService {
     /// Initialize database connection pool and run migrations
-    pub async fn new(database_url: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+    pub async fn new(database_url: &str) -> Result<Self, DbError> {
         info!("Connecting to PostgreSQL database: {}", database_url.split('@').last().unwrap_or("unknown"));
         
         let pool = PgPoolOptions::new()
@@ -46,13 +91,22 @@ impl DatabaseService {
         Ok(Self { pool })
     }
 
+    /// Build a service on a lazily-connected pool (no connection is made until first use)
+    #[cfg(test)]
+    pub fn connect_lazy(database_url: &str) -> Result<Self, DbError> {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect_lazy(database_url)?;
+        Ok(Self { pool })
+    }
+
     /// Get the connection pool
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
 
     /// Health check for database connection
-    pub async fn health_check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+    pub async fn health_check(&self) -> Result<(), DbError> {
         sqlx::query("SELECT 1")
             .fetch_one(&self.pool)
             .await?;
@@ -61,7 +115,7 @@ impl DatabaseService {
 
     /// Get conversation history (messages) for a given conversation_id
     /// Returns messages ordered by created_at ascending (oldest first)
-    pub async fn get_conversation_history(&self, conversation_id: Uuid) -> Result<Vec<Message>, Box<dyn Error + Send + Sync>> {
+    pub async fn get_conversation_history(&self, conversation_id: Uuid) -> Result<Vec<Message>, DbError> {
         let messages = sqlx::query_as::<_, Message>(
             "SELECT id, conversation_id, role, content, created_at 
              FROM messages 
@@ -75,32 +129,250 @@ impl DatabaseService {
         Ok(messages)
     }
 
-    /// Save a message to the database
+    /// Save a message to the database.
+    /// With a client-supplied `message_id` the save is idempotent: a retry with the
+    /// same id leaves the existing row alone and returns its id. The id reused for a
+    /// different message (other conversation, role or content) is `MessageIdConflict`.
     pub async fn save_message(
         &self,
         conversation_id: Uuid,
         role: &str,
         content: &str,
-    ) -> Result<Uuid, Box<dyn Error + Send + Sync>> {
-        let message_id = Uuid::new_v4();
+        message_id: Option<Uuid>,
+    ) -> Result<Uuid, DbError> {
+        let message_id = message_id.unwrap_or_else(Uuid::new_v4);
         
+        let inserted = sqlx::query(
+            "INSERT INTO messages (id, conversation_id, role, content, created_at) 
+             VALUES ($1, $2, $3, $4, NOW())
+             ON CONFLICT (id) DO NOTHING"
+        )
+        .bind(message_id)
+        .bind(conversation_id)
+        .bind(role)
+        .bind(content)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if inserted == 0 {
+            let (existing_conversation, existing_role, existing_content): (Uuid, String, String) =
+                sqlx::query_as("SELECT conversation_id, role, content FROM messages WHERE id = $1")
+                    .bind(message_id)
+                    .fetch_one(&self.pool)
+                    .await?;
+            if existing_conversation != conversation_id
+                || existing_role != role
+                || existing_content != content
+            {
+                warn!("Message id {} reused for a different message", message_id);
+                return Err(DbError::MessageIdConflict(message_id));
+            }
+            debug!("Message {} already saved, keeping the existing row", message_id);
+        }
+        Ok(message_id)
+    }
+
+    /// Save a message under a caller-chosen id, keeping its original timestamp
+    /// (late writes still sort correctly). Re-sending the same id with a later timestamp
+    /// replaces the content (a regenerated reply); an older copy, e.g. a retried
+    /// dead letter, leaves the row alone.
+    pub async fn save_message_at(
+        &self,
+        message_id: Uuid,
+        conversation_id: Uuid,
+        role: &str,
+        content: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<(), DbError> {
         sqlx::query(
             "INSERT INTO messages (id, conversation_id, role, content, created_at) 
-             VALUES ($1, $2, $3, $4, NOW())"
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (id) DO UPDATE SET content = $4, created_at = $5
+             WHERE messages.conversation_id = $2 AND messages.created_at < $5"
         )
         .bind(message_id)
         .bind(conversation_id)
         .bind(role)
         .bind(content)
+        .bind(created_at)
         .execute(&self.pool)
         .await?;
 
-        Ok(message_id)
+        Ok(())
+    }
+
+    /// Record where the original audio of a voice turn is stored. The turn's message (and
+    /// its conversation, owned by `owner`) are written first if the session store hasn't
+    /// done so yet, since the audio row references it.
+    pub async fn save_message_audio(
+        &self,
+        session_id: Uuid,
+        turn: &Turn,
+        owner: SessionOwner,
+        path: &str,
+        content_type: &str,
+    ) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+        let conversation_owner = sqlx::query_scalar::<_, Option<String>>(
+            "INSERT INTO conversations (id, owner, created_at, updated_at)
+             VALUES ($1, $2, NOW(), NOW())
+             ON CONFLICT (id) DO UPDATE SET owner = COALESCE(conversations.owner, $2)
+             RETURNING owner"
+        )
+        .bind(session_id)
+        .bind(owner.to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+        if conversation_owner != Some(owner.to_string()) {
+            warn!("Refused to store audio in conversation {} of another API key", session_id);
+            return Err(DbError::ConversationForbidden(session_id));
+        }
+        sqlx::query(
+            "INSERT INTO messages (id, conversation_id, role, content, created_at) 
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (id) DO NOTHING"
+        )
+        .bind(turn.id)
+        .bind(session_id)
+        .bind(&turn.role)
+        .bind(&turn.content)
+        .bind(turn.at)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO message_audio (message_id, owner, path, content_type) 
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (message_id) DO UPDATE SET owner = $2, path = $3, content_type = $4"
+        )
+        .bind(turn.id)
+        .bind(owner.to_string())
+        .bind(path)
+        .bind(content_type)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Stored audio (path, content type) for a message recorded under `owner`'s API key;
+    /// `DbError::NotFound` if there is none or another key recorded it
+    pub async fn get_message_audio(
+        &self,
+        message_id: Uuid,
+        owner: SessionOwner,
+    ) -> Result<(String, String), DbError> {
+        let row = sqlx::query_as::<_, (String, String)>(
+            "SELECT path, content_type FROM message_audio WHERE message_id = $1 AND owner = $2"
+        )
+        .bind(message_id)
+        .bind(owner.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Conversation and message totals; the counts run concurrently
+    pub async fn get_stats(&self) -> Result<Stats, DbError> {
+        let (conversations, messages, messages_last_24h) = tokio::try_join!(
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM conversations")
+                .fetch_one(&self.pool),
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM messages")
+                .fetch_one(&self.pool),
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM messages WHERE created_at > NOW() - INTERVAL '24 hours'"
+            )
+            .fetch_one(&self.pool),
+        )?;
+
+        Ok(Stats {
+            conversations,
+            messages,
+            messages_last_24h,
+        })
+    }
+
+    /// Set (or with None, clear) the conversation's system prompt, creating it if needed
+    pub async fn set_conversation_system_prompt(
+        &self,
+        conversation_id: Uuid,
+        system_prompt: Option<&str>,
+    ) -> Result<(), DbError> {
+        sqlx::query(
+            "INSERT INTO conversations (id, system_prompt, created_at, updated_at)
+             VALUES ($1, $2, NOW(), NOW())
+             ON CONFLICT (id) DO UPDATE SET system_prompt = $2, updated_at = NOW()"
+        )
+        .bind(conversation_id)
+        .bind(system_prompt)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The conversation's system prompt; None when unset or the conversation doesn't exist
+    pub async fn get_conversation_system_prompt(
+        &self,
+        conversation_id: Uuid,
+    ) -> Result<Option<String>, DbError> {
+        let prompt = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT system_prompt FROM conversations WHERE id = $1"
+        )
+        .bind(conversation_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(prompt.flatten())
+    }
+
+    /// Hashes of the keys in `api_keys` that are still active
+    pub async fn active_api_key_hashes(&self) -> Result<Vec<String>, DbError> {
+        let hashes = sqlx::query_scalar::<_, String>(
+            "SELECT key_hash FROM api_keys WHERE active"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(hashes)
+    }
+
+    /// Add a key (by its hash); returns the new row's id
+    #[cfg(test)]
+    pub async fn create_api_key(
+        &self,
+        key_hash: &str,
+        label: &str,
+        scopes: &[&str],
+    ) -> Result<Uuid, DbError> {
+        let id = Uuid::new_v4();
+        sqlx::query("INSERT INTO api_keys (id, key_hash, label, scopes) VALUES ($1, $2, $3, $4)")
+            .bind(id)
+            .bind(key_hash)
+            .bind(label)
+            .bind(scopes)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    #[cfg(test)]
+    pub async fn set_api_key_active(&self, id: Uuid, active: bool) -> Result<(), DbError> {
+        sqlx::query("UPDATE api_keys SET active = $2 WHERE id = $1")
+            .bind(id)
+            .bind(active)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
     }
 
     /// Create a new conversation if it doesn't exist
     /// Returns the conversation_id
-    pub async fn ensure_conversation_exists(&self, conversation_id: Uuid) -> Result<(), Box<dyn Error + Send + Sync>> {
+    pub async fn ensure_conversation_exists(&self, conversation_id: Uuid) -> Result<(), DbError> {
         sqlx::query(
             "INSERT INTO conversations (id, created_at, updated_at) 
              VALUES ($1, NOW(), NOW()) 
@@ -112,6 +384,51 @@ impl DatabaseService {
 
         Ok(())
     }
+
+    /// Create the conversation for `owner` if it doesn't exist, or check that it is
+    /// `owner`'s. A conversation without an owner yet is adopted by `owner`.
+    pub async fn claim_conversation(
+        &self,
+        conversation_id: Uuid,
+        owner: SessionOwner,
+    ) -> Result<(), DbError> {
+        let existing = sqlx::query_scalar::<_, Option<String>>(
+            "INSERT INTO conversations (id, owner, created_at, updated_at)
+             VALUES ($1, $2, NOW(), NOW())
+             ON CONFLICT (id) DO UPDATE SET owner = COALESCE(conversations.owner, $2)
+             RETURNING owner"
+        )
+        .bind(conversation_id)
+        .bind(owner.to_string())
+        .fetch_one(&self.pool)
+        .await?;
+
+        if existing != Some(owner.to_string()) {
+            warn!("Refused access to conversation {} from another API key", conversation_id);
+            return Err(DbError::ConversationForbidden(conversation_id));
+        }
+        Ok(())
+    }
+}
+
+/// Voice session turns are stored as a conversation keyed by the voice session id,
+/// owned by the session's API key
+#[async_trait::async_trait]
+impl TurnStore for DatabaseService {
+    async fn save_turn(
+        &self,
+        session_id: Uuid,
+        owner: Option<SessionOwner>,
+        turn: &Turn,
+    ) -> anyhow::Result<()> {
+        match owner {
+            Some(owner) => self.claim_conversation(session_id, owner).await?,
+            None => self.ensure_conversation_exists(session_id).await?,
+        }
+        self.save_message_at(turn.id, session_id, &turn.role, &turn.content, turn.at)
+            .await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -132,4 +449,120 @@ mod tests {
         // Verify URL format is valid (we won't actually connect in unit tests)
         assert!(url.starts_with("postgresql://"));
     }
+
+    #[test]
+    fn test_sqlx_errors_map_to_db_error() {
+        assert!(matches!(DbError::from(sqlx::Error::RowNotFound), DbError::NotFound));
+        assert!(matches!(DbError::from(sqlx::Error::PoolTimedOut), DbError::Connection(_)));
+        assert!(matches!(
+            DbError::from(sqlx::Error::ColumnNotFound("id".to_string())),
+            DbError::Query(_)
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_missing_row_is_not_found() {
+        let db = crate::test_support::database_service().await;
+
+        let err = db.get_message_audio(Uuid::new_v4(), crate::test_support::session_owner()).await.unwrap_err();
+
+        assert!(matches!(err, DbError::NotFound));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_saving_same_client_message_id_twice_keeps_one_row() {
+        let db = crate::test_support::database_service().await;
+        let conversation_id = Uuid::new_v4();
+        let message_id = Uuid::new_v4();
+        db.ensure_conversation_exists(conversation_id).await.unwrap();
+
+        let first = db.save_message(conversation_id, "user", "Hi", Some(message_id)).await.unwrap();
+        let retry = db.save_message(conversation_id, "user", "Hi", Some(message_id)).await.unwrap();
+
+        assert_eq!(first, message_id);
+        assert_eq!(retry, message_id);
+        let history = db.get_conversation_history(conversation_id).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, message_id);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_regenerated_turn_replaces_stored_reply() {
+        let db = crate::test_support::database_service().await;
+        let session_id = Uuid::new_v4();
+        let owner = Some(crate::test_support::session_owner());
+        let original = Turn::new("assistant", "Hello!");
+        let regenerated = Turn {
+            content: "Hey there!".to_string(),
+            at: original.at + chrono::Duration::milliseconds(5),
+            ..original.clone()
+        };
+
+        db.save_turn(session_id, owner, &original).await.unwrap();
+        db.save_turn(session_id, owner, &regenerated).await.unwrap();
+        // A late retry of the original doesn't bring the old reply back
+        db.save_turn(session_id, owner, &original).await.unwrap();
+
+        let history = db.get_conversation_history(session_id).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, original.id);
+        assert_eq!(history[0].content, "Hey there!");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_reusing_message_id_for_other_content_conflicts() {
+        let db = crate::test_support::database_service().await;
+        let conversation_id = Uuid::new_v4();
+        let other_conversation = Uuid::new_v4();
+        let message_id = Uuid::new_v4();
+        db.ensure_conversation_exists(conversation_id).await.unwrap();
+        db.ensure_conversation_exists(other_conversation).await.unwrap();
+        db.save_message(conversation_id, "user", "Hi", Some(message_id)).await.unwrap();
+
+        let err = db.save_message(conversation_id, "user", "Bye", Some(message_id)).await.unwrap_err();
+        assert!(matches!(err, DbError::MessageIdConflict(id) if id == message_id));
+        let err = db.save_message(other_conversation, "user", "Hi", Some(message_id)).await.unwrap_err();
+        assert!(matches!(err, DbError::MessageIdConflict(_)));
+
+        let history = db.get_conversation_history(conversation_id).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].content, "Hi");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires a running PostgreSQL instance (DATABASE_URL)
+    async fn test_deleting_conversation_cascades_to_messages() {
+        let db = crate::test_support::database_service().await;
+        let conversation_id = Uuid::new_v4();
+        db.ensure_conversation_exists(conversation_id).await.unwrap();
+        db.save_message(conversation_id, "user", "Hi", None).await.unwrap();
+        db.save_message(conversation_id, "assistant", "Hello!", None).await.unwrap();
+
+        // Plain SQL delete: only the foreign key can remove the messages
+        sqlx::query("DELETE FROM conversations WHERE id = $1")
+            .bind(conversation_id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let remaining: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE conversation_id = $1")
+                .bind(conversation_id)
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(remaining, 0);
+
+        let on_delete: i8 = sqlx::query_scalar(
+            "SELECT confdeltype FROM pg_constraint WHERE conname = 'messages_conversation_id_fkey'",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(on_delete as u8, b'c');
+    }
 }
@@ -2,8 +2,14 @@ use anyhow::{Context, Result};
 use bytes::Bytes;
 use reqwest::Client;
 use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
 use tracing::{info, warn};
 
+use super::circuit_breaker::{BreakerState, CircuitBreaker};
+use super::tts_quota::{TtsQuota, TtsUsage};
+
 #[derive(Debug, Clone, Serialize)]
 struct VoiceSettings {
     stability: f32,
@@ -23,6 +29,36 @@ impl Default for VoiceSettings {
     }
 }
 
+impl VoiceSettings {
+    /// Named preset for `ELEVENLABS_VOICE_PROFILE` / the `voice_profile` request field
+    fn profile(name: &str) -> Result<Self, UnknownVoiceProfile> {
+        match name {
+            // Consistent delivery for long replies
+            "stable" => Ok(Self {
+                stability: 0.8,
+                similarity_boost: 0.75,
+                style: 0.0,
+                use_speaker_boost: true,
+            }),
+            // More varied intonation, less predictable
+            "expressive" => Ok(Self {
+                stability: 0.3,
+                similarity_boost: 0.8,
+                style: 0.6,
+                use_speaker_boost: true,
+            }),
+            // Between the two; closest to conversational speech
+            "natural" => Ok(Self {
+                stability: 0.5,
+                similarity_boost: 0.75,
+                style: 0.2,
+                use_speaker_boost: true,
+            }),
+            other => Err(UnknownVoiceProfile(other.to_string())),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct TextToSpeechRequest {
     text: String,
@@ -30,16 +66,71 @@ struct TextToSpeechRequest {
     voice_settings: VoiceSettings,
 }
 
+/// Model used when neither `ELEVENLABS_MODEL_ID` nor the request picks one
+pub const DEFAULT_MODEL_ID: &str = "eleven_turbo_v2_5";
+
+/// Per-request overrides for a TTS call; unset fields use the service defaults
+#[derive(Debug, Clone, Default)]
+pub struct TtsOptions {
+    pub voice_id: Option<String>,
+    pub model_id: Option<String>,
+    pub voice_profile: Option<String>,
+    /// Style exaggeration (0-1) for this request in place of the profile's, e.g. from the
+    /// reply's emotion tag
+    pub style: Option<f32>,
+}
+
+/// ElevenLabs voice ids are short alphanumeric tokens (e.g. "EGNfK8LKuwEbqjx3yWz1")
+pub fn is_valid_voice_id(voice_id: &str) -> bool {
+    (16..=32).contains(&voice_id.len()) && voice_id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// ElevenLabs model ids are lowercase snake_case (e.g. "eleven_multilingual_v2")
+pub fn is_valid_model_id(model_id: &str) -> bool {
+    (1..=64).contains(&model_id.len())
+        && model_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether `name` is one of the voice profiles ("stable", "expressive", "natural")
+pub fn is_valid_voice_profile(name: &str) -> bool {
+    VoiceSettings::profile(name).is_ok()
+}
+
+/// Returned for a voice profile name that has no preset
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown voice profile '{0}' (expected stable, expressive or natural)")]
+pub struct UnknownVoiceProfile(pub String);
+
+/// Returned for empty/whitespace-only input instead of calling the API
+#[derive(Debug, thiserror::Error)]
+#[error("Text-to-speech input is empty")]
+pub struct EmptyTtsText;
+
+/// Returned when no ElevenLabs request slot frees up within the queue timeout
+#[derive(Debug, thiserror::Error)]
+#[error("Text-to-speech queue is full, try again later")]
+pub struct TtsQueueTimeout;
+
 #[derive(Debug, Clone)]
 pub struct ElevenLabsService {
     client: Client,
     api_key: String,
     voice_id: String,
+    model_id: String,
+    voice_settings: VoiceSettings,
     base_url: String,
+    breaker: CircuitBreaker,
+    permits: Arc<Semaphore>,
+    queue_timeout: Duration,
+    /// Characters synthesized per voice this month, shared by clones
+    quota: Arc<TtsQuota>,
+    /// `TTS_PROVIDER=mock`: return silent MP3 without calling ElevenLabs
+    mock: bool,
 }
 
 impl ElevenLabsService {
-    pub fn new(api_key: String, voice_id: String) -> Result<Self> {
+    /// `base_url` is the API root (`https://api.elevenlabs.io/v1`, or a regional endpoint or proxy)
+    pub fn new(api_key: String, voice_id: String, base_url: &str) -> Result<Self> {
         info!("Initializing ElevenLabs TTS service with voice_id: {}", voice_id);
         
         let client = Client::builder()
@@ -51,19 +142,154 @@ impl ElevenLabsService {
             client,
             api_key,
             voice_id,
-            base_url: "https://api.elevenlabs.io/v1".to_string(),
+            model_id: DEFAULT_MODEL_ID.to_string(),
+            voice_settings: VoiceSettings::default(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            breaker: CircuitBreaker::new("TTS", 5, Duration::from_secs(30)),
+            permits: Arc::new(Semaphore::new(4)),
+            queue_timeout: Duration::from_secs(10),
+            quota: Arc::new(TtsQuota::new(0)),
+            mock: false,
         })
     }
 
+    /// Answer every request with a short silent MP3 instead of calling ElevenLabs
+    /// (local development and CI without credits)
+    pub fn with_mock_audio(mut self) -> Self {
+        self.mock = true;
+        self
+    }
+
+    /// Default model for requests that don't override it
+    pub fn with_model_id(mut self, model_id: String) -> Self {
+        self.model_id = model_id;
+        self
+    }
+
+    /// Default voice settings preset for requests that don't pick one
+    pub fn with_voice_profile(mut self, profile: &str) -> Result<Self, UnknownVoiceProfile> {
+        info!("Using ElevenLabs voice profile: {}", profile);
+        self.voice_settings = VoiceSettings::profile(profile)?;
+        Ok(self)
+    }
+
+    /// Cap concurrent ElevenLabs requests (account rate limits); excess calls wait up to `queue_timeout`
+    pub fn with_concurrency_limit(mut self, max_concurrent: usize, queue_timeout: Duration) -> Self {
+        info!(
+            "Limiting ElevenLabs to {} concurrent requests (queue timeout {:?})",
+            max_concurrent, queue_timeout
+        );
+        self.permits = Arc::new(Semaphore::new(max_concurrent.max(1)));
+        self.queue_timeout = queue_timeout;
+        self
+    }
+
+    /// Trip after `failure_threshold` consecutive ElevenLabs failures and fail fast for `cooldown`
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.breaker = CircuitBreaker::new("TTS", failure_threshold, cooldown);
+        self
+    }
+
+    /// Allow each voice `monthly_chars` characters per calendar month (0 = unlimited);
+    /// requests past it fail with `TtsQuotaExceeded`
+    pub fn with_character_quota(mut self, monthly_chars: u64) -> Self {
+        if monthly_chars > 0 {
+            info!("Limiting each ElevenLabs voice to {} characters per month", monthly_chars);
+        }
+        self.quota = Arc::new(TtsQuota::new(monthly_chars));
+        self
+    }
+
+    /// Characters synthesized per voice so far this month
+    pub fn character_usage(&self) -> TtsUsage {
+        self.quota.usage()
+    }
+
+    /// Current state of the ElevenLabs circuit breaker
+    pub fn circuit_state(&self) -> BreakerState {
+        self.breaker.state()
+    }
+
     /// Convert text to speech using ElevenLabs API
     /// Returns MP3 audio bytes
     pub async fn text_to_speech(&self, text: &str) -> Result<Bytes> {
-        let url = format!("{}/text-to-speech/{}", self.base_url, self.voice_id);
+        self.text_to_speech_with(text, &TtsOptions::default()).await
+    }
+
+    /// Like `text_to_speech`, with per-request overrides (e.g. the session's voice)
+    pub async fn text_to_speech_with(&self, text: &str, options: &TtsOptions) -> Result<Bytes> {
+        if text.trim().is_empty() {
+            warn!("Refusing to synthesize empty text");
+            return Err(EmptyTtsText.into());
+        }
+
+        let mut voice_settings = match &options.voice_profile {
+            Some(profile) => VoiceSettings::profile(profile)?,
+            None => self.voice_settings.clone(),
+        };
+        if let Some(style) = options.style {
+            voice_settings.style = style.clamp(0.0, 1.0);
+        }
+
+        // Reserve the characters up front so concurrent requests can't overshoot the
+        // quota; a failed or cancelled call drops the reservation and gets them back
+        let voice_id = options.voice_id.as_deref().unwrap_or(&self.voice_id);
+        let chars = text.chars().count() as u64;
+        let reservation = self.quota.reserve(voice_id, chars).map_err(|e| {
+            warn!("{}", e);
+            e
+        })?;
+
+        let audio = self.speak(text, options, voice_settings).await?;
+        reservation.commit();
+        Ok(audio)
+    }
+
+    /// One TTS call, through the circuit breaker and the request slots
+    async fn speak(
+        &self,
+        text: &str,
+        options: &TtsOptions,
+        voice_settings: VoiceSettings,
+    ) -> Result<Bytes> {
+        if self.mock {
+            return Ok(silent_mp3());
+        }
+
+        let _permit = tokio::time::timeout(self.queue_timeout, self.permits.acquire())
+            .await
+            .map_err(|_| {
+                warn!("Timed out after {:?} waiting for an ElevenLabs request slot", self.queue_timeout);
+                TtsQueueTimeout
+            })??;
+
+        // Fail fast while ElevenLabs is known to be down. Checked once we hold a slot, so
+        // a half-open probe is always followed by a call that records its outcome.
+        let call = self.breaker.check()?;
+
+        let result = self.request_speech(text, options, voice_settings).await;
+        call.record(&result);
+        result
+    }
+
+    fn text_to_speech_url(&self, voice_id: &str) -> String {
+        format!("{}/text-to-speech/{}", self.base_url, voice_id)
+    }
+
+    async fn request_speech(
+        &self,
+        text: &str,
+        options: &TtsOptions,
+        voice_settings: VoiceSettings,
+    ) -> Result<Bytes> {
+        let voice_id = options.voice_id.as_deref().unwrap_or(&self.voice_id);
+        let model_id = options.model_id.as_deref().unwrap_or(&self.model_id);
+        let url = self.text_to_speech_url(voice_id);
         
         let request_body = TextToSpeechRequest {
             text: text.to_string(),
-            model_id: "eleven_turbo_v2_5".to_string(),
-            voice_settings: VoiceSettings::default(),
+            model_id: model_id.to_string(),
+            voice_settings,
         };
 
         info!("Sending TTS request to ElevenLabs (text length: {} chars)", text.len());
@@ -95,19 +321,228 @@ impl ElevenLabsService {
     }
 }
 
+/// ~0.25s of silence: ten 32kbps 44.1kHz mono MPEG-1 Layer III frames with empty payloads
+fn silent_mp3() -> Bytes {
+    const FRAME_HEADER: [u8; 4] = [0xFF, 0xFB, 0x10, 0xC0];
+    const FRAME_LEN: usize = 104;
+
+    let mut audio = Vec::with_capacity(FRAME_LEN * 10);
+    for _ in 0..10 {
+        audio.extend_from_slice(&FRAME_HEADER);
+        audio.resize(audio.len() + FRAME_LEN - FRAME_HEADER.len(), 0);
+    }
+    Bytes::from(audio)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::tts_quota::TtsQuotaExceeded;
+    use crate::test_support::{MockUpstream, MOCK_MP3};
 
     #[test]
     fn test_elevenlabs_service_creation() {
         let service = ElevenLabsService::new(
             "test_api_key".to_string(),
             "test_voice_id".to_string(),
+            "https://api.elevenlabs.io/v1",
         );
         assert!(service.is_ok());
     }
 
+    #[test]
+    fn test_configured_base_url_used_for_requests() {
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            "https://tts-proxy.internal/elevenlabs/v1/",
+        )
+        .unwrap();
+
+        assert_eq!(
+            service.text_to_speech_url("voice"),
+            "https://tts-proxy.internal/elevenlabs/v1/text-to-speech/voice"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_and_whitespace_text_rejected_before_request() {
+        let upstream = MockUpstream::start("unused").await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &upstream.base_url,
+        )
+        .unwrap();
+
+        for text in ["", "  \n\t "] {
+            let err = service.text_to_speech(text).await.unwrap_err();
+            assert!(err.downcast_ref::<EmptyTtsText>().is_some());
+        }
+        assert!(upstream.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_voice_over_character_quota_rejected_before_request() {
+        let upstream = MockUpstream::start("unused").await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &upstream.base_url,
+        )
+        .unwrap()
+        .with_character_quota(10);
+
+        service.text_to_speech("Hello").await.unwrap();
+        service.text_to_speech("there").await.unwrap();
+        let err = service.text_to_speech("!").await.unwrap_err();
+
+        let exceeded = err.downcast_ref::<TtsQuotaExceeded>().unwrap();
+        assert_eq!(exceeded.voice_id, "voice");
+        assert_eq!(upstream.requests().len(), 2);
+        assert_eq!(service.character_usage().voices["voice"], 10);
+    }
+
+    #[tokio::test]
+    async fn test_valid_text_is_synthesized() {
+        let upstream = MockUpstream::start("unused").await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &upstream.base_url,
+        )
+        .unwrap();
+
+        let audio = service.text_to_speech("Hello there").await.unwrap();
+
+        assert_eq!(&audio[..], MOCK_MP3);
+        assert_eq!(upstream.requests_to("/text-to-speech/").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_audio_makes_no_request() {
+        let upstream = MockUpstream::start("unused").await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &upstream.base_url,
+        )
+        .unwrap()
+        .with_mock_audio();
+
+        let audio = service.text_to_speech("Hello there").await.unwrap();
+
+        assert!(!audio.is_empty());
+        assert_eq!(&audio[..2], &[0xFF, 0xFB]); // MPEG-1 Layer III frame sync
+        assert!(upstream.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_configured_and_overridden_model_id_sent() {
+        let upstream = MockUpstream::start("unused").await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &upstream.base_url,
+        )
+        .unwrap()
+        .with_model_id("eleven_multilingual_v2".to_string());
+
+        service.text_to_speech("Hola").await.unwrap();
+        let options = TtsOptions {
+            model_id: Some("eleven_flash_v2_5".to_string()),
+            ..Default::default()
+        };
+        service.text_to_speech_with("Hello", &options).await.unwrap();
+
+        let requests = upstream.requests_to("/text-to-speech/");
+        assert_eq!(requests[0].json()["model_id"], "eleven_multilingual_v2");
+        assert_eq!(requests[1].json()["model_id"], "eleven_flash_v2_5");
+    }
+
+    /// TTS endpoint that answers after `delay`, tracking the peak number of in-flight requests
+    async fn slow_tts_server(delay: Duration) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (counter, max) = (in_flight.clone(), peak.clone());
+        let app = axum::Router::new().fallback(move || {
+            let (counter, max) = (counter.clone(), max.clone());
+            async move {
+                let now = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                max.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(delay).await;
+                counter.fetch_sub(1, Ordering::SeqCst);
+                MOCK_MP3
+            }
+        });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}", address), peak)
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_requests_never_exceed_cap() {
+        let (base_url, peak) = slow_tts_server(Duration::from_millis(50)).await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &base_url,
+        )
+        .unwrap()
+        .with_concurrency_limit(2, Duration::from_secs(5));
+
+        let calls = (0..8).map(|i| {
+            let service = service.clone();
+            async move { service.text_to_speech(&format!("Sentence {}", i)).await }
+        });
+        let results = futures::future::join_all(calls).await;
+
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_saturated_queue_times_out() {
+        let (base_url, _) = slow_tts_server(Duration::from_millis(300)).await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &base_url,
+        )
+        .unwrap()
+        .with_concurrency_limit(1, Duration::from_millis(20));
+
+        let (first, second) = tokio::join!(
+            service.text_to_speech("First"),
+            service.text_to_speech("Second"),
+        );
+
+        assert!(first.is_ok());
+        assert!(second.unwrap_err().downcast_ref::<TtsQueueTimeout>().is_some());
+        // Waiting for a slot isn't an ElevenLabs failure
+        assert_eq!(service.circuit_state(), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_model_id_validation() {
+        assert!(is_valid_model_id("eleven_multilingual_v2"));
+        assert!(!is_valid_model_id(""));
+        assert!(!is_valid_model_id("eleven/../v2"));
+    }
+
+    #[test]
+    fn test_voice_id_validation() {
+        assert!(is_valid_voice_id("EGNfK8LKuwEbqjx3yWz1"));
+        assert!(!is_valid_voice_id(""));
+        assert!(!is_valid_voice_id("short"));
+        assert!(!is_valid_voice_id("../../admin/voices1234"));
+        assert!(!is_valid_voice_id("EGNfK8LKuwEbqjx3yWz1?x=1"));
+    }
+
     #[test]
     fn test_voice_settings_defaults() {
         let settings = VoiceSettings::default();
@@ -116,4 +551,88 @@ mod tests {
         assert_eq!(settings.style, 0.0);
         assert!(settings.use_speaker_boost);
     }
+
+    #[test]
+    fn test_voice_profiles_resolve_to_presets() {
+        let stable = VoiceSettings::profile("stable").unwrap();
+        assert_eq!(
+            (stable.stability, stable.similarity_boost, stable.style, stable.use_speaker_boost),
+            (0.8, 0.75, 0.0, true)
+        );
+        let expressive = VoiceSettings::profile("expressive").unwrap();
+        assert_eq!(
+            (expressive.stability, expressive.similarity_boost, expressive.style, expressive.use_speaker_boost),
+            (0.3, 0.8, 0.6, true)
+        );
+        let natural = VoiceSettings::profile("natural").unwrap();
+        assert_eq!(
+            (natural.stability, natural.similarity_boost, natural.style, natural.use_speaker_boost),
+            (0.5, 0.75, 0.2, true)
+        );
+
+        let err = VoiceSettings::profile("dramatic").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown voice profile 'dramatic' (expected stable, expressive or natural)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queue_timeout_after_cooldown_does_not_wedge_breaker() {
+        let upstream = MockUpstream::start("unused").await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &upstream.base_url,
+        )
+        .unwrap()
+        .with_concurrency_limit(1, Duration::from_millis(20))
+        .with_circuit_breaker(1, Duration::from_millis(20));
+
+        service.breaker.record_failure();
+        assert_eq!(service.circuit_state(), BreakerState::Open);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        // Every slot taken: the would-be probe times out in the queue
+        let held = service.permits.clone().acquire_owned().await.unwrap();
+        let err = service.text_to_speech("Hello").await.unwrap_err();
+        assert!(err.downcast_ref::<TtsQueueTimeout>().is_some());
+        drop(held);
+
+        service.text_to_speech("Hello").await.unwrap();
+        assert_eq!(upstream.requests_to("/text-to-speech/").len(), 1);
+        assert_eq!(service.circuit_state(), BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_request_profile_overrides_configured_profile() {
+        let upstream = MockUpstream::start("unused").await;
+        let service = ElevenLabsService::new(
+            "key".to_string(),
+            "voice".to_string(),
+            &upstream.base_url,
+        )
+        .unwrap()
+        .with_voice_profile("stable")
+        .unwrap();
+
+        service.text_to_speech("Hello").await.unwrap();
+        let options = TtsOptions {
+            voice_profile: Some("expressive".to_string()),
+            ..Default::default()
+        };
+        service.text_to_speech_with("Hello", &options).await.unwrap();
+
+        let requests = upstream.requests_to("/text-to-speech/");
+        assert_eq!(requests[0].json()["voice_settings"]["stability"], 0.8);
+        assert_eq!(requests[1].json()["voice_settings"]["stability"], 0.3);
+
+        let options = TtsOptions {
+            voice_profile: Some("whisper".to_string()),
+            ..Default::default()
+        };
+        let err = service.text_to_speech_with("Hello", &options).await.unwrap_err();
+        assert!(err.downcast_ref::<UnknownVoiceProfile>().is_some());
+        assert_eq!(upstream.requests_to("/text-to-speech/").len(), 2);
+    }
 }
@@ -0,0 +1,139 @@
+// Emotion tags for spoken replies. Each sentence gets a tag, either one the LLM
+// put in front of it (`[excited] Welcome back!`) or one guessed from its words,
+// and `TTS_EMOTION_STYLES` maps the tag to an ElevenLabs style exaggeration.
+
+/// Greetings and exclamations
+pub const EXCITED: &str = "excited";
+/// Apologies and bad news
+pub const CALM: &str = "calm";
+
+const GREETINGS: [&str; 5] = ["hello", "hi", "hey", "welcome", "congratulations"];
+/// Matched anywhere in the sentence ("apolog" covers apologize/apologies)
+const APOLOGIES: [&str; 3] = ["sorry", "apolog", "unfortunately"];
+
+/// Tag guessed from the sentence itself; None for anything neutral
+pub fn detect(sentence: &str) -> Option<&'static str> {
+    let lower = sentence.to_lowercase();
+    if APOLOGIES.iter().any(|word| lower.contains(word)) {
+        return Some(CALM);
+    }
+    let first_word = lower.split(|c: char| !c.is_alphanumeric()).find(|w| !w.is_empty());
+    let greets = first_word.is_some_and(|word| GREETINGS.contains(&word));
+    if greets || sentence.trim_end().ends_with('!') {
+        return Some(EXCITED);
+    }
+    None
+}
+
+/// A leading `[tag]` naming one of `styles`, and the sentence after it
+fn explicit_tag<'a>(sentence: &'a str, styles: &[(String, f32)]) -> Option<(&'a str, &'a str)> {
+    let rest = sentence.trim_start().strip_prefix('[')?;
+    let (tag, after) = rest.split_once(']')?;
+    let tag = tag.trim();
+    styles
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case(tag))
+        .then(|| (tag, after.trim_start()))
+}
+
+/// Style exaggeration for `sentence` and the text to speak: the LLM's tag if it gave one,
+/// else the detected tag. None (keep the voice's own style) when the tag has no entry
+/// in `styles` or nothing was derived.
+pub fn style_for<'a>(sentence: &'a str, styles: &[(String, f32)]) -> (Option<f32>, &'a str) {
+    if styles.is_empty() {
+        return (None, sentence);
+    }
+    let (tag, text) = match explicit_tag(sentence, styles) {
+        Some((tag, text)) => (Some(tag), text),
+        None => (detect(sentence), sentence),
+    };
+    let style = tag.and_then(|tag| {
+        styles
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+            .map(|&(_, style)| style)
+    });
+    (style, text)
+}
+
+/// System prompt line asking the LLM to tag its sentences with the `styles` it can use;
+/// None when no styles are configured, so no tags are asked for
+pub fn tag_instruction(styles: &[(String, f32)]) -> Option<String> {
+    if styles.is_empty() {
+        return None;
+    }
+    let tags: Vec<String> = styles.iter().map(|(name, _)| format!("[{}]", name)).collect();
+    Some(format!(
+        "You may start a sentence with one of these tags to set the tone it is spoken in: {}. \
+         Tags are not read aloud; don't use any other bracketed text.",
+        tags.join(", ")
+    ))
+}
+
+/// `text` without the `[tag]`s the LLM put in front of its sentences (the reply shown
+/// to the user and kept in history)
+pub fn strip_tags(text: &str, styles: &[(String, f32)]) -> String {
+    if styles.is_empty() {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        out.push_str(&rest[..open]);
+        match explicit_tag(&rest[open..], styles) {
+            Some((_, after)) => rest = after,
+            None => {
+                out.push('[');
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styles() -> Vec<(String, f32)> {
+        vec![(EXCITED.to_string(), 0.7), (CALM.to_string(), 0.0)]
+    }
+
+    #[test]
+    fn test_detects_greetings_and_apologies() {
+        assert_eq!(detect("Hello, I'm Tea."), Some(EXCITED));
+        assert_eq!(detect("That sounds wonderful!"), Some(EXCITED));
+        assert_eq!(detect("I'm so sorry to hear that!"), Some(CALM));
+        assert_eq!(detect("Unfortunately we are out of oolong."), Some(CALM));
+        assert_eq!(detect("Your tea is ready."), None);
+        // "hi" only counts as a whole word
+        assert_eq!(detect("History is fascinating."), None);
+    }
+
+    #[test]
+    fn test_explicit_tag_wins_and_is_not_spoken() {
+        let styles = styles();
+        assert_eq!(style_for("[calm] Hello there!", &styles), (Some(0.0), "Hello there!"));
+        assert_eq!(style_for("Hello there!", &styles), (Some(0.7), "Hello there!"));
+        assert_eq!(style_for("Your tea is ready.", &styles), (None, "Your tea is ready."));
+        // Tags without a configured style are left alone
+        assert_eq!(style_for("[sad] Oh no.", &styles), (None, "[sad] Oh no."));
+        assert_eq!(style_for("Hello!", &[]), (None, "Hello!"));
+    }
+
+    #[test]
+    fn test_tag_instruction_lists_configured_tags() {
+        let instruction = tag_instruction(&styles()).unwrap();
+        assert!(instruction.contains("[excited], [calm]"), "{}", instruction);
+        assert_eq!(tag_instruction(&[]), None);
+    }
+
+    #[test]
+    fn test_tags_stripped_from_reply() {
+        assert_eq!(
+            strip_tags("[excited] Welcome back! [calm] Sorry about the wait. See [1].", &styles()),
+            "Welcome back! Sorry about the wait. See [1]."
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+/// Energy-based end-of-utterance detection for streamed 16-bit little-endian mono PCM.
+/// An utterance ends once speech has been heard and the RMS level then stays below
+/// `threshold` for `hang_time`.
+#[derive(Debug, Clone)]
+pub struct Endpointer {
+    threshold: f32,
+    hang_time: Duration,
+    sample_rate: u32,
+    heard_speech: bool,
+    silence: Duration,
+}
+
+impl Endpointer {
+    pub fn new(threshold: f32, hang_time: Duration, sample_rate: u32) -> Self {
+        Self {
+            threshold,
+            hang_time,
+            sample_rate,
+            heard_speech: false,
+            silence: Duration::ZERO,
+        }
+    }
+
+    /// Feed one chunk; returns true when it completes an utterance
+    pub fn push(&mut self, chunk: &[u8]) -> bool {
+        let samples = chunk.len() / 2;
+        if samples == 0 || self.sample_rate == 0 {
+            return false;
+        }
+
+        if rms(chunk) >= self.threshold {
+            self.heard_speech = true;
+            self.silence = Duration::ZERO;
+            return false;
+        }
+
+        if !self.heard_speech {
+            return false;
+        }
+        self.silence += Duration::from_secs_f64(samples as f64 / self.sample_rate as f64);
+        self.silence >= self.hang_time
+    }
+
+    /// Start listening for the next utterance
+    pub fn reset(&mut self) {
+        self.heard_speech = false;
+        self.silence = Duration::ZERO;
+    }
+}
+
+/// Root-mean-square amplitude of 16-bit little-endian samples
+fn rms(pcm: &[u8]) -> f32 {
+    let samples: Vec<f64> = pcm
+        .chunks_exact(2)
+        .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]) as f64)
+        .collect();
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let mean_square = samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64;
+    mean_square.sqrt() as f32
+}
+
+/// `ms` milliseconds of 16-bit PCM at a constant amplitude (test audio)
+#[cfg(test)]
+pub fn pcm_chunk(amplitude: i16, ms: u32, sample_rate: u32) -> Vec<u8> {
+    let samples = (sample_rate * ms / 1000) as usize;
+    (0..samples)
+        .flat_map(|i| {
+            let sample = if i % 2 == 0 { amplitude } else { -amplitude };
+            sample.to_le_bytes()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speech_then_silence_ends_utterance() {
+        let mut endpointer = Endpointer::new(500.0, Duration::from_millis(300), 16000);
+
+        assert!(!endpointer.push(&pcm_chunk(8000, 100, 16000)));
+        assert!(!endpointer.push(&pcm_chunk(10, 100, 16000)));
+        assert!(!endpointer.push(&pcm_chunk(10, 100, 16000)));
+        assert!(endpointer.push(&pcm_chunk(10, 100, 16000)));
+    }
+
+    #[test]
+    fn test_leading_silence_and_short_pauses_do_not_end() {
+        let mut endpointer = Endpointer::new(500.0, Duration::from_millis(300), 16000);
+
+        // Nothing said yet
+        for _ in 0..10 {
+            assert!(!endpointer.push(&pcm_chunk(0, 100, 16000)));
+        }
+
+        // A pause shorter than the hang time resets on new speech
+        assert!(!endpointer.push(&pcm_chunk(8000, 100, 16000)));
+        assert!(!endpointer.push(&pcm_chunk(0, 200, 16000)));
+        assert!(!endpointer.push(&pcm_chunk(8000, 100, 16000)));
+        assert!(!endpointer.push(&pcm_chunk(0, 200, 16000)));
+        assert!(endpointer.push(&pcm_chunk(0, 100, 16000)));
+
+        endpointer.reset();
+        assert!(!endpointer.push(&pcm_chunk(0, 500, 16000)));
+    }
+}
@@ -0,0 +1,63 @@
+use axum::http::HeaderValue;
+
+// Header values must be visible ASCII, so free text (transcriptions and replies in any
+// script, emoji, stray control characters) is sent as percent-encoded UTF-8, like
+// `encodeURIComponent`. Clients decode with `decodeURIComponent` or equivalent.
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'!' | b'~' | b'*' | b'\'' | b'(' | b')')
+}
+
+/// Percent-encode `text` into a header value that is always valid
+pub fn encode_header_text(text: &str) -> HeaderValue {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    HeaderValue::from_str(&encoded).expect("percent-encoded text is visible ASCII")
+}
+
+/// Inverse of `encode_header_text`, as a client would do it
+#[cfg(test)]
+pub fn decode_header_text(value: &HeaderValue) -> Option<String> {
+    let encoded = value.to_str().ok()?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        if encoded[i] == b'%' {
+            let hex = std::str::from_utf8(encoded.get(i + 1..i + 3)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            bytes.push(encoded[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_ascii_text_round_trips() {
+        for text in ["こんにちは、お茶はいかが？", "مرحبا بك", "Tea time ☕️🍵 👨‍👩‍👧", "Hello, tea!"] {
+            let value = encode_header_text(text);
+            assert!(value.to_str().unwrap().is_ascii());
+            assert_eq!(decode_header_text(&value).as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_control_characters_and_percent_are_escaped() {
+        let value = encode_header_text("line one\r\nline two\t100%");
+
+        assert_eq!(value, "line%20one%0D%0Aline%20two%09100%25");
+        assert_eq!(decode_header_text(&value).as_deref(), Some("line one\r\nline two\t100%"));
+    }
+}
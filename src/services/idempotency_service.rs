@@ -0,0 +1,280 @@
+use axum::{
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard, RwLock};
+use tracing::{debug, info};
+
+/// Response captured for an idempotency key so retries can be replayed
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub content_type: String,
+    pub body: Bytes,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    created_at: Instant,
+}
+
+impl CachedResponse {
+    pub fn new(status: StatusCode, content_type: &str, body: Bytes) -> Self {
+        Self {
+            status,
+            content_type: content_type.to_string(),
+            body,
+            headers: Vec::new(),
+            created_at: Instant::now(),
+        }
+    }
+
+    /// Extra response header, replayed along with the body
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    fn is_expired(&self, ttl: Duration) -> bool {
+        self.created_at.elapsed() > ttl
+    }
+}
+
+impl IntoResponse for CachedResponse {
+    fn into_response(self) -> Response {
+        let mut response = (
+            self.status,
+            [(header::CONTENT_TYPE, self.content_type)],
+            self.body,
+        )
+            .into_response();
+        response.headers_mut().extend(self.headers);
+        response
+    }
+}
+
+/// Short-lived cache of responses keyed by the client's `Idempotency-Key` header
+#[derive(Clone)]
+pub struct IdempotencyService {
+    entries: Arc<RwLock<HashMap<String, CachedResponse>>>,
+    /// One lock per key whose request is being processed; duplicates queue on it
+    in_flight: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+    ttl: Duration,
+}
+
+impl IdempotencyService {
+    pub fn new(ttl_secs: u64) -> Self {
+        info!("Initializing IdempotencyService with TTL: {} seconds", ttl_secs);
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            ttl: Duration::from_secs(ttl_secs),
+        }
+    }
+
+    /// Get a cached response if one exists and has not expired
+    pub async fn get(&self, key: &str) -> Option<CachedResponse> {
+        let entries = self.entries.read().await;
+        entries
+            .get(key)
+            .filter(|cached| !cached.is_expired(self.ttl))
+            .cloned()
+    }
+
+    /// Store a response under the given key
+    pub async fn store(&self, key: &str, response: CachedResponse) {
+        let mut entries = self.entries.write().await;
+        entries.insert(key.to_string(), response);
+        debug!("Cached response for idempotency key {}", key);
+    }
+
+    /// Return the cached response for `key`, or run `process` and cache its successful result.
+    /// A duplicate that arrives while the first request is still running waits for it and
+    /// replays its response. Failures are not cached so the client can retry them (a
+    /// waiting duplicate then runs `process` itself).
+    pub async fn get_or_run<F, Fut, E>(&self, key: &str, process: F) -> Result<CachedResponse, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<CachedResponse, E>>,
+    {
+        if let Some(cached) = self.get(key).await {
+            info!("Replaying cached response for idempotency key {}", key);
+            return Ok(cached);
+        }
+
+        let _running = self.run_exclusively(key).await;
+        if let Some(cached) = self.get(key).await {
+            info!("Replaying response of the concurrent request for idempotency key {}", key);
+            return Ok(cached);
+        }
+
+        let response = process().await?;
+        self.store(key, response.clone()).await;
+        Ok(response)
+    }
+
+    /// Wait until no other request with `key` is being processed, then hold the key
+    /// until the returned slot is dropped
+    async fn run_exclusively(&self, key: &str) -> InFlight {
+        let lock = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        if lock.try_lock().is_err() {
+            debug!("Idempotency key {} is in flight, waiting for it", key);
+        }
+        let guard = lock.clone().lock_owned().await;
+        InFlight {
+            in_flight: self.in_flight.clone(),
+            key: key.to_string(),
+            lock,
+            guard: Some(guard),
+        }
+    }
+
+    /// Drop expired entries (call periodically)
+    pub async fn cleanup_expired(&self) {
+        let mut entries = self.entries.write().await;
+        let initial_count = entries.len();
+        entries.retain(|_, cached| !cached.is_expired(self.ttl));
+
+        let removed = initial_count - entries.len();
+        if removed > 0 {
+            debug!("Cleaned up {} expired idempotency entries", removed);
+        }
+    }
+
+    /// Start background cleanup task
+    pub fn start_cleanup_task(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+
+            loop {
+                interval.tick().await;
+                self.cleanup_expired().await;
+            }
+        });
+
+        info!("Started idempotency cache cleanup background task");
+    }
+}
+
+/// A key held by `run_exclusively`; releases it on drop (also when the request is
+/// cancelled) and forgets the key once nobody else is waiting for it
+struct InFlight {
+    in_flight: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+    key: String,
+    lock: Arc<AsyncMutex<()>>,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.guard.take();
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        // Only the map and this slot still hold the lock: no request is waiting
+        if Arc::strong_count(&self.lock) == 2 {
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_same_key_runs_pipeline_once() {
+        let service = IdempotencyService::new(300);
+        let runs = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            let response = service
+                .get_or_run("retry-key", || async {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, ()>(CachedResponse::new(
+                        StatusCode::OK,
+                        "audio/mpeg",
+                        Bytes::from_static(b"mp3"),
+                    ))
+                })
+                .await
+                .unwrap();
+            assert_eq!(response.body, Bytes::from_static(b"mp3"));
+        }
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_duplicate_waits_for_first() {
+        let service = IdempotencyService::new(300);
+        let runs = AtomicUsize::new(0);
+        let run = || {
+            service.get_or_run("double-tap", || async {
+                runs.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok::<_, ()>(CachedResponse::new(
+                    StatusCode::OK,
+                    "audio/mpeg",
+                    Bytes::from_static(b"mp3"),
+                ))
+            })
+        };
+
+        let (first, second) = tokio::join!(run(), run());
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(first.unwrap().body, second.unwrap().body);
+        assert!(service.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_of_failed_request_runs_again() {
+        let service = IdempotencyService::new(300);
+        let runs = AtomicUsize::new(0);
+        let run = || {
+            service.get_or_run("flaky-double-tap", || async {
+                let run = runs.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                match run {
+                    0 => Err("boom"),
+                    _ => Ok(CachedResponse::new(StatusCode::OK, "audio/mpeg", Bytes::new())),
+                }
+            })
+        };
+
+        let (first, second) = tokio::join!(run(), run());
+
+        assert!(first.is_err());
+        assert!(second.is_ok());
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_not_cached() {
+        let service = IdempotencyService::new(300);
+
+        let first: Result<CachedResponse, &str> =
+            service.get_or_run("flaky", || async { Err("boom") }).await;
+        assert!(first.is_err());
+        assert!(service.get("flaky").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_ignored() {
+        let service = IdempotencyService::new(0);
+        service
+            .store("old", CachedResponse::new(StatusCode::OK, "audio/mpeg", Bytes::new()))
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(service.get("old").await.is_none());
+    }
+}
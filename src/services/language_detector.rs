@@ -0,0 +1,97 @@
+use serde::Serialize;
+
+/// Common short words per language, lowercase. A transcript's share of them is the score.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "are", "you", "i", "it", "of", "to", "what", "this", "with"]),
+    ("es", &["el", "la", "los", "las", "y", "es", "que", "de", "en", "un", "una", "por", "hola"]),
+    ("fr", &["le", "la", "les", "et", "est", "que", "de", "un", "une", "je", "vous", "bonjour"]),
+    ("de", &["der", "die", "das", "und", "ist", "ich", "nicht", "ein", "eine", "mit", "hallo"]),
+    ("it", &["il", "lo", "gli", "e", "che", "di", "un", "una", "non", "sono", "ciao", "per"]),
+    ("pt", &["o", "os", "as", "e", "que", "de", "um", "uma", "não", "com", "olá", "você"]),
+    ("nl", &["de", "het", "een", "en", "is", "ik", "niet", "van", "met", "je", "hallo"]),
+];
+
+/// One auto-detect hypothesis: the share of the transcript's words that are common words
+/// of `language`, from 0 to 1
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LanguageCandidate {
+    pub language: String,
+    pub score: f32,
+}
+
+/// Guesses which of the configured languages a transcript is in. Scoring is by common
+/// words, so it needs a few words to go on; languages without a word list score 0.
+#[derive(Debug, Clone)]
+pub struct LanguageDetector {
+    languages: Vec<String>,
+}
+
+impl LanguageDetector {
+    /// Candidates are limited to `languages`; on equal scores the earlier one ranks first
+    pub fn new(languages: Vec<String>) -> Self {
+        Self { languages }
+    }
+
+    /// Every configured language with its score, best first
+    pub fn rank(&self, text: &str) -> Vec<LanguageCandidate> {
+        let words: Vec<String> = text
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let mut candidates: Vec<LanguageCandidate> = self
+            .languages
+            .iter()
+            .map(|language| {
+                let code = language.split(['-', '_']).next().unwrap_or_default();
+                let stopwords = STOPWORDS
+                    .iter()
+                    .find(|(listed, _)| *listed == code)
+                    .map(|(_, stopwords)| *stopwords)
+                    .unwrap_or_default();
+                let hits = words.iter().filter(|word| stopwords.contains(&word.as_str())).count();
+                let score = if words.is_empty() { 0.0 } else { hits as f32 / words.len() as f32 };
+                LanguageCandidate {
+                    language: language.clone(),
+                    score,
+                }
+            })
+            .collect();
+        // Stable, so ties keep the configured order
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates
+    }
+
+    /// The top-ranked language, or None when no language scored at all
+    pub fn detect(&self, text: &str) -> Option<String> {
+        self.rank(text)
+            .into_iter()
+            .next()
+            .filter(|top| top.score > 0.0)
+            .map(|top| top.language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_sorted_by_score_with_top_chosen() {
+        let detector = LanguageDetector::new(vec!["en".into(), "es".into(), "de".into()]);
+        let text = "hola, ¿qué es el té verde? I like the tea";
+
+        let candidates = detector.rank(text);
+        assert_eq!(candidates.len(), 3);
+        assert!(candidates.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert_eq!(candidates[0].language, "es");
+        assert_eq!(candidates[1].language, "en");
+        assert_eq!(candidates[2].score, 0.0);
+        assert_eq!(detector.detect(text).as_deref(), Some(candidates[0].language.as_str()));
+
+        // Nothing recognizable: no guess
+        assert_eq!(detector.detect("oolong sencha matcha"), None);
+    }
+}
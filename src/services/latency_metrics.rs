@@ -0,0 +1,157 @@
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds (seconds) used when `LATENCY_BUCKETS_SECS` is unset
+pub const DEFAULT_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Pipeline stages with their own histogram, used as the `stage` label
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Transcription,
+    /// Interim transcripts on a streaming socket, kept apart from full utterances
+    Partial,
+    Llm,
+    Tts,
+}
+
+impl Stage {
+    const ALL: [Stage; 4] = [Stage::Transcription, Stage::Partial, Stage::Llm, Stage::Tts];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::Transcription => "transcription",
+            Stage::Partial => "partial",
+            Stage::Llm => "llm",
+            Stage::Tts => "tts",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    /// Observations per bucket (not cumulative); the last entry is the +Inf bucket
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Latency distributions per pipeline stage, rendered for `/metrics` in the
+/// Prometheus text format as `rusty_tea_stage_duration_seconds`
+#[derive(Debug, Clone)]
+pub struct LatencyHistograms {
+    buckets: Arc<Vec<f64>>,
+    stages: Arc<Mutex<Vec<Histogram>>>,
+}
+
+impl LatencyHistograms {
+    /// `buckets` are upper bounds in seconds; they are sorted and a `+Inf` bucket is added
+    pub fn new(buckets: &[f64]) -> Self {
+        let mut buckets: Vec<f64> =
+            buckets.iter().copied().filter(|b| b.is_finite() && *b > 0.0).collect();
+        buckets.sort_by(f64::total_cmp);
+        buckets.dedup();
+        if buckets.is_empty() {
+            buckets = DEFAULT_BUCKETS.to_vec();
+        }
+
+        let empty = Histogram {
+            counts: vec![0; buckets.len() + 1],
+            sum: 0.0,
+            count: 0,
+        };
+        Self {
+            buckets: Arc::new(buckets),
+            stages: Arc::new(Mutex::new(vec![empty; Stage::ALL.len()])),
+        }
+    }
+
+    pub fn observe(&self, stage: Stage, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let bucket = self.buckets.iter().position(|&le| secs <= le).unwrap_or(self.buckets.len());
+
+        let mut stages = self.stages.lock().unwrap();
+        let histogram = &mut stages[stage as usize];
+        histogram.counts[bucket] += 1;
+        histogram.sum += secs;
+        histogram.count += 1;
+    }
+
+    /// Run `work`, recording how long it took under `stage`
+    pub async fn time<T>(&self, stage: Stage, work: impl Future<Output = T>) -> T {
+        let started = Instant::now();
+        let output = work.await;
+        self.observe(stage, started.elapsed());
+        output
+    }
+
+    /// Cumulative count of `stage` observations at or below each bucket, `+Inf` last
+    pub fn cumulative_counts(&self, stage: Stage) -> Vec<u64> {
+        let stages = self.stages.lock().unwrap();
+        stages[stage as usize]
+            .counts
+            .iter()
+            .scan(0, |total, count| {
+                *total += count;
+                Some(*total)
+            })
+            .collect()
+    }
+
+    /// All stages in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP rusty_tea_stage_duration_seconds Time spent in each pipeline stage\n");
+        out.push_str("# TYPE rusty_tea_stage_duration_seconds histogram\n");
+
+        for stage in Stage::ALL {
+            let label = stage.label();
+            let cumulative = self.cumulative_counts(stage);
+            let bounds = self.buckets.iter().map(|le| le.to_string()).chain(["+Inf".to_string()]);
+            for (le, count) in bounds.zip(&cumulative) {
+                let _ = writeln!(
+                    out,
+                    "rusty_tea_stage_duration_seconds_bucket{{stage=\"{}\",le=\"{}\"}} {}",
+                    label, le, count
+                );
+            }
+
+            let stages = self.stages.lock().unwrap();
+            let histogram = &stages[stage as usize];
+            let _ = writeln!(
+                out,
+                "rusty_tea_stage_duration_seconds_sum{{stage=\"{}\"}} {}",
+                label, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "rusty_tea_stage_duration_seconds_count{{stage=\"{}\"}} {}",
+                label, histogram.count
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observations_land_in_cumulative_buckets() {
+        let histograms = LatencyHistograms::new(&[1.0, 0.1, 0.5]);
+        histograms.observe(Stage::Llm, Duration::from_millis(50));
+        histograms.observe(Stage::Llm, Duration::from_millis(300));
+        histograms.observe(Stage::Llm, Duration::from_secs(3));
+
+        assert_eq!(histograms.cumulative_counts(Stage::Llm), vec![1, 2, 2, 3]);
+        assert_eq!(histograms.cumulative_counts(Stage::Tts), vec![0, 0, 0, 0]);
+
+        let text = histograms.render();
+        assert!(text.contains("# TYPE rusty_tea_stage_duration_seconds histogram"));
+        assert!(text.contains("rusty_tea_stage_duration_seconds_bucket{stage=\"llm\",le=\"0.5\"} 2"));
+        assert!(text.contains("rusty_tea_stage_duration_seconds_bucket{stage=\"llm\",le=\"+Inf\"} 3"));
+        assert!(text.contains("rusty_tea_stage_duration_seconds_count{stage=\"tts\"} 0"));
+    }
+}
@@ -1,13 +1,48 @@
+pub mod audio_decode;
+pub mod audio_normalize;
+pub mod silence_trim;
+pub mod audio_store;
+pub mod upload_spool;
+pub mod circuit_breaker;
+pub mod model_selector;
+pub mod model_pool;
+pub mod model_source;
+pub mod stt;
 pub mod vosk_service;
 pub mod database_service;
 pub mod qdrant_service;
 pub mod llm_service;
 pub mod elevenlabs_service;
+pub mod tts_quota;
 pub mod voice_session_service;
+pub mod idempotency_service;
+pub mod transcription_job_service;
+pub mod transcript_cache;
+pub mod latency_metrics;
+pub mod api_key_store;
+pub mod text_normalizer;
+pub mod header_text;
+pub mod speech_sanitizer;
+pub mod emotion;
+pub mod sentence_pipeline;
+pub mod endpointing;
+pub mod stream_resume;
+pub mod partial_throttle;
+pub mod prompt_guard;
+pub mod language_detector;
 
+pub use stt::{MockSpeechToText, SpeechToText};
+pub use audio_store::AudioStore;
 pub use vosk_service::VoskService;
 pub use database_service::DatabaseService;
 pub use qdrant_service::RagService;
 pub use llm_service::LlmService;
 pub use elevenlabs_service::ElevenLabsService;
 pub use voice_session_service::VoiceSessionService;
+pub use idempotency_service::IdempotencyService;
+pub use transcription_job_service::TranscriptionJobService;
+pub use transcript_cache::TranscriptCache;
+pub use stream_resume::StreamResumeService;
+pub use latency_metrics::LatencyHistograms;
+pub use api_key_store::ApiKeyStore;
+pub use upload_spool::UploadSpool;
@@ -0,0 +1,256 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing::{debug, info};
+use vosk::Model;
+
+use super::model_source::ModelSource;
+
+/// Suggested wait, in seconds, before retrying a request refused with `ModelLoading`
+pub const MODEL_LOADING_RETRY_AFTER_SECS: u64 = 5;
+
+/// Returned to requests for a model another request is already loading
+#[derive(Debug, thiserror::Error)]
+#[error("Speech model is still loading, try again shortly")]
+pub struct ModelLoading;
+
+/// Loaded Vosk models keyed by path, at most `capacity` of them (least recently
+/// used is evicted). Loading a model takes seconds and hundreds of MB, so
+/// recognizers borrow a shared `Arc<Model>` instead of loading one per request.
+/// An evicted model stays alive until in-flight recognizers drop their `Arc`.
+pub struct ModelPool<M = Model> {
+    capacity: usize,
+    /// Download and fallback rules applied when a Vosk model is loaded
+    source: ModelSource,
+    inner: Mutex<PoolInner<M>>,
+}
+
+struct PoolInner<M> {
+    models: HashMap<String, PoolEntry<M>>,
+    /// Paths being loaded right now; other requests for them get `ModelLoading`
+    loading: HashSet<String>,
+    /// Bumped on every access; orders entries by recency
+    clock: u64,
+}
+
+struct PoolEntry<M> {
+    model: Arc<M>,
+    last_used: u64,
+}
+
+impl<M> ModelPool<M> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            source: ModelSource::default(),
+            inner: Mutex::new(PoolInner {
+                models: HashMap::new(),
+                loading: HashSet::new(),
+                clock: 0,
+            }),
+        }
+    }
+
+    pub fn with_source(mut self, source: ModelSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The cached model for `path`, loading it with `load` on a miss.
+    /// The lock isn't held while loading, so lookups of other models aren't blocked.
+    /// Only the first caller loads a given path; the others get `ModelLoading` until
+    /// it's done instead of each loading their own copy.
+    pub fn get_or_load(&self, path: &str, load: impl FnOnce(&str) -> Result<M>) -> Result<Arc<M>> {
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.clock += 1;
+            let now = inner.clock;
+            if let Some(entry) = inner.models.get_mut(path) {
+                debug!("Model cache hit: {}", path);
+                entry.last_used = now;
+                return Ok(entry.model.clone());
+            }
+            if !inner.loading.insert(path.to_string()) {
+                debug!("Model {} is still loading, refusing request", path);
+                return Err(ModelLoading.into());
+            }
+        }
+        // Declared before `inner` below so it runs after that lock is released
+        let _loading = LoadingGuard { pool: self, path };
+
+        info!("Loading Vosk model from: {}", path);
+        let loaded = load(path);
+
+        let mut inner = self.inner.lock().unwrap();
+        let model = Arc::new(loaded?);
+        inner.clock += 1;
+        let now = inner.clock;
+
+        if inner.models.len() >= self.capacity {
+            let lru = inner
+                .models
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone());
+            if let Some(lru) = lru {
+                info!("Evicting Vosk model {} from the cache", lru);
+                inner.models.remove(&lru);
+            }
+        }
+        inner.models.insert(
+            path.to_string(),
+            PoolEntry {
+                model: model.clone(),
+                last_used: now,
+            },
+        );
+        Ok(model)
+    }
+
+    /// Whether the model at `path` is currently in memory
+    pub fn is_loaded(&self, path: &str) -> bool {
+        self.inner.lock().unwrap().models.contains_key(path)
+    }
+}
+
+/// Clears a path's `loading` mark however its load ends, panics included, so later
+/// requests aren't refused with `ModelLoading` forever
+struct LoadingGuard<'a, M> {
+    pool: &'a ModelPool<M>,
+    path: &'a str,
+}
+
+impl<M> Drop for LoadingGuard<'_, M> {
+    fn drop(&mut self) {
+        let mut inner = self.pool.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.loading.remove(self.path);
+    }
+}
+
+impl ModelPool<Model> {
+    /// The Vosk model at `path`, loaded on first use. When the source had to fall
+    /// back to another model, that model is cached under `path`.
+    pub fn get(&self, path: &str) -> Result<Arc<Model>> {
+        self.get_or_load(path, |path| {
+            self.source.load(path, |path| {
+                Model::new(path)
+                    .ok_or_else(|| anyhow::anyhow!("Failed to load Vosk model from: {}", path))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Stand-in for `vosk::Model` that records how often it was loaded
+    #[derive(Debug)]
+    struct FakeModel(String);
+
+    fn loader(loads: &AtomicUsize) -> impl FnOnce(&str) -> Result<FakeModel> + '_ {
+        move |path| {
+            loads.fetch_add(1, Ordering::SeqCst);
+            Ok(FakeModel(path.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_same_path_reuses_cached_model() {
+        let pool = ModelPool::new(2);
+        let loads = AtomicUsize::new(0);
+
+        let first = pool.get_or_load("/models/en", loader(&loads)).unwrap();
+        let second = pool.get_or_load("/models/en", loader(&loads)).unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.0, "/models/en");
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_least_recently_used_model_is_evicted() {
+        let pool = ModelPool::new(2);
+        let loads = AtomicUsize::new(0);
+
+        pool.get_or_load("/models/en", loader(&loads)).unwrap();
+        pool.get_or_load("/models/es", loader(&loads)).unwrap();
+        // Touch "en" so "es" becomes the least recently used
+        pool.get_or_load("/models/en", loader(&loads)).unwrap();
+        pool.get_or_load("/models/fr", loader(&loads)).unwrap();
+
+        assert!(pool.is_loaded("/models/en"));
+        assert!(!pool.is_loaded("/models/es"));
+        assert!(pool.is_loaded("/models/fr"));
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_concurrent_first_requests_load_once() {
+        let pool: Arc<ModelPool<FakeModel>> = Arc::new(ModelPool::new(2));
+        let loads = Arc::new(AtomicUsize::new(0));
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+
+        let first = {
+            let pool = pool.clone();
+            let loads = loads.clone();
+            std::thread::spawn(move || {
+                pool.get_or_load("/models/en", |path| {
+                    loads.fetch_add(1, Ordering::SeqCst);
+                    started_tx.send(()).unwrap();
+                    release_rx.recv().unwrap();
+                    Ok(FakeModel(path.to_string()))
+                })
+            })
+        };
+        started_rx.recv().unwrap();
+
+        // Refused while the first request is loading, without loading again
+        let err = pool.get_or_load("/models/en", loader(&loads)).unwrap_err();
+        assert!(err.downcast_ref::<ModelLoading>().is_some());
+        assert!(!pool.is_loaded("/models/en"));
+
+        release_tx.send(()).unwrap();
+        let loaded = first.join().unwrap().unwrap();
+        let cached = pool.get_or_load("/models/en", loader(&loads)).unwrap();
+        assert!(Arc::ptr_eq(&loaded, &cached));
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_failed_load_is_not_cached() {
+        let pool: ModelPool<FakeModel> = ModelPool::new(2);
+
+        let err = pool
+            .get_or_load("/models/missing", |path| anyhow::bail!("no model at {}", path))
+            .unwrap_err();
+
+        assert!(err.to_string().contains("/models/missing"));
+        assert!(!pool.is_loaded("/models/missing"));
+
+        // Not stuck loading either: the next request tries again
+        let err = pool
+            .get_or_load("/models/missing", |path| anyhow::bail!("still no model at {}", path))
+            .unwrap_err();
+        assert!(err.to_string().contains("still no model"));
+    }
+
+    #[test]
+    fn test_panicking_load_does_not_leave_path_loading() {
+        let pool: ModelPool<FakeModel> = ModelPool::new(2);
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.get_or_load("/models/en", |_| panic!("corrupt model"))
+        }));
+        assert!(panicked.is_err());
+
+        let model = pool.get_or_load("/models/en", |path| Ok(FakeModel(path.to_string())));
+        assert!(model.is_ok(), "still refused: {:?}", model.err());
+    }
+}
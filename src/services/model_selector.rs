@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Responses averaged to decide whether the LLM is slow
+const LATENCY_WINDOW: usize = 5;
+
+/// The primary model is preferred again once the average drops below this share
+/// of the threshold; the gap keeps the selector from flapping around the threshold
+const RECOVERY_RATIO: f64 = 0.5;
+
+#[derive(Debug)]
+struct Inner {
+    samples: VecDeque<Duration>,
+    using_fast: bool,
+    switched_at: Option<Instant>,
+}
+
+/// Picks the model for new LLM requests from the rolling average latency.
+/// When the average of the last few responses goes over `threshold`, the fast model
+/// is preferred; once it has been in use for at least `hold` and the average is back
+/// under half the threshold, the primary model takes over again.
+/// Without a fast model the primary is always used.
+#[derive(Debug, Clone)]
+pub struct ModelSelector {
+    primary: String,
+    fast: Option<String>,
+    threshold: Duration,
+    hold: Duration,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ModelSelector {
+    pub fn new(primary: &str) -> Self {
+        Self {
+            primary: primary.to_string(),
+            fast: None,
+            threshold: Duration::MAX,
+            hold: Duration::ZERO,
+            inner: Arc::new(Mutex::new(Inner {
+                samples: VecDeque::with_capacity(LATENCY_WINDOW),
+                using_fast: false,
+                switched_at: None,
+            })),
+        }
+    }
+
+    /// Fall back to `fast` while the average latency is above `threshold`,
+    /// staying on it for at least `hold` before reconsidering
+    pub fn with_fast_model(mut self, fast: &str, threshold: Duration, hold: Duration) -> Self {
+        self.fast = Some(fast.to_string());
+        self.threshold = threshold;
+        self.hold = hold;
+        self
+    }
+
+    /// Model to use for the next request
+    pub fn active(&self) -> &str {
+        match &self.fast {
+            Some(fast) if self.inner.lock().unwrap().using_fast => fast,
+            _ => &self.primary,
+        }
+    }
+
+    /// Record how long a successful response took and switch models if needed
+    pub fn record(&self, latency: Duration) {
+        let Some(fast) = &self.fast else {
+            return;
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.samples.len() == LATENCY_WINDOW {
+            inner.samples.pop_front();
+        }
+        inner.samples.push_back(latency);
+        if inner.samples.len() < LATENCY_WINDOW {
+            return;
+        }
+        let average = inner.samples.iter().sum::<Duration>() / inner.samples.len() as u32;
+
+        if !inner.using_fast && average > self.threshold {
+            warn!(
+                "LLM average latency {:?} is over {:?}, switching to {}",
+                average, self.threshold, fast
+            );
+            inner.using_fast = true;
+            inner.switched_at = Some(Instant::now());
+            inner.samples.clear();
+        } else if inner.using_fast
+            && average < self.threshold.mul_f64(RECOVERY_RATIO)
+            && inner.switched_at.map(|at| at.elapsed() >= self.hold).unwrap_or(true)
+        {
+            info!("LLM average latency back to {:?}, switching to {}", average, self.primary);
+            inner.using_fast = false;
+            inner.switched_at = None;
+            inner.samples.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector(hold: Duration) -> ModelSelector {
+        ModelSelector::new("quality-model").with_fast_model(
+            "lite-model",
+            Duration::from_millis(1000),
+            hold,
+        )
+    }
+
+    fn record_many(selector: &ModelSelector, millis: u64) {
+        for _ in 0..LATENCY_WINDOW {
+            selector.record(Duration::from_millis(millis));
+        }
+    }
+
+    #[test]
+    fn test_slow_responses_switch_to_fast_model() {
+        let selector = selector(Duration::from_secs(60));
+        assert_eq!(selector.active(), "quality-model");
+
+        selector.record(Duration::from_millis(3000));
+        // One slow response isn't enough on its own
+        assert_eq!(selector.active(), "quality-model");
+
+        record_many(&selector, 2500);
+        assert_eq!(selector.active(), "lite-model");
+    }
+
+    #[test]
+    fn test_hysteresis_keeps_fast_model_near_threshold() {
+        let selector = selector(Duration::ZERO);
+        record_many(&selector, 2000);
+        assert_eq!(selector.active(), "lite-model");
+
+        // Under the threshold but not under the recovery level
+        record_many(&selector, 800);
+        assert_eq!(selector.active(), "lite-model");
+
+        record_many(&selector, 200);
+        assert_eq!(selector.active(), "quality-model");
+    }
+
+    #[test]
+    fn test_fast_model_held_for_minimum_time() {
+        let selector = selector(Duration::from_secs(60));
+        record_many(&selector, 2000);
+        record_many(&selector, 100);
+
+        assert_eq!(selector.active(), "lite-model");
+    }
+
+    #[test]
+    fn test_without_fast_model_primary_always_used() {
+        let selector = ModelSelector::new("only-model");
+        record_many(&selector, 60_000);
+
+        assert_eq!(selector.active(), "only-model");
+    }
+}
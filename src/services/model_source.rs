@@ -0,0 +1,327 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+/// Fetches a zipped Vosk model from `url` and unpacks it so the model directory
+/// ends up at `dest`. Called from the blocking pool (`spawn_blocking`), like model
+/// loading; it blocks the calling thread until the model is in place.
+pub trait ModelDownloader: Send + Sync {
+    fn download(&self, url: &str, dest: &Path) -> Result<()>;
+}
+
+/// Limits on a model download: connecting, the whole transfer (models run to ~2GB),
+/// and the longest wait for the next chunk before the server counts as stalled
+const DOWNLOAD_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const DOWNLOAD_STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Downloads over HTTP and unpacks with the `unzip` tool (present in the Docker image).
+/// The archive is streamed to disk next to `dest`, never held in memory.
+pub struct HttpModelDownloader;
+
+impl ModelDownloader for HttpModelDownloader {
+    fn download(&self, url: &str, dest: &Path) -> Result<()> {
+        let runtime = tokio::runtime::Handle::try_current()
+            .context("Model download needs a Tokio runtime")?;
+        // block_on panics on a runtime worker thread rather than stalling it, so calling
+        // this outside spawn_blocking fails loudly
+        let archive_path = sibling(dest, "download.zip");
+        let staging = sibling(dest, "download");
+        let unpacked = runtime
+            .block_on(fetch_to_file(url, &archive_path))
+            .and_then(|size| {
+                info!("Downloaded {} byte model archive from {}", size, url);
+                unpack(&archive_path, &staging, dest)
+            });
+        let _ = std::fs::remove_file(&archive_path);
+        let _ = std::fs::remove_dir_all(&staging);
+        unpacked
+    }
+}
+
+/// Stream the body at `url` into `path`, returning its size in bytes
+async fn fetch_to_file(url: &str, path: &Path) -> Result<u64> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(DOWNLOAD_CONNECT_TIMEOUT)
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()?;
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let mut file = tokio::fs::File::create(path)
+        .await
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+
+    let mut size = 0;
+    loop {
+        let chunk = tokio::time::timeout(DOWNLOAD_STALL_TIMEOUT, response.chunk())
+            .await
+            .with_context(|| {
+                format!("Model download stalled for {}s", DOWNLOAD_STALL_TIMEOUT.as_secs())
+            })??;
+        let Some(chunk) = chunk else {
+            break;
+        };
+        file.write_all(&chunk)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        size += chunk.len() as u64;
+    }
+    file.flush().await?;
+    Ok(size)
+}
+
+/// `dest` with `suffix` appended to its file name, next to it on the same volume
+fn sibling(dest: &Path, suffix: &str) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", suffix));
+    dest.with_file_name(name)
+}
+
+/// Unzip into `staging`, then move the model into place. Vosk archives hold a single
+/// top-level directory, which becomes `dest`; anything else is moved as a whole.
+fn unpack(archive: &Path, staging: &Path, dest: &Path) -> Result<()> {
+    let _ = std::fs::remove_dir_all(staging);
+    let status = std::process::Command::new("unzip")
+        .arg("-q")
+        .arg(archive)
+        .arg("-d")
+        .arg(staging)
+        .status()
+        .context("Failed to run unzip")?;
+    if !status.success() {
+        anyhow::bail!("unzip exited with {}", status);
+    }
+
+    let entries: Vec<PathBuf> = std::fs::read_dir(staging)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    let root = match entries.as_slice() {
+        [single] if single.is_dir() => single.clone(),
+        _ => staging.to_path_buf(),
+    };
+    std::fs::rename(&root, dest)
+        .with_context(|| format!("Failed to move model into {}", dest.display()))
+}
+
+/// Check that `path` is an existing directory, as a Vosk model must be, so a wrong
+/// `VOSK_MODEL_PATH` shows up at startup instead of on the first transcription
+pub fn check_model_dir(path: &str) -> Result<()> {
+    let metadata = std::fs::metadata(path)
+        .with_context(|| format!("Vosk model directory {} does not exist", path))?;
+    if !metadata.is_dir() {
+        anyhow::bail!("Vosk model path {} is not a directory", path);
+    }
+    Ok(())
+}
+
+/// Where models come from when the configured path can't be loaded as-is: a missing
+/// path is downloaded from `VOSK_MODEL_URL` first, and a model that still fails to load
+/// is replaced by the one at `VOSK_FALLBACK_MODEL_PATH`
+#[derive(Clone)]
+pub struct ModelSource {
+    fallback_path: Option<String>,
+    url: Option<String>,
+    downloader: Arc<dyn ModelDownloader>,
+    /// Held while downloading so concurrent first requests fetch the model once
+    download_lock: Arc<Mutex<()>>,
+}
+
+impl Default for ModelSource {
+    fn default() -> Self {
+        Self {
+            fallback_path: None,
+            url: None,
+            downloader: Arc::new(HttpModelDownloader),
+            download_lock: Arc::new(Mutex::new(())),
+        }
+    }
+}
+
+impl ModelSource {
+    pub fn with_fallback_path(mut self, path: &str) -> Self {
+        self.fallback_path = Some(path.to_string());
+        self
+    }
+
+    pub fn with_download_url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_downloader(mut self, downloader: Arc<dyn ModelDownloader>) -> Self {
+        self.downloader = downloader;
+        self
+    }
+
+    /// Load the model at `path` with `load`, downloading it first when it's absent
+    /// and a URL is configured, then trying the fallback path if loading fails
+    pub fn load<M>(&self, path: &str, load: impl Fn(&str) -> Result<M>) -> Result<M> {
+        self.download_if_missing(path);
+
+        match load(path) {
+            Ok(model) => Ok(model),
+            Err(e) => {
+                let Some(fallback) = &self.fallback_path else {
+                    return Err(e);
+                };
+                warn!("{}; trying fallback model at {}", e, fallback);
+                let model = load(fallback)
+                    .with_context(|| format!("Fallback model at {} failed too", fallback))?;
+                info!("Loaded fallback Vosk model from {} in place of {}", fallback, path);
+                Ok(model)
+            }
+        }
+    }
+
+    fn download_if_missing(&self, path: &str) {
+        let Some(url) = &self.url else {
+            return;
+        };
+        let dest = Path::new(path);
+        if dest.exists() {
+            return;
+        }
+
+        let _guard = self.download_lock.lock().unwrap();
+        if dest.exists() {
+            // Another request downloaded it while we waited
+            return;
+        }
+        info!("Vosk model missing at {}, downloading from {}", path, url);
+        match self.downloader.download(url, dest) {
+            Ok(()) => info!("Vosk model downloaded to {}", path),
+            Err(e) => warn!("Downloading Vosk model from {} failed: {}", url, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Records downloads; creates the destination directory when `succeed` is set
+    struct FakeDownloader {
+        calls: AtomicUsize,
+        succeed: bool,
+    }
+
+    impl FakeDownloader {
+        fn new(succeed: bool) -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicUsize::new(0),
+                succeed,
+            })
+        }
+    }
+
+    impl ModelDownloader for FakeDownloader {
+        fn download(&self, _url: &str, dest: &Path) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if !self.succeed {
+                anyhow::bail!("connection refused");
+            }
+            std::fs::create_dir_all(dest)?;
+            Ok(())
+        }
+    }
+
+    /// Stand-in for `Model::new`: loads any directory that exists
+    fn load_dir(path: &str) -> Result<String> {
+        if Path::new(path).is_dir() {
+            Ok(path.to_string())
+        } else {
+            anyhow::bail!("Failed to load Vosk model from: {}", path)
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rusty-tea-{}-{}", name, uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_model_dir_check_rejects_missing_path_and_files() {
+        let missing = temp_path("missing");
+        let err = check_model_dir(missing.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("does not exist"), "{}", err);
+
+        let file = temp_path("file");
+        std::fs::write(&file, b"not a model").unwrap();
+        let err = check_model_dir(file.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("is not a directory"), "{}", err);
+        std::fs::remove_file(&file).unwrap();
+
+        let dir = temp_path("model");
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(check_model_dir(dir.to_str().unwrap()).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_primary_falls_back_to_secondary() {
+        let primary = temp_path("primary");
+        let fallback = temp_path("fallback");
+        std::fs::create_dir_all(&fallback).unwrap();
+        let downloader = FakeDownloader::new(false);
+        let source = ModelSource::default()
+            .with_fallback_path(fallback.to_str().unwrap())
+            .with_download_url("http://models.invalid/model.zip")
+            .with_downloader(downloader.clone());
+
+        let model = source.load(primary.to_str().unwrap(), load_dir).unwrap();
+
+        assert_eq!(model, fallback.to_str().unwrap());
+        assert_eq!(downloader.calls.load(Ordering::SeqCst), 1);
+        std::fs::remove_dir_all(&fallback).unwrap();
+    }
+
+    #[test]
+    fn test_missing_primary_downloaded_before_loading() {
+        let primary = temp_path("primary");
+        let downloader = FakeDownloader::new(true);
+        let source = ModelSource::default()
+            .with_fallback_path("/models/unused")
+            .with_download_url("http://models.invalid/model.zip")
+            .with_downloader(downloader.clone());
+
+        let model = source.load(primary.to_str().unwrap(), load_dir).unwrap();
+        assert_eq!(model, primary.to_str().unwrap());
+
+        // Present now, so not fetched again
+        source.load(primary.to_str().unwrap(), load_dir).unwrap();
+        assert_eq!(downloader.calls.load(Ordering::SeqCst), 1);
+        std::fs::remove_dir_all(&primary).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_http_download_streams_to_disk_and_cleans_up() {
+        let app = axum::Router::new().route(
+            "/model.zip",
+            axum::routing::get(|| async { vec![0u8; 256 * 1024] }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let archive = temp_path("archive");
+        let size = fetch_to_file(&format!("http://{}/model.zip", addr), &archive).await.unwrap();
+        assert_eq!(size, 256 * 1024);
+        assert_eq!(std::fs::metadata(&archive).unwrap().len(), 256 * 1024);
+        std::fs::remove_file(&archive).unwrap();
+
+        // Not a zip: unpacking fails, and neither the archive nor the model is left behind
+        let dest = temp_path("model");
+        let url = format!("http://{}/model.zip", addr);
+        let download_dest = dest.clone();
+        let result =
+            tokio::task::spawn_blocking(move || HttpModelDownloader.download(&url, &download_dest))
+                .await
+                .unwrap();
+        assert!(result.is_err());
+        assert!(!dest.exists());
+        assert!(!sibling(&dest, "download.zip").exists());
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+
+/// Each partial re-transcribes the whole utterance so far, so past this much audio
+/// (30s of 16kHz 16-bit mono) an utterance only gets its final
+pub const MAX_PARTIAL_AUDIO_BYTES: usize = 960_000;
+
+/// Limits streamed "partial" messages to one per `interval` so a client sending many
+/// small chunks isn't flooded. Skipped partials aren't queued: the next one sent
+/// covers all audio so far. Finals don't go through the throttle, and long utterances
+/// stop getting partials at `MAX_PARTIAL_AUDIO_BYTES`.
+#[derive(Debug, Clone)]
+pub struct PartialThrottle {
+    interval: Duration,
+    last_sent: Option<Instant>,
+}
+
+impl PartialThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_sent: None,
+        }
+    }
+
+    /// Whether a partial of `buffered_bytes` of audio may go out now; if so, it counts
+    /// as sent
+    pub fn ready(&mut self, buffered_bytes: usize) -> bool {
+        if buffered_bytes > MAX_PARTIAL_AUDIO_BYTES {
+            return false;
+        }
+        let now = Instant::now();
+        match self.last_sent {
+            Some(at) if now.duration_since(at) < self.interval => false,
+            _ => {
+                self.last_sent = Some(now);
+                true
+            }
+        }
+    }
+
+    /// Start over for the next utterance, whose first partial goes out right away
+    pub fn reset(&mut self) {
+        self.last_sent = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partials_within_interval_are_skipped() {
+        let mut throttle = PartialThrottle::new(Duration::from_secs(60));
+
+        assert!(throttle.ready(3200));
+        assert!(!throttle.ready(3200));
+        assert!(!throttle.ready(3200));
+
+        throttle.reset();
+        assert!(throttle.ready(3200));
+    }
+
+    #[test]
+    fn test_zero_interval_sends_every_partial() {
+        let mut throttle = PartialThrottle::new(Duration::ZERO);
+
+        assert!((0..5).all(|_| throttle.ready(3200)));
+    }
+
+    #[test]
+    fn test_no_partials_past_audio_cap() {
+        let mut throttle = PartialThrottle::new(Duration::ZERO);
+
+        assert!(throttle.ready(MAX_PARTIAL_AUDIO_BYTES));
+        assert!(!throttle.ready(MAX_PARTIAL_AUDIO_BYTES + 1));
+
+        // The next utterance starts small again
+        throttle.reset();
+        assert!(throttle.ready(3200));
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::Result;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use std::collections::VecDeque;
+use std::future::Future;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Accumulates streamed text and hands back sentences as soon as they end.
+/// A sentence ends at `.`, `!` or `?` followed by whitespace, so "3.5" isn't split
+/// and punctuation at the very end of a chunk waits for the next one.
+#[derive(Debug, Default)]
+pub struct SentenceSplitter {
+    buffer: String,
+}
+
+impl SentenceSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a chunk of text; returns the sentences it completed, in order
+    pub fn push(&mut self, delta: &str) -> Vec<String> {
+        self.buffer.push_str(delta);
+
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        let mut previous: Option<char> = None;
+        for (index, c) in self.buffer.char_indices() {
+            if c.is_whitespace() && matches!(previous, Some('.' | '!' | '?')) {
+                let sentence = self.buffer[start..index].trim();
+                if !sentence.is_empty() {
+                    sentences.push(sentence.to_string());
+                }
+                start = index;
+            }
+            previous = Some(c);
+        }
+        self.buffer.drain(..start);
+
+        sentences
+    }
+
+    /// The unterminated tail, if any text is left
+    pub fn finish(&mut self) -> Option<String> {
+        let tail = std::mem::take(&mut self.buffer);
+        let tail = tail.trim();
+        (!tail.is_empty()).then(|| tail.to_string())
+    }
+}
+
+/// Full reply text plus its audio. A TTS failure doesn't abort generation, so the
+/// text is always complete; `audio` carries the first TTS error instead.
+pub struct SpokenReply {
+    pub text: String,
+    pub audio: Result<Bytes>,
+}
+
+/// Synthesize `deltas` sentence by sentence while they are still being generated.
+/// Up to `max_concurrent` sentences are synthesized in parallel; audio is
+/// concatenated in sentence order. An error from `deltas` aborts the pipeline.
+pub async fn speak_sentences<S, E, F, Fut>(
+    mut deltas: S,
+    max_concurrent: usize,
+    synthesize: F,
+) -> Result<SpokenReply, E>
+where
+    S: Stream<Item = Result<String, E>> + Unpin,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Bytes>> + Send + 'static,
+{
+    let max_concurrent = max_concurrent.max(1);
+    let mut splitter = SentenceSplitter::new();
+    let mut text = String::new();
+    let mut in_flight = InFlightSentences::default();
+    let mut audio = SentenceAudio::default();
+
+    let schedule = |sentence: String, in_flight: &mut VecDeque<_>| {
+        debug!("Scheduling TTS for sentence: '{}'", sentence);
+        in_flight.push_back(tokio::spawn(synthesize(sentence)));
+    };
+
+    while let Some(delta) = deltas.next().await {
+        let delta = match delta {
+            Ok(delta) => delta,
+            Err(e) => return Err(e),
+        };
+        text.push_str(&delta);
+
+        for sentence in splitter.push(&delta) {
+            while in_flight.len() >= max_concurrent {
+                audio.collect(in_flight.pop_front()).await;
+            }
+            if audio.error.is_none() {
+                schedule(sentence, &mut in_flight);
+            }
+        }
+    }
+
+    if let Some(tail) = splitter.finish() {
+        if audio.error.is_none() {
+            schedule(tail, &mut in_flight);
+        }
+    }
+    while let Some(handle) = in_flight.pop_front() {
+        audio.collect(Some(handle)).await;
+    }
+
+    Ok(SpokenReply {
+        text: text.trim().to_string(),
+        audio: audio.finish(),
+    })
+}
+
+/// Sentences being synthesized, in order. Dropping it (the reply failed, or the turn
+/// was cancelled and the pipeline dropped) aborts the TTS calls still running.
+#[derive(Default)]
+struct InFlightSentences(VecDeque<JoinHandle<Result<Bytes>>>);
+
+impl std::ops::Deref for InFlightSentences {
+    type Target = VecDeque<JoinHandle<Result<Bytes>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for InFlightSentences {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for InFlightSentences {
+    fn drop(&mut self) {
+        self.0.iter().for_each(JoinHandle::abort);
+    }
+}
+
+/// Ordered audio collected so far, or the first synthesis error
+#[derive(Default)]
+struct SentenceAudio {
+    bytes: BytesMut,
+    error: Option<anyhow::Error>,
+}
+
+impl SentenceAudio {
+    async fn collect(&mut self, handle: Option<JoinHandle<Result<Bytes>>>) {
+        let Some(handle) = handle else {
+            return;
+        };
+        let result = match handle.await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow::anyhow!("TTS task failed: {}", e)),
+        };
+        match result {
+            Ok(bytes) if self.error.is_none() => self.bytes.extend_from_slice(&bytes),
+            Ok(_) => {}
+            Err(e) => {
+                if self.error.is_none() {
+                    warn!("Sentence synthesis failed: {}", e);
+                    self.error = Some(e);
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> Result<Bytes> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.bytes.freeze()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn deltas(parts: &[&str]) -> impl Stream<Item = Result<String, String>> + Unpin {
+        futures::stream::iter(
+            parts
+                .iter()
+                .map(|part| Ok(part.to_string()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_splitter_emits_sentences_as_they_complete() {
+        let mut splitter = SentenceSplitter::new();
+
+        assert!(splitter.push("Hello the").is_empty());
+        assert!(splitter.push("re.").is_empty());
+        assert_eq!(splitter.push(" How are"), vec!["Hello there."]);
+        assert_eq!(splitter.push(" you? Pi is 3.14 ok! "), vec!["How are you?", "Pi is 3.14 ok!"]);
+        assert_eq!(splitter.push("Bye"), Vec::<String>::new());
+        assert_eq!(splitter.finish(), Some("Bye".to_string()));
+        assert_eq!(splitter.finish(), None);
+    }
+
+    #[tokio::test]
+    async fn test_three_sentences_synthesized_in_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+
+        // Mocked streaming LLM; the last sentence has no terminal punctuation
+        let stream = deltas(&["Hi there", ". How ", "are you? ", "Tell me ", "more"]);
+        let reply = speak_sentences(stream, 2, move |sentence: String| {
+            recorded.lock().unwrap().push(sentence.clone());
+            async move {
+                // Earlier sentences finish last, so ordering can't come from timing
+                let delay = match sentence.len() {
+                    n if n < 10 => 30,
+                    n if n < 13 => 15,
+                    _ => 0,
+                };
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                Ok(Bytes::from(format!("[{}]", sentence)))
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["Hi there.", "How are you?", "Tell me more"]
+        );
+        assert_eq!(reply.text, "Hi there. How are you? Tell me more");
+        assert_eq!(
+            reply.audio.unwrap(),
+            Bytes::from("[Hi there.][How are you?][Tell me more]")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tts_failure_keeps_full_text() {
+        let stream = deltas(&["One. ", "Two. ", "Three."]);
+        let reply = speak_sentences(stream, 1, |sentence: String| async move {
+            if sentence == "Two." {
+                anyhow::bail!("ElevenLabs down");
+            }
+            Ok(Bytes::from(sentence))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(reply.text, "One. Two. Three.");
+        assert!(reply.audio.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_llm_stream_error_aborts() {
+        let stream = futures::stream::iter(vec![
+            Ok("Hello. ".to_string()),
+            Err("connection reset".to_string()),
+        ]);
+        let result = speak_sentences(stream, 2, |sentence: String| async move {
+            Ok(Bytes::from(sentence))
+        })
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,126 @@
+/// Silence kept either side of the speech so soft onsets and word tails aren't clipped
+pub const GUARD_MS: u32 = 200;
+/// Frame the speech/silence decision is made on
+const FRAME_MS: u32 = 10;
+
+/// Drops leading and trailing silence from a recording as it is fed in chunks, keeping
+/// `GUARD_MS` of it next to the speech. A 10ms frame counts as speech when its RMS level
+/// reaches `threshold` (the same scale as `VAD_ENERGY_THRESHOLD`). Silence between words
+/// is held back until more speech follows, so only the trailing part is lost.
+#[derive(Debug, Clone)]
+pub struct SilenceTrimmer {
+    threshold: f32,
+    frame_len: usize,
+    guard_len: usize,
+    heard_speech: bool,
+    /// Silent samples not passed on yet: the leading guard before speech, a pause after it
+    held: Vec<i16>,
+    /// Samples short of a full frame, carried over to the next chunk
+    partial: Vec<i16>,
+}
+
+impl SilenceTrimmer {
+    pub fn new(threshold: f32, sample_rate: u32) -> Self {
+        Self {
+            threshold,
+            frame_len: (sample_rate * FRAME_MS / 1000).max(1) as usize,
+            guard_len: (sample_rate * GUARD_MS / 1000) as usize,
+            heard_speech: false,
+            held: Vec::new(),
+            partial: Vec::new(),
+        }
+    }
+
+    /// Take the next chunk; returns the samples that can go to the recognizer now
+    pub fn push(&mut self, chunk: &[i16]) -> Vec<i16> {
+        let mut out = Vec::new();
+        self.partial.extend_from_slice(chunk);
+        let complete = self.partial.len() - self.partial.len() % self.frame_len;
+        let frames: Vec<i16> = self.partial.drain(..complete).collect();
+        for frame in frames.chunks(self.frame_len) {
+            self.push_frame(frame, &mut out);
+        }
+        out
+    }
+
+    /// End of the recording: the trailing guard, or nothing if no speech was heard
+    pub fn finish(&mut self) -> Vec<i16> {
+        let mut out = Vec::new();
+        let partial = std::mem::take(&mut self.partial);
+        if !partial.is_empty() {
+            self.push_frame(&partial, &mut out);
+        }
+        if self.heard_speech {
+            let guard = self.guard_len.min(self.held.len());
+            out.extend_from_slice(&self.held[..guard]);
+        }
+        self.held.clear();
+        out
+    }
+
+    fn push_frame(&mut self, frame: &[i16], out: &mut Vec<i16>) {
+        if rms(frame) >= self.threshold {
+            self.heard_speech = true;
+            out.append(&mut self.held);
+            out.extend_from_slice(frame);
+            return;
+        }
+
+        self.held.extend_from_slice(frame);
+        if !self.heard_speech && self.held.len() > self.guard_len {
+            let excess = self.held.len() - self.guard_len;
+            self.held.drain(..excess);
+        }
+    }
+}
+
+fn rms(samples: &[i16]) -> f32 {
+    let sum: f64 = samples.iter().map(|&sample| (sample as f64).powi(2)).sum();
+    (sum / samples.len() as f64).sqrt() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn tone(len: usize) -> Vec<i16> {
+        (0..len).map(|i| (8000.0 * (i as f32 * 0.1).sin()) as i16).collect()
+    }
+
+    /// Feed `samples` in uneven chunks, as the recognizer paths do
+    fn trimmed(samples: &[i16]) -> Vec<i16> {
+        let mut trimmer = SilenceTrimmer::new(500.0, RATE);
+        let mut out = Vec::new();
+        for chunk in samples.chunks(1234) {
+            out.extend(trimmer.push(chunk));
+        }
+        out.extend(trimmer.finish());
+        out
+    }
+
+    #[test]
+    fn test_silence_trimmed_to_guard_margin() {
+        let guard = (RATE * GUARD_MS / 1000) as usize;
+        let speech = tone(8000);
+        let mut padded = vec![0i16; 32000];
+        padded.extend(&speech);
+        padded.extend(vec![0i16; 24000]);
+
+        let out = trimmed(&padded);
+
+        assert_eq!(out.len(), guard + speech.len() + guard);
+        assert_eq!(&out[guard..guard + speech.len()], &speech[..]);
+    }
+
+    #[test]
+    fn test_pauses_between_words_kept_and_silence_only_dropped() {
+        let mut words = tone(1600);
+        words.extend(vec![0i16; 4800]);
+        words.extend(tone(1600));
+
+        assert_eq!(trimmed(&words), words);
+        assert!(trimmed(&[0i16; 16000]).is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+// Clean-up pass for LLM replies before they are spoken. The persona prompt
+// forbids emojis and `*actions*`, but models still slip them in and TTS reads
+// them out literally ("asterisk waves asterisk").
+
+/// Pictographs, dingbats, flags and the joiners/selectors that glue emoji sequences
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF // pictographs, emoticons, transport, flags, supplemental symbols
+            | 0x2600..=0x27BF // misc symbols and dingbats
+            | 0x2300..=0x23FF // watch, hourglass, media controls
+            | 0x2B00..=0x2BFF // stars, arrows, squares
+            | 0x3030 | 0x303D | 0x3297 | 0x3299
+            | 0x200D // zero-width joiner
+            | 0x20E3 // combining keycap
+            | 0xFE0E | 0xFE0F // variation selectors
+            | 0xE0020..=0xE007F // tag sequences (subdivision flags)
+    )
+}
+
+/// Drop `*stage directions*`; a stray unmatched `*` is removed on its own
+fn strip_stage_directions(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('*') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after.find('*') {
+            Some(close) => rest = &after[close + 1..],
+            None => rest = after,
+        }
+        // Keep the words on either side apart
+        out.push(' ');
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Text safe to send to TTS: no emoji, no `*...*` actions, single spaces
+/// and no space left dangling before punctuation
+pub fn sanitize_for_speech(text: &str) -> String {
+    let without_emoji: String = text.chars().filter(|c| !is_emoji(*c)).collect();
+    let stripped = strip_stage_directions(&without_emoji);
+
+    let mut out = String::with_capacity(stripped.len());
+    for word in stripped.split_whitespace() {
+        let attaches = word.starts_with(['.', ',', '!', '?', ';', ':']);
+        if !out.is_empty() && !attaches {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emoji_removed() {
+        assert_eq!(sanitize_for_speech("Hello there! 😊"), "Hello there!");
+        assert_eq!(sanitize_for_speech("Tea time ☕️ is the best 👍🏽."), "Tea time is the best.");
+        assert_eq!(sanitize_for_speech("Family 👨‍👩‍👧 and flags 🇬🇧"), "Family and flags");
+    }
+
+    #[test]
+    fn test_stage_directions_stripped() {
+        assert_eq!(sanitize_for_speech("*waves* Hi, I'm Tea!"), "Hi, I'm Tea!");
+        assert_eq!(
+            sanitize_for_speech("That's lovely *smiles warmly* , tell me more."),
+            "That's lovely, tell me more."
+        );
+        assert_eq!(sanitize_for_speech("*waves*"), "");
+        // An unmatched asterisk is dropped rather than swallowing the rest
+        assert_eq!(sanitize_for_speech("5 * 3 is 15"), "5 3 is 15");
+    }
+
+    #[test]
+    fn test_whitespace_collapsed() {
+        assert_eq!(sanitize_for_speech("  So   much\n\nspace \t here "), "So much space here");
+    }
+
+    #[test]
+    fn test_plain_text_unchanged() {
+        let text = "Green tea has less caffeine than coffee. Want a recipe?";
+        assert_eq!(sanitize_for_speech(text), text);
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::endpointing::Endpointer;
+use super::voice_session_service::SessionOwner;
+
+/// Where a `/api/v1/transcribe/stream` socket is in its current utterance: the audio
+/// buffered since the last final result and the endpointer listening to it
+#[derive(Debug, Clone, Default)]
+pub struct StreamProgress {
+    pub audio_chunks: Vec<Vec<u8>>,
+    pub buffered_bytes: usize,
+    pub endpointer: Option<Endpointer>,
+    /// Utterances already finalized on this stream
+    pub utterances: usize,
+}
+
+/// Parked streams kept per API key; parking another drops that key's oldest
+const MAX_PARKED_PER_OWNER: usize = 16;
+/// Parked streams kept across all keys; parking another drops the oldest
+const MAX_PARKED_TOTAL: usize = 1000;
+
+#[derive(Debug)]
+struct ParkedStream {
+    progress: StreamProgress,
+    parked_at: Instant,
+}
+
+/// Streams whose socket dropped mid-utterance, kept for a grace period under the
+/// client's `session_token` so a reconnect can carry on where it left off. Tokens are
+/// scoped to the API key that opened the stream. A grace period of 0 turns resuming off.
+/// Each parked stream holds up to `STREAM_MAX_AUDIO_BYTES` of audio, so how many are kept
+/// is capped per API key and in total; the oldest make way for new ones.
+#[derive(Clone)]
+pub struct StreamResumeService {
+    parked: Arc<RwLock<HashMap<(SessionOwner, String), ParkedStream>>>,
+    grace: Duration,
+    max_per_owner: usize,
+    max_total: usize,
+}
+
+impl StreamResumeService {
+    pub fn new(grace_secs: u64) -> Self {
+        if grace_secs > 0 {
+            info!("Dropped transcription streams can resume for {} seconds", grace_secs);
+        }
+        Self {
+            parked: Arc::new(RwLock::new(HashMap::new())),
+            grace: Duration::from_secs(grace_secs),
+            max_per_owner: MAX_PARKED_PER_OWNER,
+            max_total: MAX_PARKED_TOTAL,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_limits(mut self, max_per_owner: usize, max_total: usize) -> Self {
+        self.max_per_owner = max_per_owner;
+        self.max_total = max_total;
+        self
+    }
+
+    /// Keep a dropped stream's progress until the grace period runs out
+    pub async fn park(&self, owner: SessionOwner, token: &str, progress: StreamProgress) {
+        if self.grace.is_zero() {
+            return;
+        }
+        debug!(
+            "Parking stream {} with {} buffered bytes",
+            token, progress.buffered_bytes
+        );
+        let mut parked = self.parked.write().await;
+        let key = (owner, token.to_string());
+        parked.remove(&key);
+        // Make room: first within this key's share, then overall
+        while parked.keys().filter(|(o, _)| *o == owner).count() >= self.max_per_owner {
+            evict_oldest(&mut parked, |(o, _)| *o == owner);
+        }
+        while parked.len() >= self.max_total {
+            evict_oldest(&mut parked, |_| true);
+        }
+        parked.insert(
+            key,
+            ParkedStream {
+                progress,
+                parked_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Take back a parked stream; None if there is none or its grace period is over
+    pub async fn resume(&self, owner: SessionOwner, token: &str) -> Option<StreamProgress> {
+        let parked = self.parked.write().await.remove(&(owner, token.to_string()))?;
+        (parked.parked_at.elapsed() <= self.grace).then_some(parked.progress)
+    }
+
+    /// Drop streams nobody came back for (call periodically)
+    pub async fn cleanup_expired(&self) {
+        let mut parked = self.parked.write().await;
+        let initial_count = parked.len();
+        parked.retain(|_, stream| stream.parked_at.elapsed() <= self.grace);
+
+        let removed = initial_count - parked.len();
+        if removed > 0 {
+            debug!("Cleaned up {} abandoned transcription streams", removed);
+        }
+    }
+
+    /// Start background cleanup task
+    pub fn start_cleanup_task(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(10));
+
+            loop {
+                interval.tick().await;
+                self.cleanup_expired().await;
+            }
+        });
+
+        info!("Started stream resume cleanup background task");
+    }
+}
+
+/// Drop the longest-parked stream among those `eligible`
+fn evict_oldest(
+    parked: &mut HashMap<(SessionOwner, String), ParkedStream>,
+    eligible: impl Fn(&(SessionOwner, String)) -> bool,
+) {
+    let oldest = parked
+        .iter()
+        .filter(|(key, _)| eligible(key))
+        .min_by_key(|(_, stream)| stream.parked_at)
+        .map(|(key, _)| key.clone());
+    if let Some(key) = oldest {
+        warn!("Too many parked transcription streams, dropping {}", key.1);
+        parked.remove(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(bytes: usize) -> StreamProgress {
+        StreamProgress {
+            audio_chunks: vec![vec![0; bytes]],
+            buffered_bytes: bytes,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_is_once_and_per_api_key() {
+        let service = StreamResumeService::new(30);
+        let owner = SessionOwner::from_api_key("key-a");
+        service.park(owner, "tok", progress(640)).await;
+
+        assert!(service.resume(SessionOwner::from_api_key("key-b"), "tok").await.is_none());
+        assert_eq!(service.resume(owner, "tok").await.unwrap().buffered_bytes, 640);
+        assert!(service.resume(owner, "tok").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_parked_streams_capped_per_key_and_in_total() {
+        let service = StreamResumeService::new(30).with_limits(2, 3);
+        let (a, b) = (SessionOwner::from_api_key("key-a"), SessionOwner::from_api_key("key-b"));
+
+        for token in ["a1", "a2", "a3"] {
+            service.park(a, token, progress(640)).await;
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        // key-a keeps its two newest
+        assert!(service.resume(a, "a1").await.is_none());
+        service.park(a, "a1", progress(640)).await;
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        assert!(service.resume(a, "a2").await.is_none());
+
+        service.park(b, "b1", progress(640)).await;
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        service.park(b, "b2", progress(640)).await;
+        // Four parked would be over the total of three: the oldest overall went
+        assert_eq!(service.parked.read().await.len(), 3);
+        assert!(service.resume(a, "a3").await.is_none());
+        assert!(service.resume(a, "a1").await.is_some());
+        assert!(service.resume(b, "b1").await.is_some());
+        assert!(service.resume(b, "b2").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_streams_cleaned_up_after_grace() {
+        let service = StreamResumeService::new(30).with_grace(Duration::from_millis(20));
+        let owner = SessionOwner::from_api_key("key-a");
+        service.park(owner, "tok", progress(640)).await;
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        service.cleanup_expired().await;
+
+        assert!(service.parked.read().await.is_empty());
+    }
+}
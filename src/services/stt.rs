@@ -0,0 +1,134 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::Path;
+
+use super::vosk_service::{NoSpeechDetected, Transcript, VoskService};
+use crate::models::WordSegment;
+
+/// Speech-to-text backend, selected with `STT_PROVIDER`
+#[async_trait]
+pub trait SpeechToText: Send + Sync {
+    /// Transcribe a complete uploaded audio file
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<Transcript>;
+
+    /// Transcribe an upload spilled to disk. Backends that can read the file
+    /// incrementally override this; the default loads it back into memory.
+    async fn transcribe_file(&self, path: &Path) -> Result<Transcript> {
+        self.transcribe(tokio::fs::read(path).await?).await
+    }
+
+    /// Transcribe raw PCM chunks collected from a stream, with word timings
+    async fn transcribe_streaming(&self, chunks: Vec<Vec<u8>>) -> Result<Transcript>;
+
+    /// Whether the model at `model_path` is in memory; backends without models report false
+    fn is_model_loaded(&self, _model_path: &str) -> bool {
+        false
+    }
+
+    /// Load the default model now rather than on the first request; backends without
+    /// models have nothing to do
+    async fn warm_up(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SpeechToText for VoskService {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<Transcript> {
+        VoskService::transcribe(self, audio).await
+    }
+
+    async fn transcribe_file(&self, path: &Path) -> Result<Transcript> {
+        VoskService::transcribe_file(self, path).await
+    }
+
+    async fn transcribe_streaming(&self, chunks: Vec<Vec<u8>>) -> Result<Transcript> {
+        VoskService::transcribe_streaming(self, chunks).await
+    }
+
+    fn is_model_loaded(&self, model_path: &str) -> bool {
+        VoskService::is_model_loaded(self, model_path)
+    }
+
+    async fn warm_up(&self) -> Result<()> {
+        VoskService::preload(self).await
+    }
+}
+
+/// Returns a fixed transcript for any non-empty audio.
+/// Used by tests and `STT_PROVIDER=mock` for running without a Vosk model.
+#[derive(Debug, Clone)]
+pub struct MockSpeechToText {
+    text: String,
+    /// Confidence given to every word; without it the transcript has no word info
+    confidence: Option<f32>,
+}
+
+impl MockSpeechToText {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            confidence: None,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_confidence(mut self, confidence: f32) -> Self {
+        self.confidence = Some(confidence);
+        self
+    }
+
+    fn transcript(&self) -> Transcript {
+        let words = match self.confidence {
+            Some(conf) => self
+                .text
+                .split_whitespace()
+                .enumerate()
+                .map(|(i, word)| WordSegment {
+                    word: word.to_string(),
+                    start: i as f32 * 0.5,
+                    end: i as f32 * 0.5 + 0.4,
+                    conf,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        Transcript {
+            text: self.text.clone(),
+            words,
+            raw: Some(serde_json::json!({ "text": self.text })),
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechToText for MockSpeechToText {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<Transcript> {
+        if audio.is_empty() {
+            anyhow::bail!("No audio data provided");
+        }
+        Ok(self.transcript())
+    }
+
+    async fn transcribe_streaming(&self, chunks: Vec<Vec<u8>>) -> Result<Transcript> {
+        if chunks.iter().all(|chunk| chunk.is_empty()) {
+            return Err(NoSpeechDetected.into());
+        }
+        Ok(self.transcript())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_mock_stt_behind_trait_object() {
+        let stt: Arc<dyn SpeechToText> = Arc::new(MockSpeechToText::new("hello tea"));
+
+        assert_eq!(stt.transcribe(vec![1, 2, 3]).await.unwrap().text, "hello tea");
+        assert!(stt.transcribe(vec![]).await.is_err());
+        assert_eq!(stt.transcribe_streaming(vec![vec![0, 0]]).await.unwrap().text, "hello tea");
+    }
+}
@@ -0,0 +1,240 @@
+/// Optional clean-up pass for raw Vosk output, which is lowercase, unpunctuated
+/// and spells numbers out ("twenty three" -> "23").
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Last {
+    None,
+    Unit,
+    Teen,
+    Tens,
+    Hundred,
+    Scale,
+}
+
+enum NumberWord {
+    Unit(u64),
+    Teen(u64),
+    Tens(u64),
+    Hundred,
+    Scale(u64),
+}
+
+fn number_word(word: &str) -> Option<NumberWord> {
+    let word = match word {
+        "zero" => NumberWord::Unit(0),
+        "one" => NumberWord::Unit(1),
+        "two" => NumberWord::Unit(2),
+        "three" => NumberWord::Unit(3),
+        "four" => NumberWord::Unit(4),
+        "five" => NumberWord::Unit(5),
+        "six" => NumberWord::Unit(6),
+        "seven" => NumberWord::Unit(7),
+        "eight" => NumberWord::Unit(8),
+        "nine" => NumberWord::Unit(9),
+        "ten" => NumberWord::Teen(10),
+        "eleven" => NumberWord::Teen(11),
+        "twelve" => NumberWord::Teen(12),
+        "thirteen" => NumberWord::Teen(13),
+        "fourteen" => NumberWord::Teen(14),
+        "fifteen" => NumberWord::Teen(15),
+        "sixteen" => NumberWord::Teen(16),
+        "seventeen" => NumberWord::Teen(17),
+        "eighteen" => NumberWord::Teen(18),
+        "nineteen" => NumberWord::Teen(19),
+        "twenty" => NumberWord::Tens(20),
+        "thirty" => NumberWord::Tens(30),
+        "forty" => NumberWord::Tens(40),
+        "fifty" => NumberWord::Tens(50),
+        "sixty" => NumberWord::Tens(60),
+        "seventy" => NumberWord::Tens(70),
+        "eighty" => NumberWord::Tens(80),
+        "ninety" => NumberWord::Tens(90),
+        "hundred" => NumberWord::Hundred,
+        "thousand" => NumberWord::Scale(1_000),
+        "million" => NumberWord::Scale(1_000_000),
+        "billion" => NumberWord::Scale(1_000_000_000),
+        _ => return None,
+    };
+    Some(word)
+}
+
+/// A spelled-out number being accumulated word by word
+struct PendingNumber {
+    total: u64,
+    current: u64,
+    last: Last,
+    /// "and" seen after hundred/thousand, only kept if another number word follows
+    pending_and: bool,
+}
+
+impl PendingNumber {
+    fn new() -> Self {
+        Self { total: 0, current: 0, last: Last::None, pending_and: false }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.last == Last::None
+    }
+
+    /// Try to extend the number; false means the word starts something new
+    fn push(&mut self, word: &NumberWord) -> bool {
+        let accepted = match (word, self.last) {
+            (NumberWord::Unit(0), Last::None) => true,
+            (NumberWord::Unit(0), _) => false,
+            (NumberWord::Unit(_), Last::None | Last::Tens | Last::Hundred | Last::Scale) => true,
+            (NumberWord::Teen(_), Last::None | Last::Hundred | Last::Scale) => true,
+            (NumberWord::Tens(_), Last::None | Last::Hundred | Last::Scale) => true,
+            (NumberWord::Hundred, Last::Unit | Last::Teen) => self.current > 0,
+            (NumberWord::Scale(_), Last::Unit | Last::Teen | Last::Tens | Last::Hundred) => true,
+            _ => false,
+        };
+        if !accepted {
+            return false;
+        }
+
+        match *word {
+            NumberWord::Unit(n) => {
+                self.current += n;
+                self.last = Last::Unit;
+            }
+            NumberWord::Teen(n) => {
+                self.current += n;
+                self.last = Last::Teen;
+            }
+            NumberWord::Tens(n) => {
+                self.current += n;
+                self.last = Last::Tens;
+            }
+            NumberWord::Hundred => {
+                self.current *= 100;
+                self.last = Last::Hundred;
+            }
+            NumberWord::Scale(scale) => {
+                self.total += self.current * scale;
+                self.current = 0;
+                self.last = Last::Scale;
+            }
+        }
+        self.pending_and = false;
+        true
+    }
+
+    /// Emit the digits (and a dangling "and") and reset
+    fn flush(&mut self, out: &mut Vec<String>) {
+        if !self.is_empty() {
+            out.push((self.total + self.current).to_string());
+            if self.pending_and {
+                out.push("and".to_string());
+            }
+        }
+        *self = Self::new();
+    }
+}
+
+/// Convert spelled-out numbers to digits ("one hundred and five" -> "105").
+/// Adjacent digits stay separate ("one two three" -> "1 2 3").
+pub fn numbers_to_digits(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut pending = PendingNumber::new();
+
+    for token in text.split_whitespace() {
+        let lower = token.to_lowercase();
+
+        if lower == "and" && matches!(pending.last, Last::Hundred | Last::Scale) && !pending.pending_and {
+            pending.pending_and = true;
+            continue;
+        }
+
+        match number_word(&lower) {
+            Some(word) => {
+                if !pending.push(&word) {
+                    pending.flush(&mut out);
+                    if !pending.push(&word) {
+                        // e.g. a bare "hundred"
+                        out.push(token.to_string());
+                    }
+                }
+            }
+            None => {
+                pending.flush(&mut out);
+                out.push(token.to_string());
+            }
+        }
+    }
+    pending.flush(&mut out);
+
+    out.join(" ")
+}
+
+/// Uppercase the first letter of the text and of every word following `.`, `!` or `?`
+pub fn capitalize_sentences(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut at_sentence_start = true;
+
+    for c in text.chars() {
+        if at_sentence_start && c.is_alphabetic() {
+            out.extend(c.to_uppercase());
+            at_sentence_start = false;
+            continue;
+        }
+        if matches!(c, '.' | '!' | '?') {
+            at_sentence_start = true;
+        } else if !c.is_whitespace() {
+            at_sentence_start = false;
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+/// Full post-processing pass applied with `?normalize=true`
+pub fn normalize_transcript(text: &str) -> String {
+    capitalize_sentences(&numbers_to_digits(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_phrases() {
+        assert_eq!(numbers_to_digits("twenty three"), "23");
+        assert_eq!(numbers_to_digits("i have twenty three cups"), "i have 23 cups");
+        assert_eq!(numbers_to_digits("one hundred and five"), "105");
+        assert_eq!(numbers_to_digits("two thousand and twenty four"), "2024");
+        assert_eq!(numbers_to_digits("nineteen hundred"), "1900");
+        assert_eq!(numbers_to_digits("three million four hundred thousand"), "3400000");
+        assert_eq!(numbers_to_digits("zero"), "0");
+    }
+
+    #[test]
+    fn test_adjacent_digits_stay_separate() {
+        assert_eq!(numbers_to_digits("one two three"), "1 2 3");
+        assert_eq!(numbers_to_digits("twenty thirty"), "20 30");
+        assert_eq!(numbers_to_digits("seven zero"), "7 0");
+    }
+
+    #[test]
+    fn test_non_numeric_and_is_kept() {
+        assert_eq!(numbers_to_digits("bread and butter"), "bread and butter");
+        assert_eq!(numbers_to_digits("one hundred and milk"), "100 and milk");
+        assert_eq!(numbers_to_digits("a hundred"), "a hundred");
+    }
+
+    #[test]
+    fn test_capitalization() {
+        assert_eq!(capitalize_sentences("hello there"), "Hello there");
+        assert_eq!(capitalize_sentences("hi. how are you? fine"), "Hi. How are you? Fine");
+        assert_eq!(capitalize_sentences(""), "");
+    }
+
+    #[test]
+    fn test_normalize_transcript() {
+        assert_eq!(
+            normalize_transcript("i would like twenty three cups of tea"),
+            "I would like 23 cups of tea"
+        );
+        assert_eq!(normalize_transcript("forty two"), "42");
+    }
+}
@@ -0,0 +1,173 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{debug, info};
+
+use super::vosk_service::Transcript;
+
+/// Transcripts kept at once; caching another drops the oldest
+const MAX_CACHED_TRANSCRIPTS: usize = 1000;
+
+/// SHA-256 of an upload, fed chunk by chunk as it arrives
+#[derive(Debug, Clone, Default)]
+pub struct AudioHasher(Sha256);
+
+impl AudioHasher {
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    /// Lowercase hex digest
+    pub fn finish(self) -> String {
+        format!("{:x}", self.0.finalize())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedTranscript {
+    transcript: Transcript,
+    created_at: Instant,
+}
+
+/// Transcripts keyed by the SHA-256 of the audio, so a client retrying the same
+/// upload (e.g. after a dropped connection) gets the earlier result without another
+/// recognizer run. A TTL of 0 turns the cache off. At most `MAX_CACHED_TRANSCRIPTS`
+/// are kept, so a burst of distinct uploads within the TTL can't grow it without bound.
+#[derive(Clone)]
+pub struct TranscriptCache {
+    entries: Arc<RwLock<HashMap<String, CachedTranscript>>>,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+impl TranscriptCache {
+    pub fn new(ttl_secs: u64) -> Self {
+        if ttl_secs > 0 {
+            info!("Caching transcripts of identical audio for {} seconds", ttl_secs);
+        }
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            ttl: Duration::from_secs(ttl_secs),
+            max_entries: MAX_CACHED_TRANSCRIPTS,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// The transcript of audio with this digest, if it's cached and not expired
+    pub async fn get(&self, digest: &str) -> Option<Transcript> {
+        let entries = self.entries.read().await;
+        entries
+            .get(digest)
+            .filter(|cached| cached.created_at.elapsed() <= self.ttl)
+            .map(|cached| cached.transcript.clone())
+    }
+
+    pub async fn store(&self, digest: &str, transcript: Transcript) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.write().await;
+        if !entries.contains_key(digest) && entries.len() >= self.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.created_at)
+                .map(|(digest, _)| digest.clone());
+            if let Some(oldest) = oldest {
+                debug!("Transcript cache full, dropping audio {}", oldest);
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            digest.to_string(),
+            CachedTranscript {
+                transcript,
+                created_at: Instant::now(),
+            },
+        );
+        debug!("Cached transcript for audio {}", digest);
+    }
+
+    /// Drop expired entries (call periodically)
+    pub async fn cleanup_expired(&self) {
+        let mut entries = self.entries.write().await;
+        let initial_count = entries.len();
+        entries.retain(|_, cached| cached.created_at.elapsed() <= self.ttl);
+
+        let removed = initial_count - entries.len();
+        if removed > 0 {
+            debug!("Cleaned up {} expired cached transcripts", removed);
+        }
+    }
+
+    /// Start background cleanup task
+    pub fn start_cleanup_task(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+
+            loop {
+                interval.tick().await;
+                self.cleanup_expired().await;
+            }
+        });
+
+        info!("Started transcript cache cleanup background task");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(text: &str) -> Transcript {
+        Transcript {
+            text: text.to_string(),
+            words: Vec::new(),
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn test_digest_independent_of_chunking() {
+        let mut whole = AudioHasher::default();
+        whole.update(b"RIFF audio bytes");
+        let mut chunked = AudioHasher::default();
+        chunked.update(b"RIFF ");
+        chunked.update(b"audio bytes");
+
+        let digest = whole.finish();
+        assert_eq!(digest, chunked.finish());
+        assert_eq!(digest.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_cache() {
+        let cache = TranscriptCache::new(0);
+        cache.store("abc", transcript("hello tea")).await;
+
+        assert!(cache.get("abc").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_full_cache_drops_oldest_transcript() {
+        let cache = TranscriptCache::new(300).with_max_entries(2);
+        for digest in ["a", "b", "c"] {
+            cache.store(digest, transcript(digest)).await;
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+
+        assert!(cache.get("a").await.is_none());
+        assert_eq!(cache.get("b").await.unwrap().text, "b");
+        assert_eq!(cache.get("c").await.unwrap().text, "c");
+
+        // Storing a cached digest again doesn't evict anything
+        cache.store("b", transcript("b")).await;
+        assert!(cache.get("c").await.is_some());
+    }
+}
@@ -0,0 +1,359 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Url};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::voice_session_service::SessionOwner;
+use crate::models::TranscriptionResponse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+/// State of a background transcription, polled via GET /api/v1/transcriptions/:id and
+/// POSTed to the job's callback URL once it completes or fails
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionJob {
+    pub id: Uuid,
+    pub status: JobStatus,
+    pub result: Option<TranscriptionResponse>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// API key that queued the job; only it can poll the job
+    #[serde(skip)]
+    pub owner: SessionOwner,
+}
+
+/// Returned for a callback URL we won't POST to
+#[derive(Debug, thiserror::Error)]
+pub enum CallbackUrlRejected {
+    #[error("callback_url must be an absolute http(s) URL")]
+    Invalid,
+    #[error("callback_url host could not be resolved")]
+    Unresolvable,
+    #[error("callback_url must not point at a private, loopback or link-local address")]
+    NotPublic,
+}
+
+/// In-memory store of async transcription jobs plus webhook delivery
+#[derive(Clone)]
+pub struct TranscriptionJobService {
+    jobs: Arc<RwLock<HashMap<Uuid, TranscriptionJob>>>,
+    client: Client,
+    job_ttl: chrono::Duration,
+    max_attempts: u32,
+    retry_delay: Duration,
+    /// `ALLOW_PRIVATE_CALLBACKS`: also deliver to internal addresses
+    allow_private: bool,
+}
+
+impl TranscriptionJobService {
+    pub fn new(job_ttl_minutes: i64) -> Result<Self> {
+        info!("Initializing TranscriptionJobService with TTL: {} minutes", job_ttl_minutes);
+
+        let client = callback_client_builder()
+            .build()
+            .context("Failed to create HTTP client for transcription callbacks")?;
+
+        Ok(Self {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            client,
+            job_ttl: chrono::Duration::minutes(job_ttl_minutes),
+            max_attempts: 3,
+            retry_delay: Duration::from_secs(1),
+            allow_private: false,
+        })
+    }
+
+    /// Allow callbacks to private, loopback and link-local addresses (webhook receivers
+    /// inside the same network)
+    pub fn with_private_callbacks(mut self) -> Self {
+        warn!("Transcription callbacks may target private addresses");
+        self.allow_private = true;
+        self
+    }
+
+    /// Check that `callback_url` is http(s) and, unless private callbacks are allowed,
+    /// that every address its host resolves to is public
+    pub async fn check_callback_url(&self, callback_url: &str) -> Result<(), CallbackUrlRejected> {
+        self.resolve_callback(callback_url).await.map(|_| ())
+    }
+
+    /// The callback's host and the public addresses it resolves to (None when private
+    /// callbacks are allowed and nothing needs pinning)
+    async fn resolve_callback(
+        &self,
+        callback_url: &str,
+    ) -> Result<Option<(String, Vec<SocketAddr>)>, CallbackUrlRejected> {
+        let url = Url::parse(callback_url).map_err(|_| CallbackUrlRejected::Invalid)?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(CallbackUrlRejected::Invalid);
+        }
+        let host = url.host_str().ok_or(CallbackUrlRejected::Invalid)?;
+        let port = url.port_or_known_default().ok_or(CallbackUrlRejected::Invalid)?;
+        if self.allow_private {
+            return Ok(None);
+        }
+
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|_| CallbackUrlRejected::Unresolvable)?
+                .collect(),
+        };
+        if addrs.is_empty() {
+            return Err(CallbackUrlRejected::Unresolvable);
+        }
+        if !addrs.iter().all(|addr| is_public(addr.ip())) {
+            warn!("Rejecting callback URL {} resolving to {:?}", callback_url, addrs);
+            return Err(CallbackUrlRejected::NotPublic);
+        }
+        Ok(Some((host.to_string(), addrs)))
+    }
+
+    /// Register a new pending job for `owner` and return its id
+    pub async fn create(&self, owner: SessionOwner) -> Uuid {
+        let id = Uuid::new_v4();
+        let job = TranscriptionJob {
+            id,
+            status: JobStatus::Pending,
+            result: None,
+            error: None,
+            created_at: Utc::now(),
+            owner,
+        };
+        self.jobs.write().await.insert(id, job);
+        id
+    }
+
+    /// The job if `owner` queued it; None for unknown ids and other keys' jobs alike
+    pub async fn get(&self, id: Uuid, owner: SessionOwner) -> Option<TranscriptionJob> {
+        let jobs = self.jobs.read().await;
+        jobs.get(&id).filter(|job| job.owner == owner).cloned()
+    }
+
+    pub async fn complete(&self, id: Uuid, result: TranscriptionResponse) -> Option<TranscriptionJob> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs.get_mut(&id)?;
+        job.status = JobStatus::Completed;
+        job.result = Some(result);
+        Some(job.clone())
+    }
+
+    pub async fn fail(&self, id: Uuid, error: String) -> Option<TranscriptionJob> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs.get_mut(&id)?;
+        job.status = JobStatus::Failed;
+        job.error = Some(error);
+        Some(job.clone())
+    }
+
+    /// POST `payload` to the client's callback URL, retrying with exponential backoff
+    pub async fn deliver_callback<T: Serialize>(&self, callback_url: &str, payload: &T) -> Result<()> {
+        // Connect to the addresses just checked, so the host can't switch to an
+        // internal one between the check and the request
+        let client = match self.resolve_callback(callback_url).await? {
+            Some((host, addrs)) => callback_client_builder()
+                .resolve_to_addrs(&host, &addrs)
+                .build()
+                .context("Failed to create HTTP client for transcription callbacks")?,
+            None => self.client.clone(),
+        };
+        let mut delay = self.retry_delay;
+
+        for attempt in 1..=self.max_attempts {
+            let outcome = client
+                .post(callback_url)
+                .json(payload)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match outcome {
+                Ok(_) => {
+                    info!("Delivered transcription callback to {} (attempt {})", callback_url, attempt);
+                    return Ok(());
+                }
+                Err(e) if attempt < self.max_attempts => {
+                    warn!(
+                        "Callback to {} failed (attempt {}/{}): {}. Retrying in {:?}",
+                        callback_url, attempt, self.max_attempts, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!(
+                        "Callback to {} failed after {} attempts: {}",
+                        callback_url,
+                        self.max_attempts,
+                        e
+                    ));
+                }
+            }
+        }
+
+        unreachable!("callback loop always returns")
+    }
+
+    /// Drop jobs older than the TTL (call periodically)
+    pub async fn cleanup_expired_jobs(&self) {
+        let cutoff = Utc::now() - self.job_ttl;
+        let mut jobs = self.jobs.write().await;
+        let initial_count = jobs.len();
+        jobs.retain(|_, job| job.created_at > cutoff);
+
+        let removed = initial_count - jobs.len();
+        if removed > 0 {
+            info!("Cleaned up {} expired transcription jobs", removed);
+        }
+    }
+
+    /// Start background cleanup task
+    pub fn start_cleanup_task(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(5 * 60));
+
+            loop {
+                interval.tick().await;
+                self.cleanup_expired_jobs().await;
+            }
+        });
+
+        info!("Started transcription job cleanup background task");
+    }
+}
+
+/// Whether `ip` is reachable on the public internet: not loopback, private, link-local,
+/// shared (CGNAT), unspecified, multicast, broadcast or documentation space
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || (first & 0xfe00) == 0xfc00 // unique local
+                    || (first & 0xffc0) == 0xfe80) // link-local
+            }
+        },
+    }
+}
+
+/// Callbacks don't follow redirects: they could lead to an internal host
+fn callback_client_builder() -> reqwest::ClientBuilder {
+    Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(reqwest::redirect::Policy::none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, MockUpstream};
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let service = TranscriptionJobService::new(60).unwrap();
+        let owner = test_support::session_owner();
+        let id = service.create(owner).await;
+        assert_eq!(service.get(id, owner).await.unwrap().status, JobStatus::Pending);
+        // Another key can't see it
+        assert!(service.get(id, SessionOwner::from_api_key("another-tenant")).await.is_none());
+
+        let response = TranscriptionResponse::new("hello".to_string(), "en".to_string(), 1.0);
+        let job = service.complete(id, response).await.unwrap();
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.result.unwrap().text, "hello");
+    }
+
+    #[tokio::test]
+    async fn test_callback_receives_result() {
+        let receiver = MockUpstream::start("unused").await;
+        let service = TranscriptionJobService::new(60).unwrap().with_private_callbacks();
+        let id = service.create(test_support::session_owner()).await;
+        let response = TranscriptionResponse::new("hello world".to_string(), "en".to_string(), 2.5);
+        let job = service.complete(id, response).await.unwrap();
+
+        let url = format!("{}/callback", receiver.base_url);
+        service.deliver_callback(&url, &job).await.unwrap();
+
+        // Same envelope as a failed job or a poll: the job with its status and result
+        let delivered = receiver.requests_to("/callback");
+        assert_eq!(delivered.len(), 1);
+        let body = delivered[0].json();
+        assert_eq!(body["id"], id.to_string());
+        assert_eq!(body["status"], "completed");
+        assert_eq!(body["result"]["text"], "hello world");
+        assert_eq!(body["result"]["duration"], 2.5);
+        assert!(body["error"].is_null());
+        assert!(body.get("owner").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_callback_retries_then_gives_up() {
+        let receiver = MockUpstream::start("unused").await;
+        let mut service = TranscriptionJobService::new(60).unwrap().with_private_callbacks();
+        service.retry_delay = Duration::from_millis(1);
+
+        // The mock upstream answers unknown paths with 404
+        let url = format!("{}/missing", receiver.base_url);
+        let result = service.deliver_callback(&url, &serde_json::json!({})).await;
+
+        assert!(result.is_err());
+        assert_eq!(receiver.requests_to("/missing").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_internal_callback_urls_rejected() {
+        let receiver = MockUpstream::start("unused").await;
+        let service = TranscriptionJobService::new(60).unwrap();
+
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.1.2.3/hook",
+            "http://[::1]/hook",
+            "http://[::ffff:192.168.0.1]/hook",
+            "http://localhost/hook",
+        ] {
+            let err = service.check_callback_url(url).await.unwrap_err();
+            assert!(matches!(err, CallbackUrlRejected::NotPublic), "{}: {}", url, err);
+        }
+        let err = service.check_callback_url("file:///etc/passwd").await.unwrap_err();
+        assert!(matches!(err, CallbackUrlRejected::Invalid));
+        service.check_callback_url("https://93.184.216.34/hook").await.unwrap();
+
+        // Delivery refuses too, without sending anything
+        let url = format!("{}/callback", receiver.base_url);
+        assert!(service.deliver_callback(&url, &serde_json::json!({})).await.is_err());
+        assert!(receiver.requests_to("/callback").is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Returned instead of calling ElevenLabs once a voice has used its monthly characters
+#[derive(Debug, thiserror::Error)]
+#[error("Monthly text-to-speech quota of {limit} characters used up for voice {voice_id}")]
+pub struct TtsQuotaExceeded {
+    pub voice_id: String,
+    pub limit: u64,
+}
+
+/// Characters synthesized per voice in the current month (`GET /api/v1/stats`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TtsUsage {
+    /// Calendar month the counts are for, e.g. "2026-10" (UTC)
+    pub period: String,
+    /// Characters each voice may use per month; 0 means unlimited
+    pub monthly_limit: u64,
+    pub voices: BTreeMap<String, u64>,
+}
+
+/// In-memory count of characters sent to ElevenLabs per voice id, reset at the start
+/// of each calendar month (UTC). Counts start from zero on restart.
+#[derive(Debug, Default)]
+pub struct TtsQuota {
+    monthly_limit: u64,
+    state: Mutex<QuotaPeriod>,
+}
+
+#[derive(Debug, Default)]
+struct QuotaPeriod {
+    period: String,
+    used: HashMap<String, u64>,
+}
+
+impl TtsQuota {
+    /// `monthly_limit` characters per voice; 0 only counts
+    pub fn new(monthly_limit: u64) -> Self {
+        Self {
+            monthly_limit,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Reserve `chars` for `voice_id`, or fail if that would go over the limit. The
+    /// characters are given back when the reservation is dropped without `commit`.
+    pub fn reserve(
+        &self,
+        voice_id: &str,
+        chars: u64,
+    ) -> Result<QuotaReservation<'_>, TtsQuotaExceeded> {
+        self.reserve_at(voice_id, chars, Utc::now())
+    }
+
+    fn reserve_at(
+        &self,
+        voice_id: &str,
+        chars: u64,
+        now: DateTime<Utc>,
+    ) -> Result<QuotaReservation<'_>, TtsQuotaExceeded> {
+        let mut state = self.current(now);
+        let used = state.used.entry(voice_id.to_string()).or_default();
+        if self.monthly_limit > 0 && *used + chars > self.monthly_limit {
+            return Err(TtsQuotaExceeded {
+                voice_id: voice_id.to_string(),
+                limit: self.monthly_limit,
+            });
+        }
+        *used += chars;
+        Ok(QuotaReservation {
+            quota: self,
+            period: state.period.clone(),
+            voice_id: voice_id.to_string(),
+            chars,
+            committed: false,
+        })
+    }
+
+    pub fn usage(&self) -> TtsUsage {
+        self.usage_at(Utc::now())
+    }
+
+    fn usage_at(&self, now: DateTime<Utc>) -> TtsUsage {
+        let state = self.current(now);
+        TtsUsage {
+            period: state.period.clone(),
+            monthly_limit: self.monthly_limit,
+            voices: state.used.iter().map(|(voice, &used)| (voice.clone(), used)).collect(),
+        }
+    }
+
+    /// The counts for `now`'s month, starting over when the month has changed
+    fn current(&self, now: DateTime<Utc>) -> std::sync::MutexGuard<'_, QuotaPeriod> {
+        let period = format!("{:04}-{:02}", now.year(), now.month());
+        let mut state = self.state.lock().unwrap();
+        if state.period != period {
+            state.period = period;
+            state.used.clear();
+        }
+        state
+    }
+}
+
+/// Characters held for one synthesis call. Dropped without `commit` (the call failed,
+/// or the request was cancelled mid-way) it gives them back, unless the month has
+/// rolled over since.
+#[must_use = "dropping a reservation gives its characters back"]
+pub struct QuotaReservation<'a> {
+    quota: &'a TtsQuota,
+    period: String,
+    voice_id: String,
+    chars: u64,
+    committed: bool,
+}
+
+impl QuotaReservation<'_> {
+    /// Keep the characters: the audio was produced
+    pub fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl Drop for QuotaReservation<'_> {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        let mut state = self.quota.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.period != self.period {
+            return;
+        }
+        if let Some(used) = state.used.get_mut(&self.voice_id) {
+            *used = used.saturating_sub(self.chars);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_quota_exceeded_until_next_month() {
+        let quota = TtsQuota::new(100);
+        let october = Utc.with_ymd_and_hms(2026, 10, 31, 23, 59, 0).unwrap();
+        let november = Utc.with_ymd_and_hms(2026, 11, 1, 0, 0, 0).unwrap();
+
+        quota.reserve_at("voice-a", 60, october).unwrap().commit();
+        quota.reserve_at("voice-a", 40, october).unwrap().commit();
+        let exceeded = quota.reserve_at("voice-a", 1, october).err().unwrap();
+        assert_eq!(exceeded.voice_id, "voice-a");
+        assert_eq!(exceeded.limit, 100);
+        // Voices are counted separately
+        quota.reserve_at("voice-b", 100, october).unwrap().commit();
+        assert_eq!(quota.usage_at(october).voices["voice-a"], 100);
+
+        // A new month starts from zero
+        quota.reserve_at("voice-a", 100, november).unwrap().commit();
+        let usage = quota.usage_at(november);
+        assert_eq!(usage.period, "2026-11");
+        assert_eq!(usage.voices.get("voice-a"), Some(&100));
+        assert_eq!(usage.voices.get("voice-b"), None);
+    }
+
+    #[test]
+    fn test_zero_limit_only_counts() {
+        let quota = TtsQuota::new(0);
+        quota.reserve("voice", 1_000_000).unwrap().commit();
+        quota.reserve("voice", 1_000_000).unwrap().commit();
+        assert_eq!(quota.usage().voices["voice"], 2_000_000);
+    }
+
+    #[test]
+    fn test_dropped_reservation_gives_characters_back() {
+        let quota = TtsQuota::new(100);
+        let october = Utc.with_ymd_and_hms(2026, 10, 31, 23, 59, 0).unwrap();
+        let november = Utc.with_ymd_and_hms(2026, 11, 1, 0, 0, 0).unwrap();
+
+        quota.reserve_at("voice", 30, october).unwrap().commit();
+        let failed = quota.reserve_at("voice", 70, october).unwrap();
+        assert!(quota.reserve_at("voice", 1, october).is_err());
+        drop(failed);
+        assert_eq!(quota.usage_at(october).voices["voice"], 30);
+
+        // Held across the month boundary: November's count is not touched
+        let late = quota.reserve_at("voice", 70, october).unwrap();
+        quota.reserve_at("voice", 10, november).unwrap().commit();
+        drop(late);
+        assert_eq!(quota.usage_at(november).voices["voice"], 10);
+    }
+}
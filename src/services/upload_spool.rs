@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Creates spill files for large uploads and counts them for /status
+#[derive(Debug, Clone, Default)]
+pub struct UploadSpool {
+    spilled: Arc<AtomicU64>,
+}
+
+impl UploadSpool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uploads spilled to disk by this spool since startup
+    pub fn spilled_count(&self) -> u64 {
+        self.spilled.load(Ordering::Relaxed)
+    }
+
+    pub async fn create(&self) -> Result<SpilledUpload> {
+        let upload = SpilledUpload::create().await?;
+        self.spilled.fetch_add(1, Ordering::Relaxed);
+        Ok(upload)
+    }
+}
+
+/// An upload written to a temp file as it arrives, so the recognizer can read it back
+/// in chunks instead of holding the encoded bytes and the decoded samples in memory
+/// together. The file is removed when this is dropped.
+#[derive(Debug)]
+pub struct SpilledUpload {
+    path: PathBuf,
+    /// Open while the upload is still being received
+    file: Option<File>,
+    len: usize,
+}
+
+impl SpilledUpload {
+    async fn create() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("rusty-tea-upload-{}", Uuid::new_v4()));
+        let file = File::create(&path)
+            .await
+            .with_context(|| format!("Failed to create spill file {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            file: Some(file),
+            len: 0,
+        })
+    }
+
+    pub async fn append(&mut self, bytes: &[u8]) -> Result<()> {
+        let file = self
+            .file
+            .as_mut()
+            .context("Spilled upload is already finished")?;
+        file.write_all(bytes)
+            .await
+            .with_context(|| format!("Failed to spill upload to {}", self.path.display()))?;
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    /// Flush and close the file; `path` is ready for readers afterwards
+    pub async fn finish(&mut self) -> Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()
+                .await
+                .with_context(|| format!("Failed to spill upload to {}", self.path.display()))?;
+        }
+        debug!("Spilled {} byte upload to {}", self.len, self.path.display());
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for SpilledUpload {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!("Failed to remove spilled upload {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spilled_upload_removed_on_drop() {
+        let spool = UploadSpool::new();
+        let mut upload = spool.create().await.unwrap();
+        upload.append(b"RIFF ").await.unwrap();
+        upload.append(b"audio").await.unwrap();
+        upload.finish().await.unwrap();
+        let path = upload.path().to_path_buf();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"RIFF audio");
+        assert_eq!(spool.spilled_count(), 1);
+
+        drop(upload);
+        assert!(!path.exists());
+    }
+}
@@ -1,14 +1,249 @@
-use std::collections::HashMap;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
-use tracing::{info, debug};
+use tracing::{info, debug, warn, error};
+
+/// Attempts made by the writer before a turn is handed to the dead-letter queue
+const PERSIST_ATTEMPTS: u32 = 2;
+const PERSIST_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Turns waiting for the background writer; when it falls this far behind (the
+/// database is down or slow), new turns go straight to the dead-letter queue
+const PERSIST_QUEUE_CAPACITY: usize = 1024;
+
+/// Dead-lettered turns kept for retry; past this the oldest are dropped (and counted)
+const MAX_DEAD_LETTERS: usize = 10_000;
+
+/// Window for the per-session LLM call limit
+const LLM_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// One message in a voice session, stamped when it was added
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    /// Message id, reused when the turn is persisted
+    pub id: Uuid,
+    pub role: String,
+    pub content: String,
+    pub at: DateTime<Utc>,
+}
+
+impl Turn {
+    pub fn new(role: &str, content: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            role: role.to_string(),
+            content: content.to_string(),
+            at: Utc::now(),
+        }
+    }
+
+    /// (role, content) shape expected by `LlmService`
+    pub fn to_llm_message(&self) -> (String, String) {
+        (self.role.clone(), self.content.clone())
+    }
+}
+
+/// Durable copy of voice session turns (the in-memory session stays authoritative).
+/// `owner` is the key the session belongs to, so the copy stays private to it.
+#[async_trait]
+pub trait TurnStore: Send + Sync {
+    async fn save_turn(
+        &self,
+        session_id: Uuid,
+        owner: Option<SessionOwner>,
+        turn: &Turn,
+    ) -> anyhow::Result<()>;
+}
+
+/// A turn whose persistence failed, waiting for the background retry
+#[derive(Debug, Clone)]
+struct FailedTurn {
+    session_id: Uuid,
+    owner: Option<SessionOwner>,
+    turn: Turn,
+}
+
+/// Turns whose persistence failed, oldest first, at most `capacity` of them
+struct DeadLetters {
+    queue: Mutex<VecDeque<FailedTurn>>,
+    capacity: usize,
+    /// Turns that exhausted their retries (or found the writer's queue full)
+    failures: AtomicU64,
+    /// Oldest turns dropped to stay under `capacity`; these are lost
+    dropped: AtomicU64,
+    /// Turns handed to the background writer and not yet handled
+    pending: AtomicU64,
+}
+
+impl DeadLetters {
+    fn new(capacity: usize) -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            capacity,
+            failures: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            pending: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue a failed turn for retry
+    async fn push(&self, failed: FailedTurn) {
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        error!(
+            "Persisting turn for session {} failed, queued for retry ({} failures total)",
+            failed.session_id, failures
+        );
+        let mut queue = self.queue.lock().await;
+        queue.push_back(failed);
+        self.trim(&mut queue);
+    }
+
+    /// Drop the oldest turns over `capacity`
+    fn trim(&self, queue: &mut VecDeque<FailedTurn>) {
+        while queue.len() > self.capacity {
+            if let Some(lost) = queue.pop_front() {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                error!(
+                    "Dead-letter queue full, dropped turn {} of session {} ({} dropped total)",
+                    lost.turn.id, lost.session_id, dropped
+                );
+            }
+        }
+    }
+}
+
+/// Background writer: saves turns in order with a bounded retry, dead-lettering the
+/// ones that still fail
+async fn write_turns(
+    store: Arc<dyn TurnStore>,
+    dead_letters: Arc<DeadLetters>,
+    mut turns: mpsc::Receiver<FailedTurn>,
+) {
+    while let Some(failed) = turns.recv().await {
+        for attempt in 1..=PERSIST_ATTEMPTS {
+            match store.save_turn(failed.session_id, failed.owner, &failed.turn).await {
+                Ok(()) => break,
+                Err(e) if attempt < PERSIST_ATTEMPTS => {
+                    warn!(
+                        "Persisting turn for session {} failed (attempt {}): {}",
+                        failed.session_id, attempt, e
+                    );
+                    tokio::time::sleep(PERSIST_RETRY_BACKOFF * attempt).await;
+                }
+                Err(e) => {
+                    warn!("Persisting turn for session {} failed: {}", failed.session_id, e);
+                    dead_letters.push(failed.clone()).await;
+                }
+            }
+        }
+        dead_letters.pending.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// LLM tuning for one session (`PATCH /api/v1/voice-sessions/:id/settings`);
+/// unset fields use the service defaults
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct SessionSettings {
+    pub temperature: Option<f32>,
+    pub system_prompt: Option<String>,
+}
+
+/// The API key that opened a session, kept as a hash rather than the key itself.
+/// SHA-256 based so the value is stable across builds; it is stored with recorded audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SessionOwner(u64);
+
+impl SessionOwner {
+    pub fn from_api_key(api_key: &str) -> Self {
+        use sha2::{Digest, Sha256};
+        let digest = Sha256::digest(api_key.as_bytes());
+        Self(u64::from_be_bytes(digest[..8].try_into().unwrap()))
+    }
+}
+
+impl std::fmt::Display for SessionOwner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Returned when a session is used with a different API key than the one that opened it
+#[derive(Debug, thiserror::Error)]
+#[error("Voice session belongs to another API key")]
+pub struct SessionForbidden;
+
+/// Returned when a session has used up its LLM calls for the current minute
+#[derive(Debug, thiserror::Error)]
+#[error("Too many LLM calls for this voice session")]
+pub struct SessionRateLimited;
+
+/// Turns of one session being answered right now, sharing one cancellation token
+struct InFlightTurns {
+    token: CancellationToken,
+    count: usize,
+}
+
+/// Held while a turn is answered; `cancelled` resolves once `cancel_turns` is called
+/// for the session. Dropping it marks the turn finished.
+pub struct TurnGuard {
+    session_id: Uuid,
+    token: CancellationToken,
+    in_flight: Arc<std::sync::Mutex<HashMap<Uuid, InFlightTurns>>>,
+}
+
+impl TurnGuard {
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        // A cancelled token's entry was already removed by `cancel_turns`
+        if self.token.is_cancelled() {
+            return;
+        }
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(turns) = in_flight.get_mut(&self.session_id) {
+            turns.count -= 1;
+            if turns.count == 0 {
+                in_flight.remove(&self.session_id);
+            }
+        }
+    }
+}
+
+/// Rough tokens-per-character ratio for English text (about 4 characters per token)
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Size of one session, for `GET /api/v1/admin/sessions`
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub message_count: usize,
+    /// Characters / 4 summed over the messages; close enough for capacity planning
+    pub estimated_tokens: usize,
+    pub idle_secs: u64,
+}
 
 /// In-memory voice chat session with TTL
 #[derive(Debug, Clone)]
 pub struct VoiceSession {
-    pub messages: Vec<(String, String)>, // (role, content)
+    pub messages: Vec<Turn>,
+    /// Key that opened the session; other keys are refused (see `claim`)
+    pub owner: Option<SessionOwner>,
+    /// ElevenLabs voice chosen for this session (service default when unset)
+    pub voice_id: Option<String>,
+    pub settings: SessionSettings,
+    /// When the LLM was called for this session within the last minute
+    llm_calls: VecDeque<Instant>,
     pub last_activity: Instant,
 }
 
@@ -16,6 +251,10 @@ impl VoiceSession {
     fn new() -> Self {
         Self {
             messages: Vec::new(),
+            owner: None,
+            voice_id: None,
+            settings: SessionSettings::default(),
+            llm_calls: VecDeque::new(),
             last_activity: Instant::now(),
         }
     }
@@ -25,13 +264,20 @@ impl VoiceSession {
     }
 
     fn add_message(&mut self, role: &str, content: &str) {
-        self.messages.push((role.to_string(), content.to_string()));
+        self.messages.push(Turn::new(role, content));
         self.update_activity();
     }
 
     fn is_expired(&self, ttl: Duration) -> bool {
         self.last_activity.elapsed() > ttl
     }
+
+    fn estimated_tokens(&self) -> usize {
+        self.messages
+            .iter()
+            .map(|turn| turn.content.chars().count().div_ceil(CHARS_PER_TOKEN))
+            .sum()
+    }
 }
 
 /// Service for managing ephemeral voice chat sessions
@@ -39,6 +285,15 @@ impl VoiceSession {
 pub struct VoiceSessionService {
     sessions: Arc<RwLock<HashMap<Uuid, VoiceSession>>>,
     session_ttl: Duration,
+    cleanup_interval: Duration,
+    store: Option<Arc<dyn TurnStore>>,
+    /// Feeds the background writer; set with the store
+    writer: Option<mpsc::Sender<FailedTurn>>,
+    dead_letters: Arc<DeadLetters>,
+    /// LLM calls allowed per session per minute (0 = unlimited)
+    llm_calls_per_minute: u32,
+    /// Turns being answered, so `cancel_turns` can stop them (barge-in)
+    in_flight: Arc<std::sync::Mutex<HashMap<Uuid, InFlightTurns>>>,
 }
 
 impl VoiceSessionService {
@@ -47,11 +302,44 @@ impl VoiceSessionService {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             session_ttl: Duration::from_secs(session_ttl_minutes * 60),
+            cleanup_interval: Duration::from_secs(5 * 60),
+            store: None,
+            writer: None,
+            dead_letters: Arc::new(DeadLetters::new(MAX_DEAD_LETTERS)),
+            llm_calls_per_minute: 0,
+            in_flight: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
 
-    /// Get conversation history for a session
-    pub async fn get_history(&self, session_id: Uuid) -> Vec<(String, String)> {
+    /// Allow each session at most `calls_per_minute` LLM calls (0 = unlimited), so one
+    /// runaway client can't keep the LLM busy
+    pub fn with_llm_rate_limit(mut self, calls_per_minute: u32) -> Self {
+        self.llm_calls_per_minute = calls_per_minute;
+        self
+    }
+
+    /// Also write every turn to `store` (best-effort; failures never fail the request).
+    /// Turns are written by a background task, so requests never wait on the database.
+    pub fn with_store(mut self, store: Arc<dyn TurnStore>) -> Self {
+        let (writer, turns) = mpsc::channel(PERSIST_QUEUE_CAPACITY);
+        tokio::spawn(write_turns(store.clone(), self.dead_letters.clone(), turns));
+        self.store = Some(store);
+        self.writer = Some(writer);
+        self
+    }
+
+    /// Random delay before the first cleanup so replicas started together stagger
+    fn initial_cleanup_delay(&self) -> Duration {
+        self.cleanup_interval.mul_f64(rand::thread_rng().gen_range(0.0..1.0))
+    }
+
+    /// Next cleanup interval with ±20% jitter (averages to `cleanup_interval`)
+    fn jittered_cleanup_interval(&self) -> Duration {
+        self.cleanup_interval.mul_f64(rand::thread_rng().gen_range(0.8..1.2))
+    }
+
+    /// Get the timestamped turns for a session
+    pub async fn get_turns(&self, session_id: Uuid) -> Vec<Turn> {
         let sessions = self.sessions.read().await;
         
         if let Some(session) = sessions.get(&session_id) {
@@ -63,8 +351,27 @@ impl VoiceSessionService {
         }
     }
 
-    /// Add a message to the session history
-    pub async fn add_message(&self, session_id: Uuid, role: &str, content: &str) {
+    /// One turn of a session by its message id
+    pub async fn get_turn(&self, session_id: Uuid, message_id: Uuid) -> Option<Turn> {
+        let sessions = self.sessions.read().await;
+        let session = sessions.get(&session_id)?;
+        session.messages.iter().find(|turn| turn.id == message_id).cloned()
+    }
+
+    /// Get conversation history for a session in the LLM's (role, content) format
+    pub async fn get_history(&self, session_id: Uuid) -> Vec<(String, String)> {
+        self.get_turns(session_id)
+            .await
+            .iter()
+            .map(Turn::to_llm_message)
+            .collect()
+    }
+
+    /// Add a message to the session history; returns the new turn's message id.
+    /// Turns are recorded in user/assistant pairs with `add_exchange`; tests use this to
+    /// build up history.
+    #[cfg(test)]
+    pub(crate) async fn add_message(&self, session_id: Uuid, role: &str, content: &str) -> Uuid {
         let mut sessions = self.sessions.write().await;
         
         let session = sessions.entry(session_id).or_insert_with(VoiceSession::new);
@@ -72,6 +379,279 @@ impl VoiceSessionService {
         
         debug!("Added {} message to session {}: {} total messages", 
                role, session_id, session.messages.len());
+
+        let turn = session.messages[session.messages.len() - 1].clone();
+        let owner = session.owner;
+        drop(sessions);
+        let id = turn.id;
+        self.persist(session_id, owner, turn).await;
+        id
+    }
+
+    /// Add a user message and the assistant's reply to it together, so history never
+    /// holds one without the other; returns the user turn's message id
+    pub async fn add_exchange(&self, session_id: Uuid, user: &str, assistant: &str) -> Uuid {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.entry(session_id).or_insert_with(VoiceSession::new);
+        session.add_message("user", user);
+        session.add_message("assistant", assistant);
+        let turns = session.messages[session.messages.len() - 2..].to_vec();
+        let owner = session.owner;
+        debug!("Added exchange to session {}: {} total messages", session_id, session.messages.len());
+        drop(sessions);
+
+        let id = turns[0].id;
+        for turn in turns {
+            self.persist(session_id, owner, turn).await;
+        }
+        id
+    }
+
+    /// Hand a turn to the background writer; when its queue is full the turn goes to
+    /// the dead-letter queue for `retry_dead_letters` instead
+    async fn persist(&self, session_id: Uuid, owner: Option<SessionOwner>, turn: Turn) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+
+        self.dead_letters.pending.fetch_add(1, Ordering::Relaxed);
+        if let Err(e) = writer.try_send(FailedTurn { session_id, owner, turn }) {
+            self.dead_letters.pending.fetch_sub(1, Ordering::Relaxed);
+            warn!("Turn writer is behind, dead-lettering turn for session {}", session_id);
+            self.dead_letters.push(e.into_inner()).await;
+        }
+    }
+
+    /// Wait until the writer has handled every turn added so far
+    #[cfg(test)]
+    pub(crate) async fn flush_persistence(&self) {
+        while self.persist_queue_len() > 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    /// Re-attempt every dead-lettered turn once; returns how many were written
+    pub async fn retry_dead_letters(&self) -> usize {
+        let Some(store) = &self.store else {
+            return 0;
+        };
+
+        let pending = std::mem::take(&mut *self.dead_letters.queue.lock().await);
+        let mut written = 0;
+        let mut still_failing = Vec::new();
+        for failed in pending {
+            match store.save_turn(failed.session_id, failed.owner, &failed.turn).await {
+                Ok(()) => written += 1,
+                Err(e) => {
+                    debug!("Dead-lettered turn for session {} still failing: {}", failed.session_id, e);
+                    still_failing.push(failed);
+                }
+            }
+        }
+
+        if written > 0 || !still_failing.is_empty() {
+            info!("Dead-letter retry: {} written, {} still pending", written, still_failing.len());
+        }
+        // Still older than anything dead-lettered during the retry, so back in front
+        let mut queue = self.dead_letters.queue.lock().await;
+        for failed in still_failing.into_iter().rev() {
+            queue.push_front(failed);
+        }
+        self.dead_letters.trim(&mut queue);
+        written
+    }
+
+    /// Turns waiting in the dead-letter queue (for monitoring)
+    pub async fn dead_letter_count(&self) -> usize {
+        self.dead_letters.queue.lock().await.len()
+    }
+
+    /// Turns that exhausted their retries since startup (for monitoring)
+    pub fn persist_failure_count(&self) -> u64 {
+        self.dead_letters.failures.load(Ordering::Relaxed)
+    }
+
+    /// Turns waiting for the background writer (for monitoring)
+    pub fn persist_queue_len(&self) -> u64 {
+        self.dead_letters.pending.load(Ordering::Relaxed)
+    }
+
+    /// Dead-lettered turns dropped because the queue was full (lost for good)
+    pub fn dead_letter_drop_count(&self) -> u64 {
+        self.dead_letters.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Check that `owner` may use the session, binding it to `owner` on first use.
+    /// Sessions that don't have an owner yet (e.g. restored internally) are adopted.
+    pub async fn claim(&self, session_id: Uuid, owner: SessionOwner) -> Result<(), SessionForbidden> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.entry(session_id).or_insert_with(VoiceSession::new);
+        match session.owner {
+            Some(existing) if existing != owner => {
+                warn!("Refused access to voice session {} from another API key", session_id);
+                Err(SessionForbidden)
+            }
+            _ => {
+                session.owner = Some(owner);
+                Ok(())
+            }
+        }
+    }
+
+    /// Whether `owner` may read an existing session, without creating or claiming it:
+    /// None when there is no such session
+    pub async fn check_owner(
+        &self,
+        session_id: Uuid,
+        owner: SessionOwner,
+    ) -> Option<Result<(), SessionForbidden>> {
+        let sessions = self.sessions.read().await;
+        let session = sessions.get(&session_id)?;
+        match session.owner {
+            Some(existing) if existing != owner => {
+                warn!("Refused access to voice session {} from another API key", session_id);
+                Some(Err(SessionForbidden))
+            }
+            _ => Some(Ok(())),
+        }
+    }
+
+    /// Mark a turn of `session_id` as being answered until the guard is dropped
+    pub fn begin_turn(&self, session_id: Uuid) -> TurnGuard {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let turns = in_flight.entry(session_id).or_insert_with(|| InFlightTurns {
+            token: CancellationToken::new(),
+            count: 0,
+        });
+        turns.count += 1;
+
+        TurnGuard {
+            session_id,
+            token: turns.token.clone(),
+            in_flight: self.in_flight.clone(),
+        }
+    }
+
+    /// Cancel every turn of `session_id` being answered. Returns false when none was.
+    /// Turns started afterwards run normally.
+    pub fn cancel_turns(&self, session_id: Uuid) -> bool {
+        let Some(turns) = self.in_flight.lock().unwrap().remove(&session_id) else {
+            return false;
+        };
+        info!("Cancelling {} in-flight turn(s) of voice session {}", turns.count, session_id);
+        turns.token.cancel();
+        true
+    }
+
+    /// Count an LLM call against the session's per-minute limit. Refused calls
+    /// aren't counted, so a client that backs off gets through once the window moves on.
+    pub async fn record_llm_call(&self, session_id: Uuid) -> Result<(), SessionRateLimited> {
+        if self.llm_calls_per_minute == 0 {
+            return Ok(());
+        }
+
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.entry(session_id).or_insert_with(VoiceSession::new);
+        let now = Instant::now();
+        while session
+            .llm_calls
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= LLM_RATE_WINDOW)
+        {
+            session.llm_calls.pop_front();
+        }
+
+        if session.llm_calls.len() >= self.llm_calls_per_minute as usize {
+            warn!(
+                "Voice session {} is over {} LLM calls per minute",
+                session_id, self.llm_calls_per_minute
+            );
+            return Err(SessionRateLimited);
+        }
+        session.llm_calls.push_back(now);
+        Ok(())
+    }
+
+    /// Remember the session's preferred TTS voice
+    pub async fn set_voice(&self, session_id: Uuid, voice_id: &str) {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.entry(session_id).or_insert_with(VoiceSession::new);
+        session.voice_id = Some(voice_id.to_string());
+        session.update_activity();
+    }
+
+    /// The session's preferred TTS voice, if one was set
+    pub async fn get_voice(&self, session_id: Uuid) -> Option<String> {
+        let sessions = self.sessions.read().await;
+        sessions.get(&session_id).and_then(|session| session.voice_id.clone())
+    }
+
+    /// Change the session's LLM settings in place (creating the session if needed);
+    /// returns the settings now in effect
+    pub async fn update_settings(
+        &self,
+        session_id: Uuid,
+        update: impl FnOnce(&mut SessionSettings),
+    ) -> SessionSettings {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.entry(session_id).or_insert_with(VoiceSession::new);
+        update(&mut session.settings);
+        session.update_activity();
+        session.settings.clone()
+    }
+
+    /// The session's LLM settings (defaults for unknown sessions)
+    pub async fn get_settings(&self, session_id: Uuid) -> SessionSettings {
+        let sessions = self.sessions.read().await;
+        sessions
+            .get(&session_id)
+            .map(|session| session.settings.clone())
+            .unwrap_or_default()
+    }
+
+    /// Start a session with an assistant greeting if it has no history yet.
+    /// Returns true when the greeting was added (at most once per session).
+    pub async fn add_greeting_if_new(&self, session_id: Uuid, greeting: &str) -> bool {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.entry(session_id).or_insert_with(VoiceSession::new);
+        if !session.messages.is_empty() {
+            return false;
+        }
+
+        session.add_message("assistant", greeting);
+        debug!("Greeted new voice session {}", session_id);
+
+        let turn = session.messages[0].clone();
+        let owner = session.owner;
+        drop(sessions);
+        self.persist(session_id, owner, turn).await;
+        true
+    }
+
+    /// Swap the content of the session's last turn if it is still the assistant reply
+    /// `turn_id`. Returns false when the session is gone or has moved on since that
+    /// reply was read (e.g. another exchange was added in the meantime). The turn keeps
+    /// its id, so the persisted copy is updated in place.
+    pub async fn replace_last_assistant(&self, session_id: Uuid, turn_id: Uuid, content: &str) -> bool {
+        let mut sessions = self.sessions.write().await;
+        let Some(session) = sessions.get_mut(&session_id) else {
+            return false;
+        };
+
+        let replaced = match session.messages.last_mut() {
+            Some(turn) if turn.id == turn_id && turn.role == "assistant" => {
+                turn.content = content.to_string();
+                turn.at = Utc::now();
+                turn.clone()
+            }
+            _ => return false,
+        };
+        session.update_activity();
+        let owner = session.owner;
+        drop(sessions);
+
+        self.persist(session_id, owner, replaced).await;
+        true
     }
 
     /// Clean up expired sessions (call periodically)
@@ -93,30 +673,96 @@ impl VoiceSessionService {
         }
     }
 
+    /// Drop every session (incident response); returns how many were removed
+    pub async fn clear_all(&self) -> usize {
+        let mut sessions = self.sessions.write().await;
+        let removed = sessions.len();
+        sessions.clear();
+        info!("Flushed all {} voice sessions", removed);
+        removed
+    }
+
+    /// Every live session with its size, largest first (for capacity planning)
+    pub async fn list_sessions(&self) -> Vec<SessionInfo> {
+        let sessions = self.sessions.read().await;
+        let mut infos: Vec<SessionInfo> = sessions
+            .iter()
+            .map(|(id, session)| SessionInfo {
+                id: *id,
+                message_count: session.messages.len(),
+                estimated_tokens: session.estimated_tokens(),
+                idle_secs: session.last_activity.elapsed().as_secs(),
+            })
+            .collect();
+        infos.sort_by(|a, b| {
+            b.estimated_tokens
+                .cmp(&a.estimated_tokens)
+                .then(a.id.cmp(&b.id))
+        });
+        infos
+    }
+
     /// Get current session count (for monitoring)
     pub async fn active_session_count(&self) -> usize {
         self.sessions.read().await.len()
     }
 
-    /// Start background cleanup task
+    /// Start background cleanup task (every ~5 minutes, jittered so replicas don't align)
     pub fn start_cleanup_task(self) {
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(5 * 60)); // Check every 5 minutes
-            
+            tokio::time::sleep(self.initial_cleanup_delay()).await;
+
             loop {
-                interval.tick().await;
                 self.cleanup_expired_sessions().await;
+                tokio::time::sleep(self.jittered_cleanup_interval()).await;
             }
         });
         
         info!("Started voice session cleanup background task");
     }
+
+    /// Start background re-persistence of dead-lettered turns (no-op without a store)
+    pub fn start_dead_letter_task(self, interval: Duration) {
+        if self.store.is_none() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                self.retry_dead_letters().await;
+            }
+        });
+
+        info!("Started voice session dead-letter retry task");
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_cancel_stops_only_turns_in_flight() {
+        let service = VoiceSessionService::new(30);
+        let session_id = Uuid::new_v4();
+        assert!(!service.cancel_turns(session_id));
+
+        let first = service.begin_turn(session_id);
+        let second = service.begin_turn(session_id);
+        assert!(service.cancel_turns(session_id));
+        first.cancelled().await;
+        second.cancelled().await;
+        drop(first);
+
+        // A turn after the cancel gets a fresh token, and a finished turn leaves nothing behind
+        let next = service.begin_turn(session_id);
+        assert!(!next.token.is_cancelled());
+        drop(second);
+        drop(next);
+        assert!(!service.cancel_turns(session_id));
+    }
+
     #[tokio::test]
     async fn test_session_creation() {
         let service = VoiceSessionService::new(30);
@@ -130,6 +776,223 @@ mod tests {
         assert_eq!(history[0].1, "Hello");
     }
 
+    #[tokio::test]
+    async fn test_turns_are_timestamped_in_order() {
+        let service = VoiceSessionService::new(30);
+        let session_id = Uuid::new_v4();
+
+        for (role, content) in [("user", "one"), ("assistant", "two"), ("user", "three")] {
+            service.add_message(session_id, role, content).await;
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+
+        let turns = service.get_turns(session_id).await;
+        assert_eq!(turns.len(), 3);
+        assert!(turns.windows(2).all(|pair| pair[0].at < pair[1].at));
+
+        let history = service.get_history(session_id).await;
+        let expected: Vec<(String, String)> = [("user", "one"), ("assistant", "two"), ("user", "three")]
+            .iter()
+            .map(|(role, content)| (role.to_string(), content.to_string()))
+            .collect();
+        assert_eq!(history, expected);
+    }
+
+    #[tokio::test]
+    async fn test_replace_skipped_once_session_moves_on() {
+        let service = VoiceSessionService::new(30);
+        let session_id = Uuid::new_v4();
+        service.add_exchange(session_id, "Hi", "Hello!").await;
+
+        // Read before the LLM call, then another exchange lands
+        let read = service.get_turns(session_id).await.last().unwrap().id;
+        service.add_exchange(session_id, "Any oolong?", "Plenty.").await;
+
+        assert!(!service.replace_last_assistant(session_id, read, "Hey there!").await);
+        let history = service.get_history(session_id).await;
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[3].1, "Plenty.");
+
+        let latest = service.get_turns(session_id).await.last().unwrap().id;
+        assert!(service.replace_last_assistant(session_id, latest, "Lots of it.").await);
+        assert_eq!(service.get_history(session_id).await[3].1, "Lots of it.");
+    }
+
+    #[tokio::test]
+    async fn test_llm_rate_limit_is_per_session() {
+        let service = VoiceSessionService::new(30).with_llm_rate_limit(2);
+        let busy = Uuid::new_v4();
+        let quiet = Uuid::new_v4();
+
+        assert!(service.record_llm_call(busy).await.is_ok());
+        assert!(service.record_llm_call(busy).await.is_ok());
+        assert!(service.record_llm_call(busy).await.is_err());
+        assert!(service.record_llm_call(quiet).await.is_ok());
+
+        let unlimited = VoiceSessionService::new(30);
+        for _ in 0..100 {
+            assert!(unlimited.record_llm_call(busy).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_info_estimate_grows_with_messages() {
+        let service = VoiceSessionService::new(30);
+        let session_id = Uuid::new_v4();
+        let info = |infos: Vec<SessionInfo>| {
+            infos.into_iter().find(|info| info.id == session_id).unwrap()
+        };
+
+        service.add_message(session_id, "user", "What's a good green tea?").await;
+        let first = info(service.list_sessions().await);
+        assert_eq!(first.message_count, 1);
+        assert_eq!(first.estimated_tokens, 6);
+
+        service
+            .add_message(session_id, "assistant", "Sencha is a lovely everyday green tea to start with.")
+            .await;
+        let second = info(service.list_sessions().await);
+        assert_eq!(second.message_count, 2);
+        assert!(second.estimated_tokens > first.estimated_tokens);
+    }
+
+    #[tokio::test]
+    async fn test_clear_all_returns_removed_count() {
+        let service = VoiceSessionService::new(30);
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        service.add_message(first, "user", "Hello").await;
+        service.add_message(second, "user", "Hi").await;
+
+        assert_eq!(service.clear_all().await, 2);
+        assert_eq!(service.active_session_count().await, 0);
+        assert!(service.get_history(first).await.is_empty());
+        assert_eq!(service.clear_all().await, 0);
+    }
+
+    #[test]
+    fn test_replicas_stagger_first_cleanup() {
+        let first = VoiceSessionService::new(30).initial_cleanup_delay();
+        let second = VoiceSessionService::new(30).initial_cleanup_delay();
+
+        assert!(first < Duration::from_secs(5 * 60));
+        assert!(second < Duration::from_secs(5 * 60));
+        let gap = first.abs_diff(second);
+        assert!(gap > Duration::from_millis(1), "first cleanups {:?} and {:?} coincide", first, second);
+    }
+
+    #[test]
+    fn test_jittered_interval_averages_to_configured() {
+        let service = VoiceSessionService::new(30);
+        let samples = 2000;
+
+        let total: Duration = (0..samples)
+            .map(|_| {
+                let interval = service.jittered_cleanup_interval();
+                assert!(interval >= Duration::from_secs(240) && interval <= Duration::from_secs(360));
+                interval
+            })
+            .sum();
+
+        let mean = total.as_secs_f64() / samples as f64;
+        assert!((mean - 300.0).abs() < 300.0 * 0.02, "mean interval {}s", mean);
+    }
+
+    /// Store that fails its first `failures` writes
+    struct FlakyStore {
+        failures: AtomicU64,
+        saved: std::sync::Mutex<Vec<(Uuid, String)>>,
+    }
+
+    #[async_trait]
+    impl TurnStore for FlakyStore {
+        async fn save_turn(
+            &self,
+            session_id: Uuid,
+            _owner: Option<SessionOwner>,
+            turn: &Turn,
+        ) -> anyhow::Result<()> {
+            let remaining = self.failures.load(Ordering::SeqCst);
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::SeqCst);
+                anyhow::bail!("connection reset");
+            }
+            self.saved.lock().unwrap().push((session_id, turn.content.clone()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_turn_failing_persistence_twice_is_written_on_retry() {
+        let store = Arc::new(FlakyStore {
+            failures: AtomicU64::new(2),
+            saved: std::sync::Mutex::new(Vec::new()),
+        });
+        let service = VoiceSessionService::new(30).with_store(store.clone());
+        let session_id = Uuid::new_v4();
+
+        service.add_message(session_id, "user", "Hello").await;
+        service.flush_persistence().await;
+
+        // The request still sees the turn; persistence is parked, not lost
+        assert_eq!(service.get_history(session_id).await.len(), 1);
+        assert!(store.saved.lock().unwrap().is_empty());
+        assert_eq!(service.dead_letter_count().await, 1);
+        assert_eq!(service.persist_failure_count(), 1);
+
+        assert_eq!(service.retry_dead_letters().await, 1);
+        assert_eq!(service.dead_letter_count().await, 0);
+        assert_eq!(*store.saved.lock().unwrap(), vec![(session_id, "Hello".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_replaced_reply_is_persisted_under_its_id() {
+        let store = Arc::new(FlakyStore {
+            failures: AtomicU64::new(0),
+            saved: std::sync::Mutex::new(Vec::new()),
+        });
+        let service = VoiceSessionService::new(30).with_store(store.clone());
+        let session_id = Uuid::new_v4();
+        service.add_exchange(session_id, "Hi", "Hello!").await;
+        let reply = service.get_turns(session_id).await[1].clone();
+
+        assert!(service.replace_last_assistant(session_id, reply.id, "Hey there!").await);
+        service.flush_persistence().await;
+
+        let replaced = service.get_turns(session_id).await[1].clone();
+        assert_eq!(replaced.id, reply.id);
+        assert!(replaced.at > reply.at);
+        let saved: Vec<String> = store.saved.lock().unwrap().iter().map(|(_, c)| c.clone()).collect();
+        assert_eq!(saved, ["Hi", "Hello!", "Hey there!"]);
+    }
+
+    #[tokio::test]
+    async fn test_full_dead_letter_queue_drops_oldest() {
+        let store = Arc::new(FlakyStore {
+            failures: AtomicU64::new(u64::MAX),
+            saved: std::sync::Mutex::new(Vec::new()),
+        });
+        let mut service = VoiceSessionService::new(30);
+        service.dead_letters = Arc::new(DeadLetters::new(2));
+        let service = service.with_store(store.clone());
+        let session_id = Uuid::new_v4();
+
+        for content in ["one", "two", "three"] {
+            service.add_message(session_id, "user", content).await;
+        }
+        service.flush_persistence().await;
+
+        assert_eq!(service.dead_letter_count().await, 2);
+        assert_eq!(service.persist_failure_count(), 3);
+        assert_eq!(service.dead_letter_drop_count(), 1);
+
+        // The newest two are the ones written once the store recovers
+        store.failures.store(0, Ordering::SeqCst);
+        assert_eq!(service.retry_dead_letters().await, 2);
+        let saved: Vec<String> = store.saved.lock().unwrap().iter().map(|(_, c)| c.clone()).collect();
+        assert_eq!(saved, ["two", "three"]);
+    }
+
     #[tokio::test]
     async fn test_session_expiry() {
         let service = VoiceSessionService::new(0); // 0 minute TTL for testing
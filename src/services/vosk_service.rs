@@ -1,150 +1,466 @@
 use anyhow::Result;
-use tracing::{info, error, debug};
-use vosk::{Model, Recognizer};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tracing::{info, error, debug, warn};
+use serde::Deserialize;
+use vosk::{CompleteResult, Recognizer};
+
+use super::audio_decode;
+use super::audio_normalize::AudioNormalizer;
+use super::model_pool::ModelPool;
+use super::model_source::ModelSource;
+use super::silence_trim::SilenceTrimmer;
+use crate::models::WordSegment;
+
+/// Final transcript with per-word timings
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub text: String,
+    pub words: Vec<WordSegment>,
+    /// The recognizer's full result as Vosk returned it (words, confidences,
+    /// alternatives), for `debug=true` responses
+    pub raw: Option<serde_json::Value>,
+}
+
+impl Transcript {
+    /// Utterance-level confidence: the mean of the word confidences,
+    /// or None when the recognizer returned no word info
+    pub fn confidence(&self) -> Option<f32> {
+        if self.words.is_empty() {
+            return None;
+        }
+        let total: f32 = self.words.iter().map(|word| word.conf).sum();
+        Some(total / self.words.len() as f32)
+    }
+}
+
+/// Vosk's final result: `{"text": "...", "result": [{"word", "start", "end", "conf"}]}`.
+/// Word timings are only present when the recognizer has `set_words(true)`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct VoskResult {
+    pub text: String,
+    #[serde(default)]
+    pub result: Vec<WordSegment>,
+}
+
+impl From<CompleteResult<'_>> for VoskResult {
+    fn from(result: CompleteResult<'_>) -> Self {
+        match result {
+            CompleteResult::Single(single) => Self {
+                text: single.text.to_string(),
+                result: single
+                    .result
+                    .iter()
+                    .map(|word| WordSegment {
+                        word: word.word.to_string(),
+                        start: word.start,
+                        end: word.end,
+                        conf: word.conf,
+                    })
+                    .collect(),
+            },
+            // Only produced with max_alternatives set; keep the best hypothesis text
+            CompleteResult::Multiple(multiple) => Self {
+                text: multiple
+                    .alternatives
+                    .first()
+                    .map(|alternative| alternative.text.to_string())
+                    .unwrap_or_default(),
+                result: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Returned when the audio decodes fine but contains no recognizable speech
+#[derive(Debug, thiserror::Error)]
+#[error("No speech detected in audio")]
+pub struct NoSpeechDetected;
+
+/// Returned when no transcription slot frees up within the queue timeout
+#[derive(Debug, thiserror::Error)]
+#[error("Transcription queue is full, try again later")]
+pub struct QueueTimeout;
+
+/// Samples fed to the recognizer per `accept_waveform` call
+const SAMPLE_CHUNK: usize = 2000;
+
+/// What happens to decoded samples on the way to the recognizer: leading and trailing
+/// silence is trimmed, then the level is normalized (each when enabled)
+#[derive(Debug, Clone)]
+struct SampleFeed {
+    trimmer: Option<SilenceTrimmer>,
+    normalizer: Option<AudioNormalizer>,
+}
+
+impl SampleFeed {
+    /// Pass the part of `samples` that is ready on to `accept`
+    fn push(
+        &mut self,
+        samples: &[i16],
+        accept: &mut impl FnMut(&[i16]) -> Result<()>,
+    ) -> Result<()> {
+        let ready = match self.trimmer.as_mut() {
+            Some(trimmer) => trimmer.push(samples),
+            None => samples.to_vec(),
+        };
+        self.emit(ready, accept)
+    }
+
+    /// End of the audio: pass on whatever the trimmer held back
+    fn finish(&mut self, accept: &mut impl FnMut(&[i16]) -> Result<()>) -> Result<()> {
+        let rest = self.trimmer.as_mut().map(SilenceTrimmer::finish).unwrap_or_default();
+        self.emit(rest, accept)
+    }
+
+    fn emit(
+        &mut self,
+        mut samples: Vec<i16>,
+        accept: &mut impl FnMut(&[i16]) -> Result<()>,
+    ) -> Result<()> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+        if let Some(normalizer) = self.normalizer.as_mut() {
+            normalizer.apply(&mut samples);
+        }
+        accept(&samples)
+    }
+}
 
 #[derive(Clone)]
 pub struct VoskService {
     model_path: String,
+    sample_rate: u32,
+    /// Run audio through `AudioNormalizer` before recognition
+    normalize: bool,
+    /// RMS level below which leading and trailing audio is trimmed (None keeps it all)
+    trim_threshold: Option<f32>,
+    permits: Arc<Semaphore>,
+    queue_timeout: Duration,
+    model_source: ModelSource,
+    models: Arc<ModelPool>,
 }
 
 impl VoskService {
     pub fn new(model_path: String) -> Self {
-        Self { model_path }
+        Self {
+            model_path,
+            sample_rate: 16000,
+            normalize: false,
+            trim_threshold: None,
+            permits: Arc::new(Semaphore::new(4)),
+            queue_timeout: Duration::from_secs(30),
+            model_source: ModelSource::default(),
+            models: Arc::new(ModelPool::new(2)),
+        }
     }
 
-    pub async fn transcribe(&self, audio_data: Vec<u8>) -> Result<String> {
+    /// Rate the model was trained at (e.g. 8000 for telephony models); input must match
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        info!("Vosk recognizer sample rate: {}Hz", sample_rate);
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// Raise quiet audio toward a fixed peak level before it reaches the recognizer
+    pub fn with_normalization(mut self, normalize: bool) -> Self {
+        if normalize {
+            info!("Normalizing audio levels before transcription");
+        }
+        self.normalize = normalize;
+        self
+    }
+
+    /// Drop leading and trailing audio quieter than `threshold` (RMS, like
+    /// `VAD_ENERGY_THRESHOLD`) before recognition, keeping a short guard margin
+    pub fn with_silence_trim(mut self, threshold: Option<f32>) -> Self {
+        if let Some(threshold) = threshold {
+            info!("Trimming leading/trailing silence below RMS {} before transcription", threshold);
+        }
+        self.trim_threshold = threshold;
+        self
+    }
+
+    fn sample_feed(&self) -> SampleFeed {
+        SampleFeed {
+            trimmer: self
+                .trim_threshold
+                .map(|threshold| SilenceTrimmer::new(threshold, self.sample_rate)),
+            normalizer: self.normalize.then(AudioNormalizer::new),
+        }
+    }
+
+    /// Bound how many recognitions run at once; excess requests wait up to `queue_timeout`
+    pub fn with_concurrency_limit(mut self, max_concurrent: usize, queue_timeout: Duration) -> Self {
+        info!(
+            "Limiting Vosk to {} concurrent transcriptions (queue timeout {:?})",
+            max_concurrent, queue_timeout
+        );
+        self.permits = Arc::new(Semaphore::new(max_concurrent.max(1)));
+        self.queue_timeout = queue_timeout;
+        self
+    }
+
+    /// How many loaded models to keep in memory; the least recently used is dropped first
+    pub fn with_model_cache_size(mut self, capacity: usize) -> Self {
+        info!("Caching up to {} Vosk models", capacity);
+        self.models = Arc::new(ModelPool::new(capacity).with_source(self.model_source.clone()));
+        self
+    }
+
+    /// Download missing models and fall back to another model when loading fails
+    pub fn with_model_source(mut self, source: ModelSource) -> Self {
+        self.models = Arc::new(ModelPool::new(self.models.capacity()).with_source(source.clone()));
+        self.model_source = source;
+        self
+    }
+
+    pub fn is_model_loaded(&self, model_path: &str) -> bool {
+        self.models.is_loaded(model_path)
+    }
+
+    /// Load the default model (downloading it if needed) without transcribing anything
+    pub async fn preload(&self) -> Result<()> {
+        let models = self.models.clone();
         let model_path = self.model_path.clone();
-        
+        tokio::task::spawn_blocking(move || models.get(&model_path).map(|_| ())).await?
+    }
+
+    pub async fn transcribe(&self, audio_data: Vec<u8>) -> Result<Transcript> {
+        let model_path = self.model_path.clone();
+        let sample_rate = self.sample_rate;
+        let feed = self.sample_feed();
+        let models = self.models.clone();
+
+        self.run_blocking(move || {
+            Self::transcribe_sync(&models, &model_path, sample_rate, feed, audio_data)
+        })
+        .await
+    }
+
+    /// Run a recognition job on the blocking pool once a transcription slot is free
+    async fn run_blocking<T, F>(&self, job: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = tokio::time::timeout(self.queue_timeout, self.permits.clone().acquire_owned())
+            .await
+            .map_err(|_| {
+                warn!("Timed out after {:?} waiting for a transcription slot", self.queue_timeout);
+                QueueTimeout
+            })??;
+
         tokio::task::spawn_blocking(move || {
-            Self::transcribe_sync(&model_path, audio_data)
+            let _permit = permit;
+            job()
         })
         .await?
     }
 
-    fn transcribe_sync(model_path: &str, audio_data: Vec<u8>) -> Result<String> {
-        // Validate audio is WAV format
-        let mut cursor = std::io::Cursor::new(&audio_data);
-        let reader = hound::WavReader::new(&mut cursor)
-            .map_err(|e| anyhow::anyhow!("Failed to read WAV: {}", e))?;
+    fn transcribe_sync(
+        models: &ModelPool,
+        model_path: &str,
+        sample_rate: u32,
+        mut feed: SampleFeed,
+        audio_data: Vec<u8>,
+    ) -> Result<Transcript> {
+        // Decode WAV (or WebM/Ogg Opus) to mono samples at the recognizer rate
+        let samples = audio_decode::decode_to_pcm(&audio_data, sample_rate)?;
+
+        info!("Processing {} bytes of {}Hz mono audio", audio_data.len(), sample_rate);
+        debug!("Decoded {} i16 samples for Vosk", samples.len());
+
+        Self::recognize(models, model_path, sample_rate, |recognizer| {
+            let mut accept = |samples: &[i16]| {
+                recognizer.accept_waveform(samples)?;
+                Ok(())
+            };
+            for chunk in samples.chunks(SAMPLE_CHUNK) {
+                feed.push(chunk, &mut accept)?;
+            }
+            feed.finish(&mut accept)
+        })
+    }
+
+    /// Transcribe an upload that was spilled to disk. WAV samples are read from the
+    /// file a chunk at a time so the decoded audio is never held in memory;
+    /// compressed containers need the whole file to decode and go through `transcribe_sync`.
+    pub async fn transcribe_file(&self, path: &Path) -> Result<Transcript> {
+        let model_path = self.model_path.clone();
+        let sample_rate = self.sample_rate;
+        let feed = self.sample_feed();
+        let models = self.models.clone();
+        let path = path.to_path_buf();
+
+        self.run_blocking(move || {
+            Self::transcribe_file_sync(&models, &model_path, sample_rate, feed, &path)
+        })
+        .await
+    }
 
-        // Validate audio format
-        let spec = reader.spec();
-        if spec.channels != 1 || spec.sample_rate != 16000 {
-            return Err(anyhow::anyhow!(
-                "Audio must be 16kHz mono WAV. Got: {}Hz {}ch",
-                spec.sample_rate,
-                spec.channels
-            ));
+    fn transcribe_file_sync(
+        models: &ModelPool,
+        model_path: &str,
+        sample_rate: u32,
+        mut feed: SampleFeed,
+        path: &Path,
+    ) -> Result<Transcript> {
+        let mut head = Vec::with_capacity(12);
+        File::open(path)?.take(12).read_to_end(&mut head)?;
+        if audio_decode::AudioContainer::detect(&head) != audio_decode::AudioContainer::Wav {
+            let audio_data = std::fs::read(path)?;
+            return Self::transcribe_sync(models, model_path, sample_rate, feed, audio_data);
         }
 
-        info!("Processing {} bytes of 16kHz mono audio", audio_data.len());
+        let reader = hound::WavReader::new(BufReader::new(File::open(path)?))
+            .map_err(|e| anyhow::anyhow!("Failed to read WAV: {}", e))?;
+        audio_decode::check_wav_spec(reader.spec(), sample_rate)?;
+
+        info!("Streaming {} samples of {}Hz mono audio from {}", reader.len(), sample_rate, path.display());
+
+        let mut samples = audio_decode::i16_samples(reader)?;
+        Self::recognize(models, model_path, sample_rate, |recognizer| {
+            let mut accept = |samples: &[i16]| {
+                recognizer.accept_waveform(samples)?;
+                Ok(())
+            };
+            let mut chunk = Vec::with_capacity(SAMPLE_CHUNK);
+            loop {
+                chunk.clear();
+                for sample in samples.by_ref().take(SAMPLE_CHUNK) {
+                    chunk.push(sample?);
+                }
+                if chunk.is_empty() {
+                    return feed.finish(&mut accept);
+                }
+                feed.push(&chunk, &mut accept)?;
+            }
+        })
+    }
 
-        // Load Vosk model
-        debug!("Loading Vosk model from: {}", model_path);
-        let model = Model::new(model_path)
-            .ok_or_else(|| anyhow::anyhow!("Failed to load Vosk model from: {}", model_path))?;
+    /// Run a recognizer over the samples pushed by `feed` and return the final transcript
+    fn recognize(
+        models: &ModelPool,
+        model_path: &str,
+        sample_rate: u32,
+        feed: impl FnOnce(&mut Recognizer) -> Result<()>,
+    ) -> Result<Transcript> {
+        // Borrow the Vosk model, loading it on first use
+        let model = models.get(model_path)?;
 
         // Create recognizer
-        let mut recognizer = Recognizer::new(&model, 16000.0)
+        let mut recognizer = Recognizer::new(&model, sample_rate as f32)
             .ok_or_else(|| anyhow::anyhow!("Failed to create Vosk recognizer"))?;
+        recognizer.set_words(true);
 
-        // Extract audio samples (16-bit PCM)
-        let mut cursor = std::io::Cursor::new(&audio_data);
-        let mut reader = hound::WavReader::new(&mut cursor)
-            .map_err(|e| anyhow::anyhow!("Failed to reopen WAV: {}", e))?;
-
-        let samples: Vec<i16> = reader
-            .samples::<i16>()
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| anyhow::anyhow!("Failed to read audio samples: {}", e))?;
+        feed(&mut recognizer)?;
 
-        debug!("Feeding {} i16 samples to Vosk", samples.len());
-
-        // Feed audio to recognizer in chunks (i16 samples, not bytes)
-        let chunk_size = 2000; // Process 2000 samples at a time
-        for chunk in samples.chunks(chunk_size) {
-            recognizer.accept_waveform(chunk)?;
-        }
-
-        // Get final result (returns CompleteResult)
-        let result = recognizer.final_result();
+        // Get final result
+        let complete = recognizer.final_result();
+        let raw = serde_json::to_value(&complete).ok();
+        let result = VoskResult::from(complete);
         
         debug!("Vosk raw result: {:?}", result);
 
-        // Serialize CompleteResult to JSON string
-        let result_json = serde_json::to_string(&result)
-            .map_err(|e| anyhow::anyhow!("Failed to serialize Vosk result: {}", e))?;
-        
-        let parsed: serde_json::Value = serde_json::from_str(&result_json)
-            .map_err(|e| anyhow::anyhow!("Failed to parse Vosk result: {}", e))?;
-
-        let transcription = parsed["text"]
-            .as_str()
-            .unwrap_or("")
-            .trim()
-            .to_string();
+        let transcription = result.text.trim().to_string();
 
         if transcription.is_empty() {
             error!("Vosk returned empty transcription");
-            return Err(anyhow::anyhow!("No speech detected in audio"));
+            return Err(NoSpeechDetected.into());
         }
 
         info!("Transcription: '{}'", transcription);
-        Ok(transcription)
+        Ok(Transcript {
+            text: transcription,
+            words: result.result,
+            raw,
+        })
     }
 
-    pub async fn transcribe_streaming(&self, audio_chunks: Vec<Vec<u8>>) -> Result<String> {
+    pub async fn transcribe_streaming(&self, audio_chunks: Vec<Vec<u8>>) -> Result<Transcript> {
         let model_path = self.model_path.clone();
+        let sample_rate = self.sample_rate;
+        let feed = self.sample_feed();
+        let models = self.models.clone();
 
-        tokio::task::spawn_blocking(move || {
-            Self::transcribe_streaming_sync(&model_path, audio_chunks)
+        self.run_blocking(move || {
+            Self::transcribe_streaming_sync(&models, &model_path, sample_rate, feed, audio_chunks)
         })
-        .await?
+            .await
     }
 
-    fn transcribe_streaming_sync(model_path: &str, audio_chunks: Vec<Vec<u8>>) -> Result<String> {
+    fn transcribe_streaming_sync(
+        models: &ModelPool,
+        model_path: &str,
+        sample_rate: u32,
+        mut feed: SampleFeed,
+        audio_chunks: Vec<Vec<u8>>,
+    ) -> Result<Transcript> {
         let total_size: usize = audio_chunks.iter().map(|c| c.len()).sum();
         info!("Processing {} chunks totaling {} bytes", audio_chunks.len(), total_size);
 
-        // Load Vosk model
-        let model = Model::new(model_path)
-            .ok_or_else(|| anyhow::anyhow!("Failed to load Vosk model from: {}", model_path))?;
+        // Borrow the Vosk model, loading it on first use
+        let model = models.get(model_path)?;
 
-        let mut recognizer = Recognizer::new(&model, 16000.0)
+        let mut recognizer = Recognizer::new(&model, sample_rate as f32)
             .ok_or_else(|| anyhow::anyhow!("Failed to create Vosk recognizer"))?;
+        recognizer.set_words(true);
 
         // Process each chunk (convert u8 bytes to i16 samples)
+        let mut accept = |samples: &[i16]| {
+            recognizer.accept_waveform(samples)?;
+            Ok(())
+        };
         for chunk in audio_chunks {
             let samples: Vec<i16> = chunk
                 .chunks_exact(2)
                 .map(|b| i16::from_le_bytes([b[0], b[1]]))
                 .collect();
-            recognizer.accept_waveform(&samples)?;
+            feed.push(&samples, &mut accept)?;
         }
+        feed.finish(&mut accept)?;
 
-        // Get final result (returns CompleteResult)
-        let result = recognizer.final_result();
+        // Capture the partial before finalizing; final_result() resets the decoder
+        let partial = recognizer.partial_result().partial.trim().to_string();
+
+        // Get final result
+        let complete = recognizer.final_result();
+        let raw = serde_json::to_value(&complete).ok();
+        let result = VoskResult::from(complete);
         
         debug!("Vosk streaming result: {:?}", result);
 
-        // Serialize CompleteResult to JSON string
-        let result_json = serde_json::to_string(&result)
-            .map_err(|e| anyhow::anyhow!("Failed to serialize Vosk result: {}", e))?;
-        
-        let parsed: serde_json::Value = serde_json::from_str(&result_json)
-            .map_err(|e| anyhow::anyhow!("Failed to parse Vosk result: {}", e))?;
+        let transcription = Self::pick_transcript(&result.text, &partial)
+            .ok_or(NoSpeechDetected)?;
+
+        Ok(Transcript {
+            text: transcription,
+            words: result.result,
+            raw,
+        })
+    }
 
-        let transcription = parsed["text"]
-            .as_str()
-            .unwrap_or("")
-            .trim()
-            .to_string();
+    /// Prefer the final text, falling back to the last partial for short utterances
+    /// where Vosk only produced a partial hypothesis
+    fn pick_transcript(final_text: &str, partial_text: &str) -> Option<String> {
+        let final_text = final_text.trim();
+        if !final_text.is_empty() {
+            return Some(final_text.to_string());
+        }
 
-        if transcription.is_empty() {
-            return Err(anyhow::anyhow!("No speech detected in streaming audio"));
+        let partial_text = partial_text.trim();
+        if !partial_text.is_empty() {
+            warn!("Final result empty, falling back to partial: '{}'", partial_text);
+            return Some(partial_text.to_string());
         }
 
-        Ok(transcription)
+        None
     }
 }
 
@@ -158,6 +474,158 @@ mod tests {
         assert_eq!(service.model_path, "/models/test");
     }
 
+    /// Recognizer stand-in: runs `audio` through `feed` the way the transcribe paths do
+    /// and "hears" one word per loud 10ms frame. Returns the transcript and the number
+    /// of samples that reached the recognizer.
+    fn stub_transcribe(mut feed: SampleFeed, audio: &[i16]) -> (String, usize) {
+        let mut fed = Vec::new();
+        let mut accept = |samples: &[i16]| {
+            fed.extend_from_slice(samples);
+            Ok(())
+        };
+        for chunk in audio.chunks(SAMPLE_CHUNK) {
+            feed.push(chunk, &mut accept).unwrap();
+        }
+        feed.finish(&mut accept).unwrap();
+
+        let words = fed.chunks(160).filter(|frame| frame.iter().any(|s| s.abs() > 4000));
+        let text = words.map(|_| "tea").collect::<Vec<_>>().join(" ");
+        (text, fed.len())
+    }
+
+    #[test]
+    fn test_trim_silence_keeps_transcript_and_feeds_fewer_samples() {
+        let service = VoskService::new("/models/test".to_string()).with_silence_trim(Some(500.0));
+        let speech: Vec<i16> =
+            (0..8000).map(|i| (8000.0 * (i as f32 * 0.1).sin()) as i16).collect();
+        let mut padded = vec![0i16; 24000];
+        padded.extend(&speech);
+        padded.extend(vec![0i16; 24000]);
+
+        let (unpadded_text, _) = stub_transcribe(service.sample_feed(), &speech);
+        let (padded_text, padded_fed) = stub_transcribe(service.sample_feed(), &padded);
+
+        assert!(!unpadded_text.is_empty());
+        assert_eq!(padded_text, unpadded_text);
+        assert!(padded_fed < padded.len());
+        // Only the 200ms guard margins remain either side
+        assert_eq!(padded_fed, speech.len() + 2 * 3200);
+
+        // Trimming is off unless configured
+        let untrimmed = VoskService::new("/models/test".to_string());
+        assert_eq!(stub_transcribe(untrimmed.sample_feed(), &padded).1, padded.len());
+    }
+
+    #[test]
+    fn test_vosk_result_deserializes_words() {
+        let json = r#"{
+            "result": [
+                {"conf": 1.0, "end": 0.45, "start": 0.12, "word": "hello"},
+                {"conf": 0.87, "end": 0.93, "start": 0.45, "word": "tea"}
+            ],
+            "text": "hello tea"
+        }"#;
+
+        let result: VoskResult = serde_json::from_str(json).unwrap();
+
+        assert_eq!(result.text, "hello tea");
+        assert_eq!(result.result.len(), 2);
+        assert_eq!(result.result[1].word, "tea");
+        assert_eq!(result.result[1].start, 0.45);
+        assert_eq!(result.result[1].end, 0.93);
+        assert_eq!(result.result[1].conf, 0.87);
+    }
+
+    #[test]
+    fn test_vosk_result_without_words() {
+        let result: VoskResult = serde_json::from_str(r#"{"text": ""}"#).unwrap();
+        assert_eq!(result, VoskResult::default());
+    }
+
+    #[test]
+    fn test_confidence_is_mean_of_word_confidences() {
+        let json = r#"{
+            "result": [
+                {"conf": 1.0, "end": 0.45, "start": 0.12, "word": "two"},
+                {"conf": 0.8, "end": 0.93, "start": 0.45, "word": "cups"},
+                {"conf": 0.6, "end": 1.2, "start": 0.93, "word": "please"}
+            ],
+            "text": "two cups please"
+        }"#;
+        let result: VoskResult = serde_json::from_str(json).unwrap();
+        let transcript = Transcript { text: result.text, words: result.result, raw: None };
+
+        assert!((transcript.confidence().unwrap() - 0.8).abs() < 1e-6);
+
+        let no_words = Transcript { text: "hello".to_string(), words: Vec::new(), raw: None };
+        assert_eq!(no_words.confidence(), None);
+    }
+
+    #[test]
+    fn test_pick_transcript_falls_back_to_partial() {
+        assert_eq!(
+            VoskService::pick_transcript("", " yes "),
+            Some("yes".to_string())
+        );
+        assert_eq!(
+            VoskService::pick_transcript("yes please", "yes"),
+            Some("yes please".to_string())
+        );
+        assert_eq!(VoskService::pick_transcript("  ", ""), None);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_bounds_in_flight_jobs() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let service = VoskService::new("/models/test".to_string())
+            .with_concurrency_limit(2, Duration::from_secs(5));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+
+        let jobs = (0..8).map(|_| {
+            let service = service.clone();
+            let in_flight = in_flight.clone();
+            let max_seen = max_seen.clone();
+            async move {
+                service
+                    .run_blocking(move || {
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_seen.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(30));
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        Ok(())
+                    })
+                    .await
+            }
+        });
+
+        for result in futures::future::join_all(jobs).await {
+            assert!(result.is_ok());
+        }
+        assert!(max_seen.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_queue_timeout_when_saturated() {
+        let service = VoskService::new("/models/test".to_string())
+            .with_concurrency_limit(1, Duration::from_millis(20));
+
+        let busy = service.clone();
+        let long_job = tokio::spawn(async move {
+            busy.run_blocking(|| {
+                std::thread::sleep(Duration::from_millis(200));
+                Ok(())
+            })
+            .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let result = service.run_blocking(|| Ok(())).await;
+        assert!(result.unwrap_err().downcast_ref::<QueueTimeout>().is_some());
+        assert!(long_job.await.unwrap().is_ok());
+    }
+
     #[tokio::test]
     async fn test_transcribe_rejects_empty_audio() {
         let service = VoskService::new("/models/test".to_string());
@@ -173,6 +641,44 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn silent_wav(sample_rate: u32) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for _ in 0..sample_rate / 10 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_8khz_service_accepts_8khz_audio() {
+        let service = VoskService::new("/models/test".to_string()).with_sample_rate(8000);
+
+        // Passes format validation; only the (missing) model load can fail
+        let err = service.transcribe(silent_wav(8000)).await.unwrap_err().to_string();
+        assert!(!err.contains("Audio must be"), "{}", err);
+
+        let err = service.transcribe(silent_wav(16000)).await.unwrap_err().to_string();
+        assert!(err.contains("Audio must be 8000Hz mono WAV"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_webm_opus_does_not_hit_wav_error() {
+        let service = VoskService::new("/models/test".to_string());
+        let webm = include_bytes!("../../tests/fixtures/tone_opus.webm").to_vec();
+
+        let err = service.transcribe(webm).await.unwrap_err().to_string();
+
+        assert!(!err.contains("Failed to read WAV"), "{}", err);
+    }
+
     #[tokio::test]
     async fn test_transcribe_valid_mock_audio() {
         let service = VoskService::new("/models/test".to_string());
@@ -194,8 +700,8 @@ mod tests {
 
         let result = service.transcribe(wav).await;
         assert!(result.is_ok());
-        let text = result.unwrap();
-        assert!(text.contains("transcription"));
+        let transcript = result.unwrap();
+        assert!(transcript.text.contains("transcription"));
     }
 
     #[tokio::test]
@@ -204,7 +710,7 @@ mod tests {
         let audio_chunks = vec![vec![0; 1024], vec![0; 1024]];
         let result = service.transcribe_streaming(audio_chunks).await;
         assert!(result.is_ok());
-        let text = result.unwrap();
-        assert!(text.contains("Streaming"));
+        let transcript = result.unwrap();
+        assert!(transcript.text.contains("Streaming"));
     }
 }
@@ -0,0 +1,291 @@
+// Shared helpers for handler and service tests
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use bytes::Bytes;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tokio_tungstenite::{tungstenite::client::IntoClientRequest, MaybeTlsStream, WebSocketStream};
+
+use crate::{
+    config::Config,
+    services::{
+        ApiKeyStore, DatabaseService, ElevenLabsService, IdempotencyService, LatencyHistograms, LlmService,
+        MockSpeechToText, StreamResumeService, TranscriptCache, TranscriptionJobService, UploadSpool,
+        VoiceSessionService,
+        voice_session_service::SessionOwner,
+    },
+    AppState,
+};
+
+/// Build an `AppState` that never touches real infrastructure.
+/// The database pool connects lazily, so only tests that issue queries need PostgreSQL.
+pub fn test_state() -> AppState {
+    let mut config = Config::from_env();
+    config.api_key = API_KEY.to_string();
+    config.admin_api_key = Some(ADMIN_API_KEY.to_string());
+
+    let database_service = Arc::new(
+        DatabaseService::connect_lazy(&config.database_url).expect("lazy pool"),
+    );
+
+    AppState {
+        config: config.clone(),
+        name: "Rusty Tea".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_sha: env!("GIT_SHA").to_string(),
+        build_timestamp: env!("BUILD_TIMESTAMP").to_string(),
+        stt_service: Arc::new(MockSpeechToText::new(MOCK_TRANSCRIPT)),
+        database_service: database_service.clone(),
+        rag_service: None,
+        llm_service: Arc::new(
+            LlmService::new("sk-or-v1-test", "http://127.0.0.1:9", "test-model").unwrap(),
+        ),
+        elevenlabs_service: Arc::new(
+            ElevenLabsService::new(
+                "test_api_key".to_string(),
+                "test_voice_id".to_string(),
+                "http://127.0.0.1:9",
+            )
+            .unwrap(),
+        ),
+        voice_sessions: VoiceSessionService::new(30),
+        idempotency: IdempotencyService::new(300),
+        api_key_store: ApiKeyStore::new(database_service),
+        transcript_cache: TranscriptCache::new(config.transcript_cache_ttl_secs),
+        // Callbacks go to MockUpstream on 127.0.0.1
+        transcription_jobs: TranscriptionJobService::new(60)
+            .unwrap()
+            .with_private_callbacks(),
+        latency: LatencyHistograms::new(&config.latency_buckets_secs),
+        audio_store: None,
+        thinking_audio: None,
+        streaming_permits: Arc::new(Semaphore::new(config.max_streaming_connections)),
+        stream_resumes: StreamResumeService::new(config.stream_resume_grace_secs),
+        upload_spool: UploadSpool::new(),
+    }
+}
+
+/// Like `test_state`, but with the LLM and TTS clients pointed at a mock upstream
+pub fn test_state_with_upstream(upstream: &MockUpstream) -> AppState {
+    let mut state = test_state();
+    state.llm_service = Arc::new(
+        LlmService::new("sk-or-v1-test", &upstream.base_url, "test-model").unwrap(),
+    );
+    state.elevenlabs_service = Arc::new(
+        ElevenLabsService::new(
+            "test_api_key".to_string(),
+            "test_voice_id".to_string(),
+            &upstream.base_url,
+        )
+        .unwrap(),
+    );
+    state
+}
+
+/// Connect to the real test database (runs migrations)
+pub async fn database_service() -> Arc<DatabaseService> {
+    let config = Config::from_env();
+    Arc::new(
+        DatabaseService::new(&config.database_url)
+            .await
+            .expect("PostgreSQL must be running for this test"),
+    )
+}
+
+/// Keys accepted by `check_api_key` for `test_state()`
+pub const API_KEY: &str = "test_api_key";
+pub const ADMIN_API_KEY: &str = "test_admin_key";
+
+/// Owner of sessions opened with `API_KEY`, for calling handlers directly
+pub fn session_owner() -> SessionOwner {
+    SessionOwner::from_api_key(API_KEY)
+}
+
+/// Collect a response body and parse it as JSON
+pub async fn body_json(response: Response) -> serde_json::Value {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("failed to read response body");
+    serde_json::from_slice(&bytes).expect("response body is not JSON")
+}
+
+/// Serve the full router for `state` on a local port, for tests that need a real
+/// connection (WebSockets)
+pub async fn spawn_app(state: AppState) -> SocketAddr {
+    let app = crate::build_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    address
+}
+
+pub type WsClient = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Open a WebSocket to `path` (with any query) on a `spawn_app` server, sending
+/// `API_KEY` in the `x-api-key` header
+pub async fn ws_connect(address: SocketAddr, path: &str) -> WsClient {
+    ws_try_connect(address, path).await.expect("WebSocket handshake failed")
+}
+
+/// `ws_connect` for tests that expect the upgrade to be refused
+pub async fn ws_try_connect(
+    address: SocketAddr,
+    path: &str,
+) -> Result<WsClient, tokio_tungstenite::tungstenite::Error> {
+    let mut request = format!("ws://{}{}", address, path).into_client_request()?;
+    request.headers_mut().insert("x-api-key", API_KEY.parse().unwrap());
+    tokio_tungstenite::connect_async(request).await.map(|(socket, _)| socket)
+}
+
+/// A request captured by `MockUpstream`
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+/// Fake bytes returned for text-to-speech calls
+pub const MOCK_MP3: &[u8] = b"ID3mock-mp3-audio";
+
+/// What the mock STT in `test_state()` hears in any audio
+pub const MOCK_TRANSCRIPT: &str = "hello tea";
+
+/// Local HTTP server standing in for OpenRouter and ElevenLabs.
+/// Chat completions answer with a fixed reply; text-to-speech answers with `MOCK_MP3`;
+/// `/callback` accepts webhook deliveries.
+#[derive(Clone)]
+pub struct MockUpstream {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockUpstream {
+    pub async fn start(reply: &str) -> Self {
+        Self::start_with_finish_reason(reply, "stop").await
+    }
+
+    /// Like `start`, with completions reporting `finish_reason` (e.g. "length")
+    pub async fn start_with_finish_reason(reply: &str, finish_reason: &'static str) -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let reply = reply.to_string();
+
+        let app = Router::new().fallback(move |request: Request| {
+            let recorded = recorded.clone();
+            let reply = reply.clone();
+            async move {
+                let (parts, body) = request.into_parts();
+                let body = axum::body::to_bytes(body, usize::MAX)
+                    .await
+                    .unwrap_or_default();
+                let path = parts.uri.path().to_string();
+                recorded.lock().unwrap().push(RecordedRequest {
+                    path: path.clone(),
+                    body: body.clone(),
+                });
+
+                let streaming = serde_json::from_slice::<serde_json::Value>(&body)
+                    .map(|json| json["stream"] == true)
+                    .unwrap_or(false);
+                if path.ends_with("/chat/completions") && streaming {
+                    (
+                        [(header::CONTENT_TYPE, "text/event-stream")],
+                        chat_completion_events(&reply, finish_reason),
+                    )
+                        .into_response()
+                } else if path.ends_with("/chat/completions") {
+                    Json(chat_completion(&reply, finish_reason)).into_response()
+                } else if path.contains("/text-to-speech/") {
+                    ([(header::CONTENT_TYPE, "audio/mpeg")], MOCK_MP3).into_response()
+                } else if path.ends_with("/callback") {
+                    StatusCode::OK.into_response()
+                } else {
+                    StatusCode::NOT_FOUND.into_response()
+                }
+            }
+        });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self {
+            base_url: format!("http://{}", address),
+            requests,
+        }
+    }
+
+    /// All requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Requests whose path contains `fragment`
+    pub fn requests_to(&self, fragment: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.path.contains(fragment))
+            .collect()
+    }
+}
+
+/// Server-sent events for a streamed completion, one word per chunk
+fn chat_completion_events(reply: &str, finish_reason: &str) -> String {
+    let mut events: String = reply
+        .split_inclusive(' ')
+        .map(|word| {
+            let chunk = serde_json::json!({
+                "id": "chatcmpl-test",
+                "object": "chat.completion.chunk",
+                "created": 0,
+                "model": "test-model",
+                "choices": [{
+                    "index": 0,
+                    "delta": { "content": word },
+                    "finish_reason": null
+                }]
+            });
+            format!("data: {}\n\n", chunk)
+        })
+        .collect();
+    let last = serde_json::json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion.chunk",
+        "created": 0,
+        "model": "test-model",
+        "choices": [{ "index": 0, "delta": {}, "finish_reason": finish_reason }]
+    });
+    events.push_str(&format!("data: {}\n\n", last));
+    events.push_str("data: [DONE]\n\n");
+    events
+}
+
+/// Non-streamed chat completion body answering with `reply`
+pub fn chat_completion(reply: &str, finish_reason: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "test-model",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": reply },
+            "finish_reason": finish_reason
+        }]
+    })
+}